@@ -4,12 +4,14 @@ use bevy::ecs::system::{SystemMeta, SystemParam};
 use bevy::ecs::world::{unsafe_world_cell::UnsafeWorldCell,Command, CommandQueue};
 use bevy::ui::UiSystem;
 use bevy::utils::{HashMap, HashSet};
-use smallvec::SmallVec;
+use smallvec::{smallvec, SmallVec};
 use std::ops::Deref;
 use std::ops::DerefMut;
 
 use crate::eml::Eml;
-use crate::ess::{ElementsBranch, PropertyValue, Selector};
+use crate::ess::{
+    parse_selector_list, ElementsBranch, ElementsTree, PropertyValue, RuleIndex, Selector,
+};
 use crate::tags;
 use crate::tags::*;
 use bevy::prelude::*;
@@ -85,7 +87,11 @@ impl Default for ImageElementBundle {
     }
 }
 
-#[derive(Default)]
+/// Inline elements (`<strong>`, `<em>`, `<span>`, `<color>`) don't break
+/// the text: the text of the element containing only inline elements and
+/// text nodes is rendered as a single [`Text`] with a section per text node,
+/// see [`InlineText`](crate::ess::InlineText).
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DisplayElement {
     #[default]
     Block,
@@ -102,6 +108,8 @@ pub struct Element {
     pub classes: HashSet<Tag>,
     pub state: HashSet<Tag>,
     pub styles: HashMap<Tag, PropertyValue>,
+    /// Values matched by attribute selectors: `[mode=toggle]`, `[disabled]`
+    pub attributes: HashMap<Tag, String>,
 }
 
 impl Element {
@@ -158,6 +166,21 @@ pub struct ChildrenQuery {
     children: &'static Children,
 }
 
+impl<'w, 's> ElementsTree for Elements<'w, 's> {
+    fn element(&self, entity: Entity) -> Option<&Element> {
+        self.elements.get(entity).ok().map(|e| e.element)
+    }
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.parents.get(entity).ok().map(|p| p.get())
+    }
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.children
+            .get(entity)
+            .map(|ch| &**ch.children)
+            .unwrap_or_default()
+    }
+}
+
 // #[derive(Deref, DerefMut)]
 // struct EntitiesHashSet(HashSet<Entity>);
 // impl Default for EntitiesHashSet {
@@ -172,9 +195,11 @@ pub struct Elements<'w, 's> {
     pub(crate) commands: ElementCommands<'w, 's>,
     pub(crate) elements: Query<'w, 's, ElementsQuery, ()>,
     pub(crate) children: Query<'w, 's, ChildrenQuery, ()>,
+    pub(crate) parents: Query<'w, 's, &'static Parent>,
     pub(crate) id_index: Res<'w, ElementIdIndex>,
     states: Local<'s, HashMap<Entity, HashMap<Tag, bool>>>,
     classes: Local<'s, HashMap<Entity, HashSet<Tag>>>,
+    attributes: Local<'s, HashMap<Entity, HashMap<Tag, Option<String>>>>,
 }
 
 impl<'w, 's> Elements<'w, 's> {
@@ -209,59 +234,91 @@ impl<'w, 's> Elements<'w, 's> {
     /// example and [Selector](https://github.com/jkb0o/belly#selectors)
     /// chapter in readme.
     pub fn select<'e>(&'e mut self, query: &str) -> SelectedElements<'w, 's, 'e> {
-        let selector: Selector = query.into();
+        let selectors = match parse_selector_list(query) {
+            Ok(selectors) => selectors,
+            Err(e) => {
+                error!("Failed to parse selector '{query}': {e}");
+                Default::default()
+            }
+        };
+        let entities = self.select_entities(&selectors);
+        SelectedElements {
+            elements: self,
+            entities,
+        }
+    }
+
+    fn select_entities(&self, selectors: &[Selector]) -> Vec<Entity> {
         let mut result = vec![];
-        if selector.is_empty() {
-            return SelectedElements {
-                elements: self,
-                entities: result,
-            };
+        if selectors.is_empty() {
+            return result;
         }
         let mut branch = vec![];
-        if let Some(id) = selector.get_root_id() {
+        let root_id = match selectors {
+            [selector] => selector.get_root_id(),
+            _ => None,
+        };
+        if let Some(id) = root_id {
             // indexed-by-id branch lookup
             if let Some(entity) = self.id_index.get(&id) {
-                self.select_branch(*entity, &mut branch, &selector, &mut result);
+                self.select_branch(*entity, None, &mut branch, selectors, &mut result);
             } else {
                 warn!("Element #{id} not indexed, Elements.select() will return empty result");
             }
         } else {
             for root in self.roots.iter() {
                 // branch.append(&*entuty);
-                self.select_branch(root, &mut branch, &selector, &mut result);
+                self.select_branch(root, None, &mut branch, selectors, &mut result);
             }
         }
-        SelectedElements {
-            elements: self,
-            entities: result,
-        }
+        result
     }
 
     fn select_branch(
         &self,
         entity: Entity,
-        element_ptrs: &mut Vec<*const Element>,
-        selector: &Selector,
+        siblings: Option<(&SmallVec<[Entity; 8]>, usize)>,
+        levels: &mut Vec<(SmallVec<[Entity; 8]>, usize)>,
+        selectors: &[Selector],
         result: &mut Vec<Entity>,
     ) {
         let Ok(elem) = self.elements.get(entity) else {
             return;
         };
-        let elem = &*elem as *const Element;
-        element_ptrs.push(elem);
-        let mut branch = ElementsBranch::new();
-        for e in element_ptrs.iter() {
-            branch.append(unsafe { e.as_ref().unwrap() })
+        match siblings {
+            Some((siblings, position)) if !elem.element.is_virtual() => {
+                levels.push((siblings.clone(), position))
+            }
+            _ => levels.push((smallvec![entity], 0)),
+        }
+        let mut branch = ElementsBranch::with_tree(self);
+        for (siblings, position) in levels.iter() {
+            branch.append_entities(siblings.clone(), *position)
         }
-        if selector.matches(&branch) {
+        if selectors.iter().any(|selector| selector.matches(&branch)) {
             result.push(entity);
         }
         if let Ok(children) = self.children.get(entity) {
+            let siblings: SmallVec<[Entity; 8]> = children
+                .children
+                .iter()
+                .copied()
+                .filter(|ch| {
+                    self.elements
+                        .get(*ch)
+                        .is_ok_and(|ch| !ch.element.is_virtual())
+                })
+                .collect();
+            let mut position = 0;
             for ch in children.children {
-                self.select_branch(*ch, element_ptrs, selector, result);
+                let is_element = siblings.get(position) == Some(ch);
+                self.select_branch(*ch, Some((&siblings, position)), levels, selectors, result);
+                if is_element {
+                    position += 1;
+                }
             }
         }
-        element_ptrs.pop();
+        levels.pop();
     }
 
     pub fn set_state(&mut self, entity: Entity, state: Tag, value: bool) {
@@ -291,6 +348,41 @@ impl<'w, 's> Elements<'w, 's> {
         self.invalidate(entity);
     }
 
+    pub fn set_attribute(&mut self, entity: Entity, name: Tag, value: impl Into<String>) {
+        self.update_attribute(entity, name, Some(value.into()));
+    }
+
+    pub fn remove_attribute(&mut self, entity: Entity, name: Tag) {
+        self.update_attribute(entity, name, None);
+    }
+
+    fn update_attribute(&mut self, entity: Entity, name: Tag, value: Option<String>) {
+        let Some(old_value) = self
+            .attributes
+            .get(&entity)
+            .and_then(|a| a.get(&name).cloned())
+            .or_else(|| {
+                if let Ok(element) = self.elements.get(entity) {
+                    Some(element.attributes.get(&name).cloned())
+                } else {
+                    None
+                }
+            })
+        else {
+            return;
+        };
+        if value == old_value {
+            return;
+        }
+        self.attributes
+            .entry(entity)
+            .or_default()
+            .insert(name, value.clone());
+        self.commands
+            .add(SetAttributeCommand(entity, name, value));
+        self.invalidate(entity);
+    }
+
     pub fn add_class(&mut self, entity: Entity, class: Tag) {
         let mut element_found = true;
         let classes = self.classes.entry(entity).or_insert_with(|| {
@@ -352,6 +444,8 @@ impl<'w, 's> Elements<'w, 's> {
 
     pub fn add_child(&mut self, entity: Entity, eml: Eml) {
         self.commands.add(eml.add_to(entity));
+        // siblings may be matched by structural pseudo-classes
+        self.invalidate(entity);
     }
 
     pub fn commands(&mut self) -> &mut Commands<'w, 's> {
@@ -400,12 +494,36 @@ impl<'w, 's, 'e> SelectedElements<'w, 's, 'e> {
         self
     }
 
+    pub fn set_attribute<T: Into<Tag>>(&mut self, name: T, value: &str) -> &mut Self {
+        let name = name.into();
+        for entity in self.entities.iter() {
+            self.elements.set_attribute(*entity, name, value);
+        }
+        self
+    }
+
+    pub fn remove_attribute<T: Into<Tag>>(&mut self, name: T) -> &mut Self {
+        let name = name.into();
+        for entity in self.entities.iter() {
+            self.elements.remove_attribute(*entity, name);
+        }
+        self
+    }
+
     pub fn remove(self) {
+        let mut parents = HashSet::new();
         for entity in self.entities {
+            if let Ok(parent) = self.elements.parents.get(entity) {
+                parents.insert(parent.get());
+            }
             if let Some(entity) = self.elements.commands.get_entity(entity) {
                 entity.despawn_recursive();
             }
         }
+        // remaining siblings may be matched by structural pseudo-classes
+        for parent in parents {
+            self.elements.invalidate(parent);
+        }
     }
 
     /// Adds eml content to the first matched element
@@ -421,6 +539,7 @@ impl<'w, 's, 'e> SelectedElements<'w, 's, 'e> {
             let child = self.elements.commands.spawn_empty().id();
             self.elements.commands.entity(*entity).add_child(child);
             self.elements.commands.add(func(child).render_to(child));
+            self.elements.invalidate(*entity);
         }
         self
     }
@@ -536,6 +655,20 @@ impl Command for RemoveClassCommand {
     }
 }
 
+pub struct SetAttributeCommand(Entity, Tag, Option<String>);
+impl Command for SetAttributeCommand {
+    fn apply(self, world: &mut World) {
+        if let Some(mut entity) = world.get_entity_mut(self.0) {
+            if let Some(mut element) = entity.get_mut::<Element>() {
+                match self.2 {
+                    Some(value) => element.attributes.insert(self.1, value),
+                    None => element.attributes.remove(&self.1),
+                };
+            }
+        }
+    }
+}
+
 pub struct CleanupElementCommand(Entity);
 impl Command for CleanupElementCommand {
     fn apply(self, world: &mut World) {
@@ -549,20 +682,67 @@ impl Command for CleanupElementCommand {
 pub struct InvalidateElement;
 pub fn invalidate_elements(
     invalid: Query<Entity, With<InvalidateElement>>,
+    parents: Query<&Parent>,
     children: Query<&Children>,
     mut elements: Query<&mut Element>,
     mut invalidated: Local<HashSet<Entity>>,
     mut commands: Commands,
+    rules: Res<RuleIndex>,
 ) {
     invalidated.clear();
+    if invalid.is_empty() {
+        return;
+    }
+    let relational = rules.is_relational();
     for entity in invalid.iter() {
+        if relational {
+            invalidate_relational(entity, &parents, &children, &mut elements);
+        }
         invalidate_children(entity, &children, &mut elements, invalidated.deref_mut());
+        // following siblings may be matched by `+` and `~` combinators
+        if let Some(siblings) = parents
+            .get(entity)
+            .ok()
+            .and_then(|p| children.get(p.get()).ok())
+        {
+            for sibling in siblings.iter().skip_while(|e| **e != entity).skip(1) {
+                invalidate_children(*sibling, &children, &mut elements, invalidated.deref_mut());
+            }
+        }
         if let Some(mut entity) = commands.get_entity(entity) {
             entity.remove::<InvalidateElement>();
         }
     }
 }
 
+/// Ancestors (and their previous siblings) may be matched
+/// by `:has()` pseudo-class, so they should be invalidated too.
+fn invalidate_relational(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    children: &Query<&Children>,
+    elements: &mut Query<&mut Element>,
+) {
+    let mut tail = entity;
+    loop {
+        let parent = parents.get(tail).ok().map(|p| p.get());
+        if let Some(siblings) = parent.and_then(|p| children.get(p).ok()) {
+            for sibling in siblings.iter().take_while(|e| **e != tail) {
+                if let Ok(mut element) = elements.get_mut(*sibling) {
+                    element.invalidate();
+                }
+            }
+        }
+        let Some(parent) = parent else {
+            break;
+        };
+        if let Ok(mut element) = elements.get_mut(parent) {
+            element.invalidate();
+        }
+        tail = parent;
+    }
+}
+
 pub fn invalidate_children(
     entity: Entity,
     children: &Query<&Children>,
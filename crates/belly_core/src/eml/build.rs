@@ -1,7 +1,10 @@
-use super::{Params, StyleParams, Variant};
+use super::{Param, Params, StyleParams, Variant};
 use crate::{
     element::{Element, ElementIdIndex},
-    ess::{PropertyExtractor, PropertyTransformer, StyleRule, StyleSheetParser},
+    ess::{
+        AnimationEvent, AnimationEventKind, PropertyExtractor, PropertyTransformer, StyleRule,
+        StyleSheetParser,
+    },
     relations::connect::{EventFilter, EventSource},
     tags,
 };
@@ -182,6 +185,11 @@ impl<'w, 's> WidgetContext<'w, 's> {
         self.data.params.styles()
     }
 
+    /// Sets the `name` style property of the widget, like `s:name=value` does
+    pub fn style(&mut self, name: impl Into<Tag>, value: Variant) {
+        self.data.params.add(Param::style(name.into(), value));
+    }
+
     pub fn apply_commands(&mut self) {
         if let Some(attr_commands) = self.data.params.commands(tags::with()) {
             attr_commands(&mut self.commands.entity(self.entity()));
@@ -381,6 +389,7 @@ pub trait Widget {
     }
 
     fn build(&self, world: &mut World, mut data: WidgetData) {
+        let attributes = data.params.attributes();
         let components = self.instantiate_components(world, &mut data.params);
         let mut queue = CommandQueue::default();
         let commands = Commands::new(&mut queue, world);
@@ -424,6 +433,13 @@ pub trait Widget {
         let id = ctx.id();
         let classes = ctx.classes();
         let styles = ctx.styles().transform(|tag, variant| {
+            let warning = ctx
+                .extractor
+                .warning(tag, &variant)
+                .or_else(|| ctx.transformer.warning(tag, &variant));
+            if let Some(warning) = warning {
+                warn!("{warning}");
+            }
             if ctx.extractor.is_compound_property(tag) {
                 match ctx.extractor.extract(tag, variant) {
                     Ok(mut props) => props.drain().collect(),
@@ -454,6 +470,7 @@ pub trait Widget {
             element.id = id;
             element.classes.extend(classes);
             element.styles.extend(styles);
+            element.attributes.extend(attributes);
         });
 
         queue.apply(world)
@@ -530,6 +547,24 @@ impl DefaultSignals {
     pub fn ready(&self) -> EventFilter<ReadyEvent> {
         EventFilter::Entity(|e| EventSource::single(e.0))
     }
+    pub fn animationstart(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::Start => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
+    pub fn animationiteration(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::Iteration => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
+    pub fn animationend(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::End => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
 }
 
 pub struct Eml {
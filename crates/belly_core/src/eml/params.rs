@@ -165,6 +165,19 @@ impl Params {
         mem::take(&mut self.defined_styles)
     }
 
+    /// Literal params available for attribute selectors: string params
+    /// keep their values, `true` flags become empty attributes.
+    pub fn attributes(&self) -> HashMap<Tag, String> {
+        self.rest
+            .iter()
+            .filter_map(|(name, param)| match &param.value {
+                Variant::String(value) => Some((*name, value.clone())),
+                Variant::Bool(true) => Some((*name, "".to_string())),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn id(&mut self) -> Option<Tag> {
         self.drop::<String>(tags::id()).map(|s| s.into())
     }
@@ -302,4 +315,21 @@ mod test {
             Some(&"black".to_string())
         );
     }
+
+    #[test]
+    fn test_literal_attributes() {
+        let mut attrs = Params::default();
+        attrs.add(Param::new("mode", "toggle".into()));
+        attrs.add(Param::new("disabled", Variant::Bool(true)));
+        attrs.add(Param::new("hidden", Variant::Bool(false)));
+        attrs.add(Param::new("s:color", "black".into()));
+        attrs.add(Param::new("class", "some-class".into()));
+        let attributes = attrs.attributes();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(
+            attributes.get(&"mode".as_tag()),
+            Some(&"toggle".to_string())
+        );
+        assert_eq!(attributes.get(&"disabled".as_tag()), Some(&"".to_string()));
+    }
 }
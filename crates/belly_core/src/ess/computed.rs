@@ -0,0 +1,881 @@
+use crate::element::Element;
+use crate::ess::defaults::Defaults;
+use crate::ess::{
+    has_vars, is_custom_property, resolve_font_units, substitute_vars, var_names, CustomProperty,
+    DeferredValue, ElementsBranch, ElementsTreeQuery, MatchedRule, MatchedRules, MediaContext,
+    PropertyRegistry, PropertyValue, RuleIndex, SelectorWeight, StyleKeyword, StyleProperty,
+    StyleSheet,
+};
+use crate::{tag, ElementsError};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use smallvec::SmallVec;
+use tagstr::Tag;
+
+/// Describes where the [`ComputedValue`] comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleSource {
+    /// Style param defined within the tag: `<span s:color="red">`
+    Inline,
+    /// Rule of the loaded (or parsed) stylesheet
+    Rule {
+        selector: String,
+        stylesheet: Handle<StyleSheet>,
+        weight: SelectorWeight,
+    },
+    /// Rule of the default stylesheet: `belly` defaults
+    /// and [`Widget::default_styles`](crate::eml::Widget::default_styles)
+    Default {
+        selector: String,
+        weight: SelectorWeight,
+    },
+    /// Value of the parent element: the property is inherited
+    /// or declared with `inherit` (`unset`) keyword
+    Inherited,
+    /// Initial value of the property: the property is declared with
+    /// `initial` (`unset`) keyword or the root element inherits it
+    Initial,
+}
+
+impl StyleSource {
+    /// The weight of the rule the value comes from,
+    /// `None` for inline, inherited and initial values.
+    pub fn weight(&self) -> Option<SelectorWeight> {
+        match self {
+            StyleSource::Rule { weight, .. } | StyleSource::Default { weight, .. } => Some(*weight),
+            _ => None,
+        }
+    }
+
+    /// The selector of the rule the value comes from,
+    /// `None` for inline, inherited and initial values.
+    pub fn selector(&self) -> Option<&str> {
+        match self {
+            StyleSource::Rule { selector, .. } | StyleSource::Default { selector, .. } => {
+                Some(selector)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The winning value of the single property
+#[derive(Debug, Clone)]
+pub struct ComputedValue {
+    pub value: PropertyValue,
+    pub source: StyleSource,
+}
+
+impl ComputedValue {
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+}
+
+/// Property values resolved for the element during
+/// [`ApplyStyleProperties`](crate::ess::ApplyStyleProperties): the value
+/// of each property applied to the element and the source it comes from.
+///
+/// ```rust
+/// # use belly_core::prelude::*;
+/// # use belly_core::build::*;
+/// # use bevy::prelude::*;
+/// fn system(styles: Query<&ComputedStyle, Changed<ComputedStyle>>) {
+///     for style in styles.iter() {
+///         if let Some(color) = style.get(tag!("color")) {
+///             info!("color from {:?}", color.source);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Default, Debug, Clone, Deref)]
+pub struct ComputedStyle(HashMap<Tag, ComputedValue>);
+
+impl ComputedStyle {
+    pub fn get(&self, property: Tag) -> Option<&ComputedValue> {
+        self.0.get(&property)
+    }
+
+    /// Returns the typed value of the property, if any.
+    pub fn value<T: 'static>(&self, property: Tag) -> Option<&T> {
+        self.0.get(&property).and_then(|v| v.get::<T>())
+    }
+
+    /// Resolves the style of the element. The `parent` style is used by inherited
+    /// properties and by `inherit` and `unset` keywords. Custom properties are
+    /// resolved first, so `var()` functions of other values can be substituted.
+    /// Then `font-size` is resolved, so `em` and `rem` units (relative to the
+    /// `font-size` of the `root` element) can be substituted as well.
+    pub(crate) fn compute(
+        inline: Option<&HashMap<Tag, PropertyValue>>,
+        matched: &[MatchedRule],
+        index: &RuleIndex,
+        defaults: &Handle<StyleSheet>,
+        parent: Option<&ComputedStyle>,
+        root: Option<&ComputedStyle>,
+        registry: &PropertyRegistry,
+    ) -> ComputedStyle {
+        let mut properties: HashSet<Tag> = matched
+            .iter()
+            .flat_map(|m| index.rule(m.rule).properties.keys().copied())
+            .collect();
+        if let Some(inline) = inline {
+            properties.extend(inline.keys().copied());
+        }
+        properties.extend(registry.inherited().copied());
+        if let Some(parent) = parent {
+            properties.extend(parent.keys().copied().filter(|p| is_custom_property(*p)));
+        }
+        let mut custom = vec![];
+        let mut declared = vec![];
+        for property in properties {
+            let inline = inline.and_then(|styles| styles.get(&property));
+            // managed values are applied by widgets
+            if inline.map(|value| value.is_managed()) == Some(true) {
+                continue;
+            }
+            let resolved = index.resolve_rule(matched, property);
+            // `!important` rules win over the inline styles
+            let important = resolved.map(|(rule, _)| rule.important) == Some(true);
+            let inline = inline.filter(|_| !important);
+            let (value, source) = if let Some(value) = inline {
+                (value.clone(), StyleSource::Inline)
+            } else if let Some((rule, value)) = resolved {
+                let selector = rule.selector.to_string();
+                let weight = rule.selector.weight;
+                let source = if &rule.stylesheet == defaults {
+                    StyleSource::Default { selector, weight }
+                } else {
+                    let stylesheet = rule.stylesheet.clone();
+                    StyleSource::Rule {
+                        selector,
+                        stylesheet,
+                        weight,
+                    }
+                };
+                (value.clone(), source)
+            } else if registry.is_inherited(property) {
+                let value = PropertyValue::new(StyleKeyword::Inherit);
+                (value, StyleSource::Inherited)
+            } else {
+                continue;
+            };
+            if is_custom_property(property) {
+                custom.push((property, value, source));
+            } else {
+                declared.push((property, value, source));
+            }
+        }
+
+        let mut computed = ComputedStyle::default();
+        let mut pending = vec![];
+        for (property, value, source) in custom {
+            match value.downcast_ref::<CustomProperty>() {
+                Some(custom) if has_vars(custom) => {
+                    pending.push((property, custom.0.clone(), source))
+                }
+                _ => computed.insert(property, value, source, parent, registry),
+            }
+        }
+        // custom properties referencing other custom properties are
+        // substituted when all the referenced properties are resolved
+        while !pending.is_empty() {
+            let count = pending.len();
+            let waiting: HashSet<Tag> = pending.iter().map(|(property, ..)| *property).collect();
+            let mut next = vec![];
+            for (property, value, source) in pending {
+                if var_names(&value).iter().any(|name| waiting.contains(name)) {
+                    next.push((property, value, source));
+                    continue;
+                }
+                match computed.substitute(&value) {
+                    Ok(value) => {
+                        let value = PropertyValue::new(CustomProperty(value));
+                        computed.insert(property, value, source, parent, registry);
+                    }
+                    Err(e) => warn!("Ignoring custom property {property}: {e}"),
+                }
+            }
+            if next.len() == count {
+                for (property, ..) in next {
+                    warn!("Ignoring custom property {property}: cyclic var() reference");
+                }
+                break;
+            }
+            pending = next;
+        }
+
+        // font-size goes first: `em` and `rem` units are relative to it
+        let font_size = tag!("font-size");
+        declared.sort_by_key(|(property, ..)| *property != font_size);
+        let initial_font_size = registry
+            .initial(font_size)
+            .and_then(|size| size.downcast_ref::<f32>())
+            .copied()
+            .unwrap_or(16.);
+        let root_font_size = root
+            .and_then(|root| root.value::<f32>(font_size))
+            .copied()
+            .unwrap_or(initial_font_size);
+        for (property, value, source) in declared {
+            let value = match value.downcast_ref::<DeferredValue>() {
+                Some(deferred) => match computed.substitute(&deferred.value).and_then(|value| {
+                    // `em` of the font-size is relative to the font-size of the parent
+                    let em = if property == font_size {
+                        parent.and_then(|parent| parent.value::<f32>(font_size))
+                    } else {
+                        computed.value::<f32>(font_size)
+                    };
+                    let em = em.copied().unwrap_or(initial_font_size);
+                    let value = resolve_font_units(&value, em, root_font_size);
+                    registry.transform_deferred(
+                        property,
+                        DeferredValue {
+                            property: deferred.property,
+                            value,
+                        },
+                    )
+                }) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        // invalid substituted values are treated as `unset`
+                        warn!("Unable to substitute var() of {property}: {e}");
+                        PropertyValue::new(StyleKeyword::Unset)
+                    }
+                },
+                None => value,
+            };
+            computed.insert(property, value, source, parent, registry);
+        }
+        computed
+    }
+
+    fn insert(
+        &mut self,
+        property: Tag,
+        value: PropertyValue,
+        source: StyleSource,
+        parent: Option<&ComputedStyle>,
+        registry: &PropertyRegistry,
+    ) {
+        let value = match value.downcast_ref::<StyleKeyword>() {
+            Some(keyword) => ComputedStyle::resolve_keyword(property, *keyword, parent, registry),
+            None => Some(ComputedValue { value, source }),
+        };
+        if let Some(value) = value {
+            self.0.insert(property, value);
+        }
+    }
+
+    /// Substitutes `var()` functions of the `value` with the
+    /// custom properties of the style.
+    fn substitute(&self, value: &StyleProperty) -> Result<StyleProperty, ElementsError> {
+        substitute_vars(value, &|name| {
+            self.value::<CustomProperty>(name).map(|custom| &custom.0)
+        })
+    }
+
+    fn resolve_keyword(
+        property: Tag,
+        keyword: StyleKeyword,
+        parent: Option<&ComputedStyle>,
+        registry: &PropertyRegistry,
+    ) -> Option<ComputedValue> {
+        let inherit = match keyword {
+            StyleKeyword::Inherit => true,
+            StyleKeyword::Initial => false,
+            StyleKeyword::Unset => registry.is_inherited(property),
+        };
+        if inherit {
+            if let Some(value) = parent.and_then(|p| p.get(property)) {
+                return Some(ComputedValue {
+                    value: value.value.clone(),
+                    source: StyleSource::Inherited,
+                });
+            }
+        }
+        registry.initial(property).map(|value| ComputedValue {
+            value: value.clone(),
+            source: StyleSource::Initial,
+        })
+    }
+
+    /// Checks if both styles share the same values from the same sources
+    fn same(&self, other: &ComputedStyle) -> bool {
+        self.len() == other.len()
+            && self.iter().all(|(property, a)| {
+                other
+                    .get(*property)
+                    .is_some_and(|b| same_value(&a.value, &b.value) && a.source == b.source)
+            })
+    }
+
+    /// Checks if inherited properties of both styles share the same values
+    fn inherits_same(&self, other: &ComputedStyle, registry: &PropertyRegistry) -> bool {
+        registry.inherited().all(
+            |property| match (self.get(*property), other.get(*property)) {
+                (Some(a), Some(b)) => same_value(&a.value, &b.value),
+                (None, None) => true,
+                _ => false,
+            },
+        )
+    }
+}
+
+/// Checks if the values are the same: the transformed values are shared,
+/// the custom properties are substituted on every compute, so they are compared
+fn same_value(a: &PropertyValue, b: &PropertyValue) -> bool {
+    a.ptr_eq(b)
+        || matches!(
+            (a.downcast_ref::<CustomProperty>(), b.downcast_ref::<CustomProperty>()),
+            (Some(a), Some(b)) if a == b
+        )
+}
+
+/// Computes [`ComputedStyle`] of the changed elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct ComputeStyles;
+
+/// Computes styles of the changed elements from the top of the tree to the bottom,
+/// so children inherit the fresh values of their parents. When inherited values
+/// of the element change, the descendants are recomputed as well.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_styles_system(
+    changed: Query<Entity, Changed<Element>>,
+    mut styles: Query<&mut ComputedStyle>,
+    parents: Query<&'static Parent>,
+    children: Query<&'static Children>,
+    elements: Query<&'static Element>,
+    index: Res<RuleIndex>,
+    matched: Res<MatchedRules>,
+    defaults: Res<Defaults>,
+    registry: Res<PropertyRegistry>,
+    media: Res<MediaContext>,
+    mut commands: Commands,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let tree = ElementsTreeQuery {
+        elements: &elements,
+        parents: &parents,
+        children: &children,
+    };
+    let mut roots: Vec<_> = changed
+        .iter()
+        .map(|entity| (parents.iter_ancestors(entity).count(), entity))
+        .collect();
+    roots.sort_unstable_by_key(|(depth, _)| *depth);
+    let mut computed: HashMap<Entity, ComputedStyle> = HashMap::default();
+    let mut stack = vec![];
+    for (_, root) in roots {
+        stack.push(root);
+        while let Some(entity) = stack.pop() {
+            if computed.contains_key(&entity) {
+                continue;
+            }
+            // virtual elements use inline styles of the nearest real parent
+            let mut inline = None;
+            let mut tail = entity;
+            while let Ok(element) = elements.get(tail) {
+                if !element.is_virtual() {
+                    inline = Some(&element.styles);
+                    break;
+                }
+                let Ok(parent) = parents.get(tail) else {
+                    break;
+                };
+                tail = parent.get();
+            }
+            let style_of = |e: Entity| computed.get(&e).or_else(|| styles.get(e).ok());
+            let parent = parents
+                .iter_ancestors(entity)
+                .find(|e| elements.contains(*e))
+                .and_then(style_of);
+            let root = parents
+                .iter_ancestors(entity)
+                .filter(|e| elements.contains(*e))
+                .last()
+                .and_then(style_of);
+            let fallback;
+            let matched = match matched.get(&entity) {
+                Some(matched) => matched.as_slice(),
+                None => {
+                    // element changed after the rules were matched
+                    // or inherits the changed values
+                    let branch = ElementsBranch::build(entity, &tree);
+                    let root = parents.iter_ancestors(entity).last().unwrap_or(entity);
+                    fallback = if index.is_scoped() {
+                        let ancestors: SmallVec<[Entity; 16]> = std::iter::once(entity)
+                            .chain(parents.iter_ancestors(entity))
+                            .collect();
+                        index.match_branch_in(&branch, media.get(root), &ancestors)
+                    } else {
+                        index.match_branch(&branch, media.get(root))
+                    };
+                    fallback.as_slice()
+                }
+            };
+            let style = ComputedStyle::compute(
+                inline,
+                matched,
+                &index,
+                &defaults.style_sheet,
+                parent,
+                root,
+                &registry,
+            );
+            let inherits_same = styles
+                .get(entity)
+                .map(|previous| previous.inherits_same(&style, &registry))
+                .unwrap_or_default();
+            if !inherits_same {
+                if let Ok(children) = children.get(entity) {
+                    stack.extend(children.iter().filter(|e| elements.contains(**e)));
+                }
+            }
+            computed.insert(entity, style);
+        }
+    }
+    for (entity, style) in computed {
+        if let Ok(mut computed) = styles.get_mut(entity) {
+            // the unchanged styles aren't applied again
+            if !computed.same(&style) {
+                *computed = style;
+            }
+        } else {
+            commands.entity(entity).insert(style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::eml::Variant;
+    use crate::ess::{CalcValue, CompoundProperty, Media, Property, StyleRule};
+    use smallvec::smallvec;
+    use tagstr::*;
+
+    fn sheet(rules: &[(&str, &str, &str)]) -> StyleSheet {
+        StyleSheet::new(rules.iter().map(|(selector, name, value)| {
+            let mut properties = HashMap::default();
+            properties.insert(name.as_tag(), PropertyValue::new(value.to_string()));
+            StyleRule {
+                selector: (*selector).into(),
+                properties,
+                media: default(),
+                important: false,
+                layer: None,
+            }
+        }))
+    }
+
+    #[test]
+    fn computed_style_sources() {
+        let defaults = sheet(&[("*", "color", "white"), ("*", "width", "auto")]);
+        let mut app_sheet = sheet(&[("span", "color", "red"), (".a", "color", "blue")]);
+        app_sheet.set_extra_weight(1);
+        let defaults_handle = Handle::weak_from_u128(1);
+        let app_handle = Handle::weak_from_u128(2);
+        let index = RuleIndex::new([
+            (defaults_handle.clone(), &defaults),
+            (app_handle.clone(), &app_sheet),
+        ]);
+        let element = Element {
+            names: smallvec!["span".as_tag()],
+            classes: ["a".as_tag()].into_iter().collect(),
+            ..default()
+        };
+        let mut branch = ElementsBranch::new();
+        branch.insert(&element);
+        let matched = index.match_branch(&branch, &Media::default());
+
+        let mut inline = HashMap::default();
+        inline.insert("height".as_tag(), PropertyValue::new("10px".to_string()));
+        let registry = PropertyRegistry::default();
+        let style = ComputedStyle::compute(
+            Some(&inline),
+            &matched,
+            &index,
+            &defaults_handle,
+            None,
+            None,
+            &registry,
+        );
+
+        assert_eq!(style.len(), 3);
+        let color = style.get("color".as_tag()).unwrap();
+        assert_eq!(color.get::<String>(), Some(&"blue".to_string()));
+        assert_eq!(
+            color.source,
+            StyleSource::Rule {
+                selector: ".a".to_string(),
+                stylesheet: app_handle,
+                weight: SelectorWeight(10, 1),
+            }
+        );
+        let width = style.get("width".as_tag()).unwrap();
+        assert_eq!(width.source.selector(), Some("*"));
+        assert!(matches!(width.source, StyleSource::Default { .. }));
+        let height = style.get("height".as_tag()).unwrap();
+        assert_eq!(height.source, StyleSource::Inline);
+        assert_eq!(height.source.weight(), None);
+        assert_eq!(
+            style.value::<String>("height".as_tag()),
+            Some(&"10px".to_string())
+        );
+    }
+
+    #[test]
+    fn computed_style_important() {
+        let mut sheet = sheet(&[("span", "color", "red"), ("span", "width", "50px")]);
+        sheet.rules[1].important = true;
+        let handle = Handle::weak_from_u128(1);
+        let index = RuleIndex::new([(handle.clone(), &sheet)]);
+        let element = Element {
+            names: smallvec!["span".as_tag()],
+            ..default()
+        };
+        let mut branch = ElementsBranch::new();
+        branch.insert(&element);
+        let matched = index.match_branch(&branch, &Media::default());
+
+        let mut inline = HashMap::default();
+        inline.insert("color".as_tag(), PropertyValue::new("blue".to_string()));
+        inline.insert("width".as_tag(), PropertyValue::new("10px".to_string()));
+        let style = ComputedStyle::compute(
+            Some(&inline),
+            &matched,
+            &index,
+            &Handle::default(),
+            None,
+            None,
+            &PropertyRegistry::default(),
+        );
+        // inline styles win over regular rules, but not over `!important` ones
+        assert_eq!(
+            style.value::<String>("color".as_tag()),
+            Some(&"blue".to_string())
+        );
+        assert_eq!(
+            style.value::<String>("width".as_tag()),
+            Some(&"50px".to_string())
+        );
+        assert_eq!(
+            style.get("width".as_tag()).unwrap().source.selector(),
+            Some("span")
+        );
+    }
+
+    #[test]
+    fn computed_style_inheritance() {
+        let mut registry = PropertyRegistry::default();
+        registry.register::<crate::ess::property::impls::text::ColorProperty>();
+        registry.register::<crate::ess::property::impls::layout_control::LeftProperty>();
+        let keyword = |k| PropertyValue::new(k);
+        let rule = |selector: &str, props: Vec<(&str, PropertyValue)>| StyleRule {
+            selector: selector.into(),
+            properties: props.into_iter().map(|(n, v)| (n.as_tag(), v)).collect(),
+            media: default(),
+            important: false,
+            layer: None,
+        };
+        let sheet = StyleSheet::new([
+            rule("div", vec![("color", PropertyValue::new(Color::WHITE))]),
+            rule(".initial", vec![("color", keyword(StyleKeyword::Initial))]),
+            rule(".unset", vec![("left", keyword(StyleKeyword::Unset))]),
+            rule(".inherit", vec![("left", keyword(StyleKeyword::Inherit))]),
+        ]);
+        let handle = Handle::weak_from_u128(1);
+        let index = RuleIndex::new([(handle.clone(), &sheet)]);
+        let compute = |element: &Element, parent: Option<&ComputedStyle>| {
+            let mut branch = ElementsBranch::new();
+            branch.insert(element);
+            let matched = index.match_branch(&branch, &Media::default());
+            ComputedStyle::compute(None, &matched, &index, &handle, parent, None, &registry)
+        };
+        let span = |classes: &[&str]| Element {
+            names: smallvec!["span".as_tag()],
+            classes: classes.iter().map(|c| c.as_tag()).collect(),
+            ..default()
+        };
+
+        // the root element inherits the initial value
+        let root = compute(&span(&[]), None);
+        assert_eq!(
+            root.get("color".as_tag()).unwrap().source,
+            StyleSource::Initial
+        );
+
+        let div = compute(
+            &Element {
+                names: smallvec!["div".as_tag()],
+                ..default()
+            },
+            Some(&root),
+        );
+        assert_eq!(div.value::<Color>("color".as_tag()), Some(&Color::WHITE));
+        assert!(div.get("left".as_tag()).is_none());
+
+        // inherited properties take the value of the parent
+        let child = compute(&span(&[]), Some(&div));
+        let color = child.get("color".as_tag()).unwrap();
+        assert_eq!(color.source, StyleSource::Inherited);
+        assert_eq!(color.get::<Color>(), Some(&Color::WHITE));
+
+        // `initial` resets inherited property
+        let initial = compute(&span(&["initial"]), Some(&div));
+        let color = initial.get("color".as_tag()).unwrap();
+        assert_eq!(color.source, StyleSource::Initial);
+        assert_ne!(color.get::<Color>(), Some(&Color::WHITE));
+
+        // `unset` of not inherited property is `initial`
+        let unset = compute(&span(&["unset"]), Some(&div));
+        assert_eq!(
+            unset.get("left".as_tag()).unwrap().source,
+            StyleSource::Initial
+        );
+
+        // `inherit` of not inherited property takes the parent value
+        let mut parent = span(&[]);
+        parent
+            .styles
+            .insert("left".as_tag(), PropertyValue::new(Val::Px(5.)));
+        let parent = ComputedStyle::compute(
+            Some(&parent.styles),
+            &[],
+            &index,
+            &handle,
+            None,
+            None,
+            &registry,
+        );
+        let inherit = compute(&span(&["inherit"]), Some(&parent));
+        let left = inherit.get("left".as_tag()).unwrap();
+        assert_eq!(left.source, StyleSource::Inherited);
+        assert_eq!(left.get::<Val>(), Some(&Val::Px(5.)));
+    }
+
+    #[test]
+    fn computed_style_custom_properties() {
+        use crate::ess::property::impls::{spacing, text};
+        let mut registry = PropertyRegistry::default();
+        registry.register::<text::ColorProperty>();
+        registry.register::<spacing::MarginLeftProperty>();
+        registry.register_compound::<spacing::MarginProperty>();
+        let transform = |name: &str, value: &str| {
+            let name = name.as_tag();
+            let value = Variant::String(value.to_string());
+            if name == "margin".as_tag() {
+                spacing::MarginProperty::extract_value(value).unwrap()
+            } else if is_custom_property(name) {
+                [(name, CustomProperty::transform(value).unwrap())]
+                    .into_iter()
+                    .collect()
+            } else {
+                [(name, text::ColorProperty::transform(value).unwrap())]
+                    .into_iter()
+                    .collect()
+            }
+        };
+        let rule = |selector: &str, props: &[(&str, &str)]| StyleRule {
+            selector: selector.into(),
+            properties: props.iter().flat_map(|(n, v)| transform(n, v)).collect(),
+            media: default(),
+            important: false,
+            layer: None,
+        };
+        let sheet = StyleSheet::new([
+            rule("div", &[("--accent", "#ff0000"), ("--gap", "5px")]),
+            rule("span", &[("color", "var(--accent, #0000ff)")]),
+            rule(".gap", &[("margin", "var(--gap) 0px")]),
+            rule(".fallback", &[("color", "var(--missing, #0000ff)")]),
+            rule(
+                ".chain",
+                &[("--main", "var(--accent)"), ("color", "var(--main)")],
+            ),
+            rule(".cycle", &[("--a", "var(--b)"), ("--b", "var(--a)")]),
+        ]);
+        let handle = Handle::weak_from_u128(1);
+        let index = RuleIndex::new([(handle.clone(), &sheet)]);
+        let compute = |element: &Element, parent: Option<&ComputedStyle>| {
+            let mut branch = ElementsBranch::new();
+            branch.insert(element);
+            let matched = index.match_branch(&branch, &Media::default());
+            ComputedStyle::compute(
+                Some(&element.styles),
+                &matched,
+                &index,
+                &handle,
+                parent,
+                None,
+                &registry,
+            )
+        };
+        let element = |name: &str, classes: &[&str]| Element {
+            names: smallvec![name.as_tag()],
+            classes: classes.iter().map(|c| c.as_tag()).collect(),
+            ..default()
+        };
+        let red = Color::srgb(1., 0., 0.);
+        let blue = Color::srgb(0., 0., 1.);
+
+        let div = compute(&element("div", &[]), None);
+        // custom properties are inherited
+        let span = compute(&element("span", &["gap"]), Some(&div));
+        assert!(span.get("--accent".as_tag()).is_some());
+        assert_eq!(span.value::<Color>("color".as_tag()), Some(&red));
+        assert_eq!(
+            span.value::<Val>("margin-left".as_tag()),
+            Some(&Val::Px(0.))
+        );
+        assert_eq!(span.value::<Val>("margin-top".as_tag()), Some(&Val::Px(5.)));
+
+        // fallback is used for undefined custom properties
+        let span = compute(&element("span", &[]), None);
+        assert_eq!(span.value::<Color>("color".as_tag()), Some(&blue));
+        let fallback = compute(&element("p", &["fallback"]), Some(&div));
+        assert_eq!(fallback.value::<Color>("color".as_tag()), Some(&blue));
+
+        // custom properties may reference each other
+        let chain = compute(&element("p", &["chain"]), Some(&div));
+        assert_eq!(chain.value::<Color>("color".as_tag()), Some(&red));
+        // the substituted values are shared, so the same style is detected
+        let again = compute(&element("p", &["chain"]), Some(&div));
+        assert!(chain.same(&again));
+        assert!(chain.inherits_same(&again, &registry));
+
+        // inline custom properties re-theme the subtree
+        let mut themed = element("p", &[]);
+        themed.styles.extend(transform("--accent", "#0000ff"));
+        let themed = compute(&themed, Some(&div));
+        let span = compute(&element("span", &[]), Some(&themed));
+        assert_eq!(span.value::<Color>("color".as_tag()), Some(&blue));
+
+        // cyclic references are ignored
+        let cycle = compute(&element("p", &["cycle"]), None);
+        assert!(cycle.get("--a".as_tag()).is_none());
+        assert!(cycle.get("--b".as_tag()).is_none());
+    }
+
+    #[test]
+    fn computed_style_relative_units() {
+        use crate::ess::property::impls::{size_constraints, text};
+        let mut registry = PropertyRegistry::default();
+        registry.register::<text::FontSizeProperty>();
+        registry.register::<size_constraints::WidthProperty>();
+        let index = RuleIndex::default();
+        let handle = Handle::weak_from_u128(1);
+        let style = |props: &[(&str, &str)]| {
+            let mut element = Element::default();
+            for (name, value) in props {
+                let value = Variant::String(value.to_string());
+                let value = match *name {
+                    "font-size" => text::FontSizeProperty::transform(value),
+                    _ => size_constraints::WidthProperty::transform(value),
+                };
+                element.styles.insert(name.as_tag(), value.unwrap());
+            }
+            element.styles
+        };
+        let compute = |styles, parent, root| {
+            ComputedStyle::compute(Some(&styles), &[], &index, &handle, parent, root, &registry)
+        };
+        let font_size = "font-size".as_tag();
+        let width = "width".as_tag();
+
+        let root = compute(style(&[("font-size", "10px")]), None, None);
+        // em of the font-size is relative to the parent font-size
+        let parent = compute(style(&[("font-size", "2em")]), Some(&root), Some(&root));
+        assert_eq!(parent.value::<f32>(font_size), Some(&20.));
+        // em of other properties is relative to the element font-size,
+        // rem is relative to the root font-size
+        let child = compute(
+            style(&[("font-size", "1.5em"), ("width", "calc(2em + 1rem)")]),
+            Some(&parent),
+            Some(&root),
+        );
+        assert_eq!(child.value::<f32>(font_size), Some(&30.));
+        assert_eq!(child.value::<Val>(width), Some(&Val::Px(70.)));
+
+        let child = compute(style(&[("width", "50vw")]), Some(&parent), Some(&root));
+        assert_eq!(child.value::<Val>(width), Some(&Val::Vw(50.)));
+        // calc() mixing units is evaluated when applied
+        let child = compute(
+            style(&[("width", "calc(100% - 1em)")]),
+            Some(&parent),
+            Some(&root),
+        );
+        let calc = child.value::<CalcValue>(width).unwrap();
+        assert_eq!(calc.evaluate(100., Vec2::ZERO), 80.);
+    }
+
+    #[test]
+    fn compute_styles_keeps_scoped_rules() {
+        use crate::ess::match_rules_system;
+        use crate::ess::property::impls::text::ColorProperty;
+        let mut world = World::new();
+        let mut registry = PropertyRegistry::default();
+        registry.register::<ColorProperty>();
+        let scoped = StyleSheet::new([StyleRule {
+            selector: "span".into(),
+            properties: [("color".as_tag(), PropertyValue::new(Color::WHITE))]
+                .into_iter()
+                .collect(),
+            media: default(),
+            important: false,
+            layer: None,
+        }]);
+        let mut styles = HashMap::default();
+        styles.insert("color".as_tag(), PropertyValue::new(Color::BLACK));
+        let parent = world
+            .spawn(Element {
+                names: smallvec!["div".as_tag()],
+                styles,
+                ..default()
+            })
+            .id();
+        let child = world
+            .spawn(Element {
+                names: smallvec!["span".as_tag()],
+                ..default()
+            })
+            .set_parent(parent)
+            .id();
+        let index = RuleIndex::with_scoped(
+            [],
+            [(Handle::weak_from_u128(1), &scoped, smallvec![parent])],
+        );
+        world.insert_resource(index);
+        world.insert_resource(registry);
+        world.init_resource::<MatchedRules>();
+        world.init_resource::<MediaContext>();
+        world.init_resource::<Defaults>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((match_rules_system, compute_styles_system).chain());
+        let mut run = |world: &mut World| {
+            schedule.run(world);
+            world.clear_trackers();
+        };
+        let color = |world: &World, entity| {
+            *world
+                .get::<ComputedStyle>(entity)
+                .unwrap()
+                .value::<Color>("color".as_tag())
+                .unwrap()
+        };
+        run(&mut world);
+        assert_eq!(color(&world, parent), Color::BLACK);
+        assert_eq!(color(&world, child), Color::WHITE);
+
+        // the child isn't matched again, but is recomputed with its scoped rule
+        world.get_mut::<Element>(parent).unwrap().styles.insert(
+            "color".as_tag(),
+            PropertyValue::new(Color::srgb(1., 0., 0.)),
+        );
+        run(&mut world);
+        assert_eq!(color(&world, parent), Color::srgb(1., 0., 0.));
+        assert_eq!(color(&world, child), Color::WHITE);
+    }
+}
@@ -3,49 +3,72 @@ use crate::ess::PropertyExtractor;
 use crate::ess::PropertyTransformer;
 use crate::ess::StyleSheet;
 use crate::ess::StyleSheetParser;
+use crate::ess::{FontFaces, FontStyle, DEFAULT_FONT_FAMILY};
 use bevy::prelude::*;
 
 #[derive(Default, Resource)]
 pub struct Defaults {
+    #[deprecated = "use `font-family`/`font-weight`/`font-style` or `@font-face`, see `FontFaces`"]
     pub regular_font: Handle<Font>,
+    #[deprecated = "use `font-family`/`font-weight`/`font-style` or `@font-face`, see `FontFaces`"]
     pub italic_font: Handle<Font>,
+    #[deprecated = "use `font-family`/`font-weight`/`font-style` or `@font-face`, see `FontFaces`"]
     pub bold_font: Handle<Font>,
+    #[deprecated = "use `font-family`/`font-weight`/`font-style` or `@font-face`, see `FontFaces`"]
     pub bold_italic_font: Handle<Font>,
     pub style_sheet: Handle<StyleSheet>,
 }
 
+// fills the deprecated font fields for the code still reading them
+#[allow(deprecated)]
 pub fn setup_defaults(
     mut commands: Commands,
     mut fonts: ResMut<Assets<Font>>,
+    mut faces: ResMut<FontFaces>,
     mut defaults: ResMut<Defaults>,
     widgets: Res<WidgetRegistry>,
     extractor: Res<PropertyExtractor>,
     validator: Res<PropertyTransformer>,
 ) {
-    let font_bytes = include_bytes!("assets/Exo2-ExtraLight.ttf").to_vec();
-    let font_asset = Font::try_from_bytes(font_bytes).unwrap();
-    let font_handle = fonts.add(font_asset);
-    defaults.regular_font = font_handle;
-    let font_bytes = include_bytes!("assets/Exo2-ExtraLightItalic.ttf").to_vec();
-    let font_asset = Font::try_from_bytes(font_bytes).unwrap();
-    let font_handle = fonts.add(font_asset);
-    defaults.italic_font = font_handle;
-    let font_bytes = include_bytes!("assets/Exo2-SemiBold.ttf").to_vec();
-    let font_asset = Font::try_from_bytes(font_bytes).unwrap();
-    let font_handle = fonts.add(font_asset);
-    defaults.bold_font = font_handle;
-    let font_bytes = include_bytes!("assets/Exo2-SemiBoldItalic.ttf").to_vec();
-    let font_asset = Font::try_from_bytes(font_bytes).unwrap();
-    let font_handle = fonts.add(font_asset);
-    defaults.bold_italic_font = font_handle;
+    let bundled: [(&[u8], u16, FontStyle); 4] = [
+        (
+            include_bytes!("assets/Exo2-ExtraLight.ttf"),
+            200,
+            FontStyle::Normal,
+        ),
+        (
+            include_bytes!("assets/Exo2-ExtraLightItalic.ttf"),
+            200,
+            FontStyle::Italic,
+        ),
+        (
+            include_bytes!("assets/Exo2-SemiBold.ttf"),
+            600,
+            FontStyle::Normal,
+        ),
+        (
+            include_bytes!("assets/Exo2-SemiBoldItalic.ttf"),
+            600,
+            FontStyle::Italic,
+        ),
+    ];
+    for (font_bytes, weight, style) in bundled {
+        let font_asset = Font::try_from_bytes(font_bytes.to_vec()).unwrap();
+        let font_handle = fonts.add(font_asset);
+        faces.register(DEFAULT_FONT_FAMILY, weight, style, font_handle);
+    }
+    // the fonts of the bundled family
+    let family = [DEFAULT_FONT_FAMILY.to_string()];
+    let face = |weight, style| faces.find(&family, weight, style).unwrap_or_default();
+    defaults.regular_font = face(200, FontStyle::Normal);
+    defaults.italic_font = face(200, FontStyle::Italic);
+    defaults.bold_font = face(600, FontStyle::Normal);
+    defaults.bold_italic_font = face(600, FontStyle::Italic);
 
     let parser = StyleSheetParser::new(validator.clone(), extractor.clone());
     let mut rules = parser.parse(
         r#"
             * {
-                font: regular;
-                color: #cfcfcf;
-                font-size: 22px;
                 display: flex;
                 background-color: transparent;
             }
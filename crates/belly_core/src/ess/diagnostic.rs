@@ -0,0 +1,88 @@
+use bevy::log::*;
+use cssparser::SourceLocation;
+use std::fmt::Display;
+
+/// Severity of the [`Diagnostic`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The invalid part of the stylesheet is dropped
+    Error,
+    /// The part of the stylesheet is valid, but ignored
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// The error or the warning produced by parsing the stylesheet.
+/// The diagnostics of the loaded stylesheets are available with
+/// [`StyleSheet::diagnostics`](crate::ess::StyleSheet::diagnostics).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Asset path of the stylesheet, `None` for the stylesheets parsed from strings
+    pub file: Option<String>,
+    /// Line number, starting at 1
+    pub line: u32,
+    /// Column number, starting at 1
+    pub column: u32,
+    /// The line of the source the diagnostic points to
+    pub excerpt: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(
+        severity: Severity,
+        message: String,
+        source: &str,
+        location: SourceLocation,
+    ) -> Diagnostic {
+        let excerpt = source
+            .lines()
+            .nth(location.line as usize)
+            .unwrap_or_default()
+            .to_string();
+        Diagnostic {
+            severity,
+            message,
+            file: None,
+            line: location.line + 1,
+            column: location.column,
+            excerpt,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub(crate) fn log(&self) {
+        match self.severity {
+            Severity::Error => error!("{self}"),
+            Severity::Warning => warn!("{self}"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} at ", self.severity, self.message)?;
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        let pos = self.column.saturating_sub(1) as usize;
+        let empty = "";
+        write!(
+            f,
+            "{}:{}\n{}\n{empty: <pos$}^",
+            self.line, self.column, self.excerpt
+        )
+    }
+}
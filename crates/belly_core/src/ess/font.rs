@@ -0,0 +1,247 @@
+use crate::ess::{SpacedFonts, StyleSheet, Styles};
+use bevy::prelude::*;
+
+/// The family of the fonts bundled with belly. The elements use it
+/// when none of the families of `font-family` is registered.
+pub const DEFAULT_FONT_FAMILY: &str = "Exo 2";
+
+/// `font-style` of the text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+}
+
+/// The font face declared with `@font-face` rule:
+/// ```css
+/// @font-face {
+///     font-family: "Inter";
+///     src: "fonts/Inter-Bold.ttf";
+///     font-weight: 700;
+///     font-style: normal;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontFace {
+    pub family: String,
+    /// Path of the font asset
+    pub src: String,
+    pub weight: u16,
+    pub style: FontStyle,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RegisteredFace {
+    /// Family name in lowercase, the families are matched case-insensitively
+    family: String,
+    weight: u16,
+    style: FontStyle,
+    font: Handle<Font>,
+}
+
+impl RegisteredFace {
+    fn new(family: &str, weight: u16, style: FontStyle, font: Handle<Font>) -> RegisteredFace {
+        RegisteredFace {
+            family: family.to_lowercase(),
+            weight,
+            style,
+            font,
+        }
+    }
+}
+
+/// Font faces available to `font-family`, `font-weight` and `font-style`:
+/// the faces registered with [`FontFaces::register`] and the ones declared
+/// with `@font-face` rule by the loaded stylesheets.
+#[derive(Resource, Default, PartialEq)]
+pub struct FontFaces {
+    registered: Vec<RegisteredFace>,
+    declared: Vec<RegisteredFace>,
+}
+
+impl FontFaces {
+    /// Registers the `font` as the face of the `family` with the `weight` and the `style`
+    pub fn register(&mut self, family: &str, weight: u16, style: FontStyle, font: Handle<Font>) {
+        self.registered
+            .push(RegisteredFace::new(family, weight, style, font));
+    }
+
+    /// Returns the face of the first available family from `families` (or of
+    /// [`DEFAULT_FONT_FAMILY`] if none of them is available) best matching the
+    /// `style` and then the `weight`. The faces registered later win the ties.
+    pub fn find(&self, families: &[String], weight: u16, style: FontStyle) -> Option<Handle<Font>> {
+        families
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(DEFAULT_FONT_FAMILY))
+            .find_map(|family| self.find_in_family(&family.to_lowercase(), weight, style))
+    }
+
+    fn find_in_family(&self, family: &str, weight: u16, style: FontStyle) -> Option<Handle<Font>> {
+        self.registered
+            .iter()
+            .chain(self.declared.iter())
+            .enumerate()
+            .filter(|(_, face)| face.family == family)
+            .min_by_key(|(idx, face)| {
+                (
+                    face.style != style,
+                    weight_distance(weight, face.weight),
+                    std::cmp::Reverse(*idx),
+                )
+            })
+            .map(|(_, face)| face.font.clone())
+    }
+}
+
+/// Orders the `available` weights for the `desired` one like CSS does:
+/// for 400–500 the heavier weights up to 500 go first, then the lighter
+/// ones, then the rest of the heavier ones. The lighter weights go first
+/// for the weights below 400, the heavier ones for the weights above 500.
+fn weight_distance(desired: u16, available: u16) -> (u8, u16) {
+    let distance = desired.abs_diff(available);
+    let tier = match desired {
+        _ if available == desired => 0,
+        400..=500 if available > desired && available <= 500 => 1,
+        400..=500 if available < desired => 2,
+        400..=500 => 3,
+        _ if desired < 400 && available < desired => 1,
+        _ if desired > 500 && available > desired => 1,
+        _ => 2,
+    };
+    (tier, distance)
+}
+
+/// The font of the text element described by `font-family`,
+/// `font-weight` and `font-style`, resolved with [`FontFaces`]
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct FontDescriptor {
+    /// Families in the order of preference
+    pub family: Vec<String>,
+    pub weight: u16,
+    pub style: FontStyle,
+}
+
+impl Default for FontDescriptor {
+    fn default() -> Self {
+        FontDescriptor {
+            family: vec![DEFAULT_FONT_FAMILY.to_string()],
+            weight: 400,
+            style: FontStyle::Normal,
+        }
+    }
+}
+
+/// Collects the faces declared with `@font-face` by the loaded stylesheets
+pub(crate) fn index_font_faces_system(
+    styles: Res<Styles>,
+    stylesheets: Res<Assets<StyleSheet>>,
+    mut faces: ResMut<FontFaces>,
+) {
+    if !styles.is_changed() && !stylesheets.is_changed() {
+        return;
+    }
+    let mut handles: Vec<_> = styles.iter().cloned().collect();
+    handles.sort_by_key(|h| styles.weight(h));
+    let declared: Vec<_> = handles
+        .iter()
+        .filter_map(|handle| stylesheets.get(handle))
+        .flat_map(|sheet| sheet.font_faces())
+        .map(|(face, font)| {
+            RegisteredFace::new(&face.family, face.weight, face.style, font.clone())
+        })
+        .collect();
+    if faces.declared != declared {
+        faces.declared = declared;
+    }
+}
+
+/// Sets the font of the [`Text`] sections resolved from [`FontDescriptor`].
+/// The family ending with `.ttf` or `.otf` which isn't registered is loaded
+/// as the path of the font asset. The sections keep the resolved font spaced
+/// by `letter-spacing` and `line-height`.
+pub(crate) fn resolve_fonts_system(
+    faces: Res<FontFaces>,
+    spaced: Res<SpacedFonts>,
+    asset_server: Res<AssetServer>,
+    mut texts: Query<(Ref<FontDescriptor>, &mut Text)>,
+) {
+    for (descriptor, mut text) in texts.iter_mut() {
+        if !faces.is_changed() && !descriptor.is_changed() && !text.is_changed() {
+            continue;
+        }
+        let path = descriptor
+            .family
+            .first()
+            .filter(|family| family.ends_with(".ttf") || family.ends_with(".otf"))
+            .filter(|family| {
+                faces
+                    .find_in_family(&family.to_lowercase(), 400, FontStyle::Normal)
+                    .is_none()
+            });
+        let font = match path {
+            Some(path) => asset_server.load(path.clone()),
+            None => {
+                let Some(font) =
+                    faces.find(&descriptor.family, descriptor.weight, descriptor.style)
+                else {
+                    continue;
+                };
+                font
+            }
+        };
+        if text
+            .sections
+            .iter()
+            .any(|s| spaced.base(&s.style.font) != font)
+        {
+            text.sections
+                .iter_mut()
+                .for_each(|section| section.style.font = font.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_font_faces() {
+        let mut faces = FontFaces::default();
+        let fonts = Assets::<Font>::default();
+        let mut handles = vec![];
+        let mut add = |faces: &mut FontFaces, family, weight, style| {
+            let handle = fonts.reserve_handle();
+            faces.register(family, weight, style, handle.clone());
+            handles.push(handle);
+        };
+        add(&mut faces, DEFAULT_FONT_FAMILY, 200, FontStyle::Normal);
+        add(&mut faces, "Inter", 300, FontStyle::Normal);
+        add(&mut faces, "Inter", 500, FontStyle::Normal);
+        add(&mut faces, "Inter", 700, FontStyle::Normal);
+        add(&mut faces, "Inter", 400, FontStyle::Italic);
+        let inter = vec!["Missing".to_string(), "inter".to_string()];
+        let find = |weight, style| {
+            let font = faces.find(&inter, weight, style).unwrap();
+            handles.iter().position(|h| *h == font).unwrap()
+        };
+
+        // 400 prefers the heavier weights up to 500
+        assert_eq!(find(400, FontStyle::Normal), 2);
+        assert_eq!(find(700, FontStyle::Normal), 3);
+        assert_eq!(find(900, FontStyle::Normal), 3);
+        assert_eq!(find(600, FontStyle::Normal), 3);
+        assert_eq!(find(100, FontStyle::Normal), 1);
+        // the style is matched before the weight
+        assert_eq!(find(700, FontStyle::Italic), 4);
+
+        // unknown families fall back to the default one
+        let font = faces.find(&["Missing".to_string()], 700, FontStyle::Normal);
+        assert_eq!(font, Some(handles[0].clone()));
+        assert_eq!(weight_distance(400, 500), (1, 100));
+        assert_eq!(weight_distance(400, 300), (2, 100));
+        assert_eq!(weight_distance(400, 600), (3, 200));
+    }
+}
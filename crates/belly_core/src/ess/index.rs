@@ -0,0 +1,593 @@
+use crate::element::Element;
+use crate::ess::{
+    scoped_stylesheets, ElementsBranch, ElementsTreeQuery, Keyframes, Media, MediaContext,
+    MediaQuery, PropertyValue, ScopedStyleSheet, Selector, SelectorElement, StyleSheet, Styles,
+};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use smallvec::{smallvec, SmallVec};
+use std::cmp::Reverse;
+use std::sync::Arc;
+use tagstr::Tag;
+
+/// The style rule stored in [`RuleIndex`]
+pub struct IndexedRule {
+    pub selector: Selector,
+    pub properties: Arc<HashMap<Tag, PropertyValue>>,
+    pub stylesheet: Handle<StyleSheet>,
+    /// Queries of the enclosing `@media` rules, all of them should match
+    pub media: SmallVec<[MediaQuery; 1]>,
+    /// The rule holds the declarations marked with `!important`
+    pub important: bool,
+    /// Position of the rule in the cascade, see [`RuleIndex`]
+    pub cascade: u32,
+    /// Entities the rule of [`ScopedStyleSheet`] is scoped to,
+    /// empty for the rules of global stylesheets
+    pub scopes: SmallVec<[Entity; 1]>,
+}
+
+/// Rule matched by the element: position of the rule in [`RuleIndex`]
+/// and the number of levels the selector walked through to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchedRule {
+    pub rule: usize,
+    pub depth: u8,
+}
+
+/// Rules of all loaded stylesheets ordered by the cascade and then by decreasing
+/// weight (rules with the same weight keep the definition order) and bucketed by
+/// the rightmost compound selector, so only the rules that could possibly match
+/// the element are tested against its branch.
+///
+/// The cascade goes first: `!important` rules win over the regular ones. Among
+/// the regular rules the rules of the later declared `@layer` win and unlayered
+/// rules win over any layer, while the order is reversed for `!important` rules.
+/// Layers are ordered by their first declaration following the order of stylesheets.
+///
+/// The index is rebuilt only when [`Styles`], any [`StyleSheet`]
+/// or [`ScopedStyleSheet`] changes.
+#[derive(Resource, Default)]
+pub struct RuleIndex {
+    rules: Vec<IndexedRule>,
+    by_id: HashMap<Tag, Vec<usize>>,
+    by_class: HashMap<Tag, Vec<usize>>,
+    by_tag: HashMap<Tag, Vec<usize>>,
+    any: Vec<usize>,
+    relational: bool,
+    scoped: bool,
+    media: Vec<MediaQuery>,
+    keyframes: HashMap<Tag, Arc<Keyframes>>,
+}
+
+impl RuleIndex {
+    pub fn new<'a, I: IntoIterator<Item = (Handle<StyleSheet>, &'a StyleSheet)>>(
+        stylesheets: I,
+    ) -> RuleIndex {
+        RuleIndex::with_scoped(stylesheets, [])
+    }
+
+    /// Indexes the global `stylesheets` and the `scoped` ones: the rules of
+    /// scoped stylesheets match only the subtrees of the entities they are scoped
+    /// to and win over the global rules with the same selector weight.
+    pub fn with_scoped<'a, I, S>(stylesheets: I, scoped: S) -> RuleIndex
+    where
+        I: IntoIterator<Item = (Handle<StyleSheet>, &'a StyleSheet)>,
+        S: IntoIterator<Item = (Handle<StyleSheet>, &'a StyleSheet, SmallVec<[Entity; 1]>)>,
+    {
+        let mut stylesheets: Vec<_> = stylesheets
+            .into_iter()
+            .map(|(handle, sheet)| (handle, sheet, smallvec![]))
+            .collect();
+        let scoped_weight = stylesheets
+            .iter()
+            .flat_map(|(_, sheet, _)| sheet.iter())
+            .map(|rule| rule.selector.weight.1 + 1)
+            .max()
+            .unwrap_or(1);
+        stylesheets.extend(scoped);
+        let mut layers: Vec<Tag> = vec![];
+        for layer in stylesheets.iter().flat_map(|(_, sheet, _)| sheet.layers()) {
+            if !layers.contains(layer) {
+                layers.push(*layer);
+            }
+        }
+        let mut keyframes = HashMap::default();
+        let mut rules: Vec<_> = stylesheets
+            .into_iter()
+            .flat_map(|(handle, sheet, scopes)| {
+                for defined in sheet.keyframes() {
+                    if scopes.is_empty() {
+                        // keyframes of the stylesheets loaded later win
+                        keyframes.insert(defined.name, Arc::new(defined.clone()));
+                    } else {
+                        // scoped stylesheets don't override global keyframes
+                        keyframes
+                            .entry(defined.name)
+                            .or_insert_with(|| Arc::new(defined.clone()));
+                    }
+                }
+                let layers = &layers;
+                sheet.iter().map(move |rule| {
+                    let mut selector = rule.selector.clone();
+                    if !scopes.is_empty() {
+                        selector.weight.1 = scoped_weight;
+                    }
+                    IndexedRule {
+                        selector,
+                        properties: Arc::new(rule.properties.clone()),
+                        stylesheet: handle.clone(),
+                        media: rule.media.clone(),
+                        important: rule.important,
+                        cascade: cascade(layers, rule.layer, rule.important),
+                        scopes: scopes.clone(),
+                    }
+                })
+            })
+            .collect();
+        rules.sort_by_key(|r| (Reverse(r.cascade), -r.selector.weight));
+
+        let mut index = RuleIndex {
+            keyframes,
+            ..default()
+        };
+        for (position, rule) in rules.iter().enumerate() {
+            index.relational |= rule.selector.is_relational();
+            index.scoped |= !rule.scopes.is_empty();
+            for query in rule.media.iter() {
+                if !index.media.contains(query) {
+                    index.media.push(query.clone());
+                }
+            }
+            let mut id = None;
+            let mut class = None;
+            let mut tag = None;
+            for element in rule.selector.elements.iter() {
+                match element {
+                    e if e.is_separator() => break,
+                    SelectorElement::Id(v) => id = id.or(Some(*v)),
+                    SelectorElement::Class(v) => class = class.or(Some(*v)),
+                    SelectorElement::Tag(v) => tag = tag.or(Some(*v)),
+                    _ => continue,
+                }
+            }
+            // the most selective bucket wins
+            let bucket = if let Some(id) = id {
+                index.by_id.entry(id).or_default()
+            } else if let Some(class) = class {
+                index.by_class.entry(class).or_default()
+            } else if let Some(tag) = tag {
+                index.by_tag.entry(tag).or_default()
+            } else {
+                &mut index.any
+            };
+            bucket.push(position);
+        }
+        index.rules = rules;
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn rule(&self, position: usize) -> &IndexedRule {
+        &self.rules[position]
+    }
+
+    /// Checks if any of the indexed rules uses `:has()` pseudo-class
+    pub fn is_relational(&self) -> bool {
+        self.relational
+    }
+
+    /// Checks if any of the indexed rules comes from [`ScopedStyleSheet`]
+    pub fn is_scoped(&self) -> bool {
+        self.scoped
+    }
+
+    /// Returns all the distinct queries of `@media` rules
+    pub fn media(&self) -> impl Iterator<Item = &MediaQuery> {
+        self.media.iter()
+    }
+
+    /// Returns the keyframes defined by `@keyframes` rule with the `name`
+    pub fn keyframes(&self, name: Tag) -> Option<&Arc<Keyframes>> {
+        self.keyframes.get(&name)
+    }
+
+    /// Returns all the global rules matching the tail of the `branch`
+    /// within the `media` ordered by decreasing weight.
+    pub fn match_branch(
+        &self,
+        branch: &ElementsBranch,
+        media: &Media,
+    ) -> SmallVec<[MatchedRule; 8]> {
+        self.match_branch_in(branch, media, &[])
+    }
+
+    /// Same as [`RuleIndex::match_branch`], but also matches the rules of
+    /// [`ScopedStyleSheet`]s attached to any of `ancestors` (the tail of
+    /// the `branch` itself and all of its parents).
+    pub fn match_branch_in(
+        &self,
+        branch: &ElementsBranch,
+        media: &Media,
+        ancestors: &[Entity],
+    ) -> SmallVec<[MatchedRule; 8]> {
+        let Some(element) = branch.tail_element() else {
+            return smallvec![];
+        };
+        let mut candidates: SmallVec<[usize; 32]> = self.any.iter().copied().collect();
+        let mut extend = |bucket: Option<&Vec<usize>>| {
+            if let Some(bucket) = bucket {
+                candidates.extend(bucket.iter().copied());
+            }
+        };
+        if let Some(id) = element.id {
+            extend(self.by_id.get(&id));
+        }
+        for class in element.classes.iter() {
+            extend(self.by_class.get(class));
+        }
+        for tag in element.names.iter().chain(element.aliases.iter()) {
+            extend(self.by_tag.get(tag));
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .filter_map(|rule| {
+                let indexed = &self.rules[rule];
+                if !indexed.media.iter().all(|query| query.matches(media)) {
+                    return None;
+                }
+                if !indexed.scopes.is_empty()
+                    && !indexed.scopes.iter().any(|scope| ancestors.contains(scope))
+                {
+                    return None;
+                }
+                indexed
+                    .selector
+                    .match_depth(branch)
+                    .map(|depth| MatchedRule { rule, depth })
+            })
+            .collect()
+    }
+
+    /// Resolves the value of the `property` from the `matched` rules: the rules
+    /// with the highest cascade position and weight are taken first, then the most context-precise
+    /// (with the lowest depth) and then the last defined one.
+    pub fn resolve(&self, matched: &[MatchedRule], property: Tag) -> Option<&PropertyValue> {
+        self.resolve_rule(matched, property).map(|(_, value)| value)
+    }
+
+    /// Same as [`RuleIndex::resolve`], but also returns the rule the value comes from.
+    pub fn resolve_rule(
+        &self,
+        matched: &[MatchedRule],
+        property: Tag,
+    ) -> Option<(&IndexedRule, &PropertyValue)> {
+        let mut found: Option<(&IndexedRule, u8, &PropertyValue)> = None;
+        for matched in matched {
+            let rule = &self.rules[matched.rule];
+            let Some(value) = rule.properties.get(&property) else {
+                continue;
+            };
+            match found {
+                Some((found_rule, _, _))
+                    if rule.cascade != found_rule.cascade
+                        || rule.selector.weight != found_rule.selector.weight =>
+                {
+                    break
+                }
+                Some((_, depth, _)) if depth < matched.depth => continue,
+                _ => found = Some((rule, matched.depth, value)),
+            }
+        }
+        found.map(|(rule, _, value)| (rule, value))
+    }
+}
+
+/// Position of the rule in the cascade, the higher wins. Undeclared
+/// layers (the layer of widget defaults) share the position with unlayered rules.
+fn cascade(layers: &[Tag], layer: Option<Tag>, important: bool) -> u32 {
+    let count = layers.len() as u32;
+    let position = layer
+        .and_then(|layer| layers.iter().position(|l| *l == layer))
+        .map(|position| position as u32)
+        .unwrap_or(count);
+    if important {
+        2 * count + 1 - position
+    } else {
+        position
+    }
+}
+
+/// Rules matched by the elements changed during the current frame.
+/// Computed once before [`ApplyStyleProperties`](crate::ess::ApplyStyleProperties)
+/// and shared by all the properties.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct MatchedRules(HashMap<Entity, SmallVec<[MatchedRule; 8]>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct MatchStyleRules;
+
+pub fn index_rules_system(
+    styles: Res<Styles>,
+    stylesheets: Res<Assets<StyleSheet>>,
+    mut index: ResMut<RuleIndex>,
+    scoped: Query<(Entity, &ScopedStyleSheet)>,
+    changed_scopes: Query<(), Changed<ScopedStyleSheet>>,
+    mut removed_scopes: RemovedComponents<ScopedStyleSheet>,
+) {
+    let scopes_changed = !changed_scopes.is_empty() || removed_scopes.read().count() > 0;
+    if !styles.is_changed() && !stylesheets.is_changed() && !scopes_changed {
+        return;
+    }
+    let mut handles: Vec<_> = styles.iter().cloned().collect();
+    handles.sort_by_key(|h| styles.weight(h));
+    let scoped = scoped_stylesheets(scoped.iter(), &stylesheets);
+    *index = RuleIndex::with_scoped(
+        handles
+            .into_iter()
+            .filter_map(|h| stylesheets.get(&h).map(|s| (h, s))),
+        scoped
+            .into_iter()
+            .filter_map(|(h, scopes)| stylesheets.get(&h).map(|s| (h, s, scopes))),
+    );
+}
+
+pub fn match_rules_system(
+    changed: Query<Entity, Changed<Element>>,
+    index: Res<RuleIndex>,
+    mut matched: ResMut<MatchedRules>,
+    parents: Query<&'static Parent>,
+    children: Query<&'static Children>,
+    elements: Query<&'static Element>,
+    media: Res<MediaContext>,
+) {
+    matched.clear();
+    if changed.is_empty() || index.is_empty() {
+        return;
+    }
+    let tree = ElementsTreeQuery {
+        elements: &elements,
+        parents: &parents,
+        children: &children,
+    };
+    for entity in changed.iter() {
+        let branch = ElementsBranch::build(entity, &tree);
+        let root = parents.iter_ancestors(entity).last().unwrap_or(entity);
+        let rules = if index.is_scoped() {
+            let ancestors: SmallVec<[Entity; 16]> = std::iter::once(entity)
+                .chain(parents.iter_ancestors(entity))
+                .collect();
+            index.match_branch_in(&branch, media.get(root), &ancestors)
+        } else {
+            index.match_branch(&branch, media.get(root))
+        };
+        matched.insert(entity, rules);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::{MediaCondition, MediaFeature, MediaRange, StyleRule, DEFAULTS_LAYER};
+    use tagstr::*;
+
+    fn sheet(rules: &[(&str, &str)]) -> StyleSheet {
+        StyleSheet::new(rules.iter().map(|(selector, value)| {
+            let mut properties = HashMap::default();
+            properties.insert("prop".as_tag(), PropertyValue::new(value.to_string()));
+            StyleRule {
+                selector: (*selector).into(),
+                properties,
+                media: default(),
+                important: false,
+                layer: None,
+            }
+        }))
+    }
+
+    fn element(tag: &str, id: Option<&str>, classes: &[&str]) -> Element {
+        Element {
+            names: smallvec![tag.as_tag()],
+            id: id.map(|id| id.as_tag()),
+            classes: classes.iter().map(|c| c.as_tag()).collect(),
+            ..default()
+        }
+    }
+
+    fn resolve(index: &RuleIndex, branch: &ElementsBranch) -> Option<String> {
+        let matched = index.match_branch(branch, &Media::default());
+        index
+            .resolve(&matched, "prop".as_tag())
+            .and_then(|v| v.downcast_ref::<String>())
+            .cloned()
+    }
+
+    #[test]
+    fn index_buckets() {
+        let sheet = sheet(&[
+            ("#a", "id"),
+            ("div .b:hover", "class"),
+            ("span.b", "class"),
+            ("span", "tag"),
+            ("*", "any"),
+            (":hover", "any"),
+            ("#a > span", "tag"),
+        ]);
+        let index = RuleIndex::new([(Handle::default(), &sheet)]);
+        assert_eq!(index.len(), 7);
+        assert_eq!(index.by_id.get(&"a".as_tag()).map(|b| b.len()), Some(1));
+        assert_eq!(index.by_class.get(&"b".as_tag()).map(|b| b.len()), Some(2));
+        assert_eq!(index.by_tag.get(&"span".as_tag()).map(|b| b.len()), Some(2));
+        assert_eq!(index.any.len(), 2);
+        assert!(!index.is_relational());
+    }
+
+    #[test]
+    fn index_match_and_resolve() {
+        let sheet = sheet(&[
+            ("span", "span"),
+            ("div span", "div span"),
+            ("#root span", "#root span"),
+            (".b", "first .b"),
+            (".b", "last .b"),
+            ("section span", "section span"),
+        ]);
+        let index = RuleIndex::new([(Handle::default(), &sheet)]);
+        let root = element("div", Some("root"), &[]);
+        let div = element("div", None, &[]);
+        let span = element("span", None, &[]);
+        let classy = element("span", None, &["b"]);
+
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        branch.insert(&div);
+        let matched = index.match_branch(&branch, &Media::default());
+        assert_eq!(matched.len(), 2);
+        assert_eq!(resolve(&index, &branch), Some("div span".to_string()));
+
+        // the highest weight wins
+        branch.insert(&root);
+        assert_eq!(resolve(&index, &branch), Some("#root span".to_string()));
+
+        // the last defined rule wins among the same weight and depth
+        let mut branch = ElementsBranch::new();
+        branch.insert(&classy);
+        assert_eq!(resolve(&index, &branch), Some("last .b".to_string()));
+
+        // the lowest depth wins among the same weight
+        let section = element("section", None, &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        branch.insert(&div);
+        branch.insert(&section);
+        let matched = index.match_branch(&branch, &Media::default());
+        assert_eq!(matched.len(), 3);
+        assert_eq!(resolve(&index, &branch), Some("div span".to_string()));
+    }
+
+    #[test]
+    fn index_media_rules() {
+        let mut sheet = sheet(&[("span", "wide"), ("span", "narrow")]);
+        let narrow = MediaQuery(smallvec![MediaCondition {
+            screen: true,
+            features: smallvec![MediaFeature::Width(MediaRange::Max(800.))],
+            ..default()
+        }]);
+        sheet.rules[1].media.push(narrow.clone());
+        let index = RuleIndex::new([(Handle::default(), &sheet)]);
+        assert_eq!(index.media().collect::<Vec<_>>(), vec![&narrow]);
+
+        let span = element("span", None, &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        let resolve = |width| {
+            let media = Media { width, ..default() };
+            let matched = index.match_branch(&branch, &media);
+            index
+                .resolve(&matched, "prop".as_tag())
+                .and_then(|v| v.downcast_ref::<String>())
+                .cloned()
+        };
+        assert_eq!(resolve(600.), Some("narrow".to_string()));
+        assert_eq!(resolve(1000.), Some("wide".to_string()));
+    }
+
+    #[test]
+    fn index_cascade() {
+        let mut defaults = sheet(&[("#a", "defaults")]);
+        defaults.set_defaults_layer();
+        let layered = |rules: &[(&str, &str, &str)]| {
+            let mut sheet = sheet(&rules.iter().map(|(s, v, _)| (*s, *v)).collect::<Vec<_>>());
+            for (rule, (_, _, layer)) in sheet.rules.iter_mut().zip(rules) {
+                rule.layer = Some(layer.as_tag());
+            }
+            sheet
+        };
+        let span = element("span", Some("a"), &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        let resolve = |sheets: &[&StyleSheet]| {
+            let index = RuleIndex::new(sheets.iter().map(|s| (Handle::default(), *s)));
+            resolve(&index, &branch)
+        };
+
+        // the layer of defaults isn't declared, the highest weight wins
+        let mut app = layered(&[("span", "app", "app"), ("span", "base", "base")]);
+        assert_eq!(resolve(&[&defaults, &app]), Some("defaults".to_string()));
+
+        // the later declared layer wins regardless of the weight
+        app.layers.clear();
+        app.add_layers(["base".as_tag(), DEFAULTS_LAYER.as_tag(), "app".as_tag()]);
+        assert_eq!(resolve(&[&defaults, &app]), Some("app".to_string()));
+
+        // unlayered rules win over any layer
+        let unlayered = sheet(&[("*", "unlayered")]);
+        assert_eq!(
+            resolve(&[&defaults, &app, &unlayered]),
+            Some("unlayered".to_string())
+        );
+
+        // `!important` rules win, the earlier declared layer goes first
+        let mut important = layered(&[
+            ("span", "important base", "base"),
+            ("#a", "important app", "app"),
+        ]);
+        important.rules.iter_mut().for_each(|r| r.important = true);
+        assert_eq!(
+            resolve(&[&defaults, &app, &unlayered, &important]),
+            Some("important base".to_string())
+        );
+    }
+
+    #[test]
+    fn index_scoped() {
+        let global = sheet(&[("span", "global"), ("#a", "global id")]);
+        let scoped = sheet(&[("span", "scoped"), (".b", "scoped class")]);
+        let scope = Entity::from_raw(1);
+        let index = RuleIndex::with_scoped(
+            [(Handle::default(), &global)],
+            [(Handle::default(), &scoped, smallvec![scope])],
+        );
+        assert!(index.is_scoped());
+        let span = element("span", None, &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        let resolve = |ancestors: &[Entity]| {
+            let matched = index.match_branch_in(&branch, &Media::default(), ancestors);
+            index
+                .resolve(&matched, "prop".as_tag())
+                .and_then(|v| v.downcast_ref::<String>())
+                .cloned()
+        };
+
+        // scoped rules don't match outside of the scope
+        assert_eq!(resolve(&[]), Some("global".to_string()));
+        assert_eq!(resolve(&[Entity::from_raw(2)]), Some("global".to_string()));
+
+        // scoped rules win the rules with the same specificity
+        assert_eq!(
+            resolve(&[Entity::from_raw(2), scope]),
+            Some("scoped".to_string())
+        );
+
+        // but not the more specific global ones
+        let span = element("span", Some("a"), &["b"]);
+        let mut branch = ElementsBranch::new();
+        branch.insert(&span);
+        let matched = index.match_branch_in(&branch, &Media::default(), &[scope]);
+        assert_eq!(
+            index
+                .resolve(&matched, "prop".as_tag())
+                .and_then(|v| v.downcast_ref::<String>()),
+            Some(&"global id".to_string())
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use crate::element::{DisplayElement, Element};
+use bevy::prelude::*;
+use bevy::text::BreakLineOn;
+use bevy::utils::HashSet;
+
+/// Marks the text entity rendering the inline content of its parent: the
+/// text nodes and the inline elements (see [`DisplayElement::Inline`]) are
+/// merged into its [`Text`], each text node becomes the section styled
+/// with the color, the font and the size of the text node. So the text
+/// wraps across the style changes: `"Hello " <strong>"world"</strong> "!"`.
+///
+/// The merged children stay in the tree as the source of the sections,
+/// but they are hidden with `display: none`. The entity itself isn't an
+/// [`Element`], so the style properties don't override its sections.
+#[derive(Component, Default)]
+pub struct InlineText;
+
+/// Marks the child hidden because its content is merged into [`InlineText`].
+/// Holds the `display` of the child restored when the content is split
+/// again, `display` property updates it instead of the [`Style`].
+#[derive(Component)]
+pub struct InlineContent(pub(crate) Display);
+
+/// Merges the inline content of the elements into [`InlineText`]. Only the
+/// elements containing the changed texts, inline elements or children
+/// are merged again.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn inline_text_system(
+    mut commands: Commands,
+    changed_texts: Query<&Parent, (Changed<Text>, Without<InlineText>)>,
+    changed_displays: Query<Entity, Changed<DisplayElement>>,
+    changed_children: Query<Entity, Changed<Children>>,
+    mut removed_displays: RemovedComponents<DisplayElement>,
+    mut removed_children: RemovedComponents<Children>,
+    displays: Query<&DisplayElement>,
+    hosts: Query<&'static Parent, With<InlineText>>,
+    elements: Query<&'static Element>,
+    children: Query<&'static Children>,
+    parents: Query<&Parent>,
+    texts: Query<&'static Text>,
+    mut styles: Query<(&mut Style, Option<&mut InlineContent>)>,
+) {
+    let is_inline = |entity| {
+        displays
+            .get(entity)
+            .is_ok_and(|display| *display == DisplayElement::Inline)
+    };
+    // nested inline elements are merged by the closest block ancestor
+    let root = |entity: Entity| {
+        std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find(|entity| !is_inline(*entity))
+    };
+    let mut dirty: HashSet<_> = changed_texts.iter().map(|parent| parent.get()).collect();
+    dirty.extend(changed_displays.iter());
+    dirty.extend(changed_children.iter());
+    dirty.extend(removed_displays.read());
+    dirty.extend(removed_children.read());
+    let mut roots = HashSet::new();
+    for entity in dirty {
+        roots.extend(root(entity));
+        // the element changed its display is merged by the parent
+        if let Ok(parent) = parents.get(entity) {
+            roots.extend(root(parent.get()));
+        }
+    }
+
+    let flow = InlineFlow {
+        is_inline: &is_inline,
+        hosts: &hosts,
+        elements: &elements,
+        children: &children,
+        texts: &texts,
+    };
+    for root in roots {
+        let Ok(kids) = children.get(root) else {
+            continue;
+        };
+        let host = kids.iter().copied().find(|kid| hosts.contains(*kid));
+        let content: Vec<_> = kids
+            .iter()
+            .copied()
+            .filter(|kid| Some(*kid) != host)
+            .collect();
+        let merged = content.iter().any(|kid| is_inline(*kid))
+            && content.iter().all(|kid| flow.is_flowable(*kid));
+        if !merged {
+            // the block content breaks the text: the children are shown as is
+            if let Some(host) = host {
+                commands.entity(host).despawn_recursive();
+            }
+            for kid in content {
+                if let Ok((mut style, Some(inline))) = styles.get_mut(kid) {
+                    style.display = inline.0;
+                    commands.entity(kid).remove::<InlineContent>();
+                }
+            }
+            continue;
+        }
+        let mut sections = vec![];
+        let first = content.iter().find_map(|kid| flow.first_text(*kid));
+        let (justify, linebreak_behavior) = first
+            .and_then(|text| texts.get(text).ok())
+            .map_or((JustifyText::Left, BreakLineOn::WordBoundary), |text| {
+                (text.justify, text.linebreak_behavior)
+            });
+        for kid in content.iter() {
+            flow.collect(*kid, &mut sections);
+            if let Ok((mut style, None)) = styles.get_mut(*kid) {
+                commands.entity(*kid).insert(InlineContent(style.display));
+                style.display = Display::None;
+            }
+        }
+        let text = Text {
+            sections,
+            justify,
+            linebreak_behavior,
+        };
+        match host.map(|host| (host, texts.get(host))) {
+            Some((host, Ok(current))) => {
+                if !same_sections(&current.sections, &text.sections)
+                    || current.justify != text.justify
+                    || current.linebreak_behavior != text.linebreak_behavior
+                {
+                    commands.entity(host).insert(text);
+                }
+            }
+            Some((host, Err(_))) => {
+                commands.entity(host).insert(text);
+            }
+            None => {
+                let host = commands
+                    .spawn((
+                        TextBundle {
+                            text,
+                            background_color: BackgroundColor(Color::NONE),
+                            ..default()
+                        },
+                        InlineText,
+                    ))
+                    .id();
+                commands.entity(root).insert_children(0, &[host]);
+            }
+        }
+    }
+}
+
+struct InlineFlow<'a, 'w, 's, F: Fn(Entity) -> bool> {
+    is_inline: &'a F,
+    hosts: &'a Query<'w, 's, &'static Parent, With<InlineText>>,
+    elements: &'a Query<'w, 's, &'static Element>,
+    children: &'a Query<'w, 's, &'static Children>,
+    texts: &'a Query<'w, 's, &'static Text>,
+}
+
+impl<'a, 'w, 's, F: Fn(Entity) -> bool> InlineFlow<'a, 'w, 's, F> {
+    /// The text node produced by the string content of `eml`
+    fn is_text_node(&self, entity: Entity) -> bool {
+        !self.hosts.contains(entity)
+            && self.texts.contains(entity)
+            && self.elements.get(entity).is_ok_and(|e| e.is_virtual())
+    }
+
+    /// The text node or the inline element containing only flowable children
+    fn is_flowable(&self, entity: Entity) -> bool {
+        if self.is_text_node(entity) {
+            return true;
+        }
+        if !(self.is_inline)(entity) {
+            return false;
+        }
+        self.children
+            .get(entity)
+            .map_or(true, |kids| kids.iter().all(|kid| self.is_flowable(*kid)))
+    }
+
+    /// The first text node in the order of the tree, its alignment
+    /// and line breaking are used for the whole text
+    fn first_text(&self, entity: Entity) -> Option<Entity> {
+        if self.is_text_node(entity) {
+            return Some(entity);
+        }
+        self.children
+            .get(entity)
+            .ok()?
+            .iter()
+            .find_map(|kid| self.first_text(*kid))
+    }
+
+    /// Collects the sections of the text nodes in the order of the tree
+    fn collect(&self, entity: Entity, sections: &mut Vec<TextSection>) {
+        if let Ok(text) = self.texts.get(entity) {
+            sections.extend(text.sections.iter().cloned());
+        }
+        if let Ok(kids) = self.children.get(entity) {
+            for kid in kids.iter() {
+                self.collect(*kid, sections);
+            }
+        }
+    }
+}
+
+fn same_sections(a: &[TextSection], b: &[TextSection]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.value == b.value
+                && a.style.font == b.style.font
+                && a.style.font_size == b.style.font_size
+                && a.style.color == b.style.color
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::TextElementBundle;
+    use smallvec::smallvec;
+    use tagstr::AsTag;
+
+    fn text_node(world: &mut World, value: &str, color: Color) -> Entity {
+        let mut bundle = TextElementBundle::default();
+        bundle.text.text = Text::from_section(value, TextStyle { color, ..default() });
+        world.spawn(bundle).id()
+    }
+
+    fn element(world: &mut World, name: &str, inline: bool, kids: &[Entity]) -> Entity {
+        let element = Element {
+            names: smallvec![name.as_tag()],
+            ..default()
+        };
+        let mut entity = world.spawn((element, NodeBundle::default()));
+        if inline {
+            entity.insert(DisplayElement::Inline);
+        }
+        entity.push_children(kids).id()
+    }
+
+    fn merged(world: &mut World, root: Entity) -> Option<Vec<(String, Color)>> {
+        let host = world.get::<Children>(root)?[0];
+        world.get::<InlineText>(host)?;
+        let text = world.get::<Text>(host)?;
+        Some(
+            text.sections
+                .iter()
+                .map(|s| (s.value.clone(), s.style.color))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn merge_inline_text() {
+        let mut world = World::new();
+        let hello = text_node(&mut world, "Hello ", Color::WHITE);
+        let world_text = text_node(&mut world, "world", Color::BLACK);
+        let em_text = text_node(&mut world, " again", Color::WHITE);
+        let em = element(&mut world, "em", true, &[em_text]);
+        let strong = element(&mut world, "strong", true, &[world_text, em]);
+        let bang = text_node(&mut world, "!", Color::WHITE);
+        let root = element(&mut world, "div", false, &[hello, strong, bang]);
+        let plain_text = text_node(&mut world, "plain", Color::WHITE);
+        let plain = element(&mut world, "div", false, &[plain_text]);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(inline_text_system);
+        let mut run = |world: &mut World| {
+            schedule.run(world);
+            world.clear_trackers();
+        };
+        run(&mut world);
+
+        assert_eq!(
+            merged(&mut world, root),
+            Some(vec![
+                ("Hello ".to_string(), Color::WHITE),
+                ("world".to_string(), Color::BLACK),
+                (" again".to_string(), Color::WHITE),
+                ("!".to_string(), Color::WHITE),
+            ])
+        );
+        assert_eq!(world.get::<Style>(strong).unwrap().display, Display::None);
+        assert_eq!(world.get::<Style>(em).unwrap().display, Display::Flex);
+        // the text without inline elements isn't merged
+        assert_eq!(merged(&mut world, plain), None);
+
+        // the content of the merged text nodes is followed
+        world.get_mut::<Text>(world_text).unwrap().sections[0].value = "there".to_string();
+        run(&mut world);
+        assert_eq!(merged(&mut world, root).unwrap()[1].0, "there");
+
+        // the block content breaks the merged text, the hidden children
+        // get the display set while they were merged
+        world.get_mut::<InlineContent>(strong).unwrap().0 = Display::Grid;
+        let block = element(&mut world, "div", false, &[]);
+        world.entity_mut(root).add_child(block);
+        run(&mut world);
+        assert_eq!(merged(&mut world, root), None);
+        assert_eq!(world.get::<Style>(strong).unwrap().display, Display::Grid);
+        assert_eq!(world.get::<Children>(root).unwrap().len(), 4);
+
+        // the inline element turned into the block one breaks it as well
+        world.entity_mut(block).despawn_recursive();
+        run(&mut world);
+        assert!(merged(&mut world, root).is_some());
+        world.entity_mut(strong).insert(DisplayElement::Block);
+        run(&mut world);
+        assert_eq!(merged(&mut world, root), None);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::element::InvalidateElement;
+use crate::ess::RuleIndex;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
+use smallvec::SmallVec;
+
+/// The environment `@media` queries are evaluated against: logical
+/// size of the window (or the camera target) and its scale factor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Media {
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f32,
+}
+
+impl Default for Media {
+    fn default() -> Self {
+        Media {
+            width: 0.,
+            height: 0.,
+            scale_factor: 1.,
+        }
+    }
+}
+
+impl Media {
+    pub fn orientation(&self) -> MediaOrientation {
+        if self.height >= self.width {
+            MediaOrientation::Portrait
+        } else {
+            MediaOrientation::Landscape
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaOrientation {
+    Portrait,
+    Landscape,
+}
+
+/// Comparison of the media feature with the value: `(min-width: 800px)`
+/// is `Min(800.)`, `(width: 800px)` is `Exact(800.)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaRange {
+    Min(f32),
+    Max(f32),
+    Exact(f32),
+}
+
+impl MediaRange {
+    fn matches(&self, value: f32) -> bool {
+        match *self {
+            MediaRange::Min(min) => value >= min,
+            MediaRange::Max(max) => value <= max,
+            MediaRange::Exact(exact) => (value - exact).abs() < f32::EPSILON,
+        }
+    }
+}
+
+/// Single media feature: `(max-width: 800px)`, `(orientation: portrait)`
+/// or `(min-resolution: 2dppx)`. The resolution is stored in `dppx`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaFeature {
+    Width(MediaRange),
+    Height(MediaRange),
+    AspectRatio(MediaRange),
+    Resolution(MediaRange),
+    Orientation(MediaOrientation),
+}
+
+impl MediaFeature {
+    pub fn matches(&self, media: &Media) -> bool {
+        match self {
+            MediaFeature::Width(range) => range.matches(media.width),
+            MediaFeature::Height(range) => range.matches(media.height),
+            MediaFeature::AspectRatio(range) => {
+                media.height > 0. && range.matches(media.width / media.height)
+            }
+            MediaFeature::Resolution(range) => range.matches(media.scale_factor),
+            MediaFeature::Orientation(orientation) => media.orientation() == *orientation,
+        }
+    }
+}
+
+/// Media type with the features joined by `and`:
+/// `screen and (min-width: 400px) and (orientation: landscape)`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaCondition {
+    /// `not` prefix negates the whole condition
+    pub negated: bool,
+    /// `true` for `all` and `screen` media types (or when the type is omitted)
+    pub screen: bool,
+    pub features: SmallVec<[MediaFeature; 2]>,
+}
+
+impl MediaCondition {
+    pub fn matches(&self, media: &Media) -> bool {
+        let matches = self.screen && self.features.iter().all(|f| f.matches(media));
+        matches != self.negated
+    }
+}
+
+/// Prelude of the `@media` rule: the list of comma-separated conditions.
+/// The query matches if any of the conditions matches.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaQuery(pub SmallVec<[MediaCondition; 1]>);
+
+impl MediaQuery {
+    pub fn matches(&self, media: &Media) -> bool {
+        self.0.iter().any(|condition| condition.matches(media))
+    }
+}
+
+/// [`Media`] of every UI tree. The roots with [`TargetCamera`] use the
+/// viewport of the camera, the rest of them use the primary window.
+#[derive(Resource, Default)]
+pub struct MediaContext {
+    window: Media,
+    targets: HashMap<Entity, Media>,
+}
+
+impl MediaContext {
+    /// Returns the [`Media`] of the tree starting from the `root` entity
+    pub fn get(&self, root: Entity) -> &Media {
+        self.targets.get(&root).unwrap_or(&self.window)
+    }
+}
+
+/// Updates [`MediaContext`] and invalidates the trees where any of
+/// `@media` queries changed its result, so the layout adapts to the
+/// new size of the window.
+#[allow(clippy::type_complexity)]
+pub fn update_media_system(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<&Camera>,
+    roots: Query<(Entity, Option<&TargetCamera>), (With<Node>, Without<Parent>)>,
+    index: Res<RuleIndex>,
+    mut context: ResMut<MediaContext>,
+    mut commands: Commands,
+) {
+    let window = windows
+        .get_single()
+        .map(|window| Media {
+            width: window.width(),
+            height: window.height(),
+            scale_factor: window.scale_factor(),
+        })
+        .unwrap_or_default();
+    let mut targets = HashMap::default();
+    for (root, target) in roots.iter() {
+        let Some(camera) = target.and_then(|t| cameras.get(t.entity()).ok()) else {
+            continue;
+        };
+        if let Some(size) = camera.logical_viewport_size() {
+            targets.insert(
+                root,
+                Media {
+                    width: size.x,
+                    height: size.y,
+                    scale_factor: camera.target_scaling_factor().unwrap_or(1.),
+                },
+            );
+        }
+    }
+    if context.window == window && context.targets == targets {
+        return;
+    }
+    let previous = std::mem::replace(&mut *context, MediaContext { window, targets });
+    for (root, _) in roots.iter() {
+        let (before, after) = (previous.get(root), context.get(root));
+        if before != after && index.media().any(|q| q.matches(before) != q.matches(after)) {
+            commands.entity(root).insert(InvalidateElement);
+        }
+    }
+}
@@ -1,23 +1,42 @@
+mod computed;
 mod defaults;
+mod diagnostic;
+mod font;
+mod index;
+mod inline;
+mod media;
 mod parser;
 pub mod property;
+mod scoped;
 mod selector;
 
-pub use self::parser::StyleSheetParser;
-use crate::{element::Elements, ess::defaults::Defaults};
+pub use self::parser::{Statement, StyleSheetParser};
+pub(crate) use self::parser::parse_selector_list;
+use crate::{
+    element::{Elements, InvalidateElements},
+    ess::defaults::Defaults,
+};
 use anyhow::Error;
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt},
+    render::camera::CameraUpdateSystem,
     ecs::world::Command,
     prelude::*,
     reflect::TypePath,
-    utils::{hashbrown::hash_map::Keys, BoxedFuture, HashMap},
+    utils::{hashbrown::hash_map::Keys, BoxedFuture, HashMap, HashSet},
 };
+pub use computed::*;
+pub use diagnostic::*;
+pub use font::*;
+pub use index::*;
+pub use inline::*;
+pub use media::*;
 pub use property::*;
+pub use scoped::*;
 pub use selector::*;
 use smallvec::SmallVec;
 use std::ops::Deref;
-use tagstr::Tag;
+use tagstr::{AsTag, Tag};
 use thiserror::Error;
 
 #[derive(Default)]
@@ -26,6 +45,12 @@ pub struct EssPlugin;
 impl Plugin for EssPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<Styles>();
+        app.init_resource::<RuleIndex>();
+        app.init_resource::<MatchedRules>();
+        app.init_resource::<PropertyRegistry>();
+        app.init_resource::<MediaContext>();
+        app.init_resource::<FontFaces>();
+        app.init_resource::<SpacedFonts>();
 
         // TODO: may be desabled with feature
         app.insert_resource(Defaults::default());
@@ -45,6 +70,38 @@ impl Plugin for EssPlugin {
             extractor,
         });
         app.add_systems(Update, process_styles_system);
+        app.add_systems(
+            PostUpdate,
+            (
+                index_rules_system.before(InvalidateElements),
+                update_media_system
+                    .after(index_rules_system)
+                    .after(CameraUpdateSystem)
+                    .before(InvalidateElements),
+                match_rules_system
+                    .in_set(MatchStyleRules)
+                    .after(InvalidateElements)
+                    .before(ApplyStyleProperties),
+                compute_styles_system
+                    .in_set(ComputeStyles)
+                    .in_set(ApplyStyleProperties)
+                    .after(MatchStyleRules),
+                index_font_faces_system.before(ApplyStyleProperties),
+                resolve_fonts_system
+                    .after(ApplyStyleProperties)
+                    .after(index_font_faces_system)
+                    .before(bevy::ui::UiSystem::Layout),
+                text_spacing_system
+                    .after(ApplyStyleProperties)
+                    .after(resolve_fonts_system)
+                    .before(bevy::ui::widget::measure_text_system),
+                inline_text_system
+                    .after(ApplyStyleProperties)
+                    .after(text_spacing_system)
+                    .after(opacity_system)
+                    .before(bevy::ui::UiSystem::Layout),
+            ),
+        );
         app.add_plugins(property::PropertyPlugin);
         app.add_plugins(bevy_stylebox::StyleboxPlugin);
 
@@ -60,6 +117,9 @@ pub enum EssAssetLoaderError {
     /// EML parse error
     #[error("Could not parse ess: {0}")]
     ParseError(#[from] Error),
+    /// The stylesheet can't be read or isn't valid UTF-8
+    #[error("Could not read ess: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Default)]
@@ -81,28 +141,62 @@ impl AssetLoader for EssLoader {
         &'a self,
         reader: &'a mut Reader,
         _: &'a Self::Settings,
-        _: &'a mut bevy::asset::LoadContext,
+        load_context: &'a mut bevy::asset::LoadContext,
     ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut source = String::new();
-            reader.read_to_string(&mut source).await.unwrap();
+            reader.read_to_string(&mut source).await?;
             let parser = StyleSheetParser::new(self.validator.clone(), self.extractor.clone());
-            let rules = parser.parse(source.as_str());
             let mut stylesheet = StyleSheet::default();
-            for rule in rules {
-                stylesheet.add_rule(rule)
+            let (statements, diagnostics) = parser.parse_with_diagnostics(source.as_str());
+            stylesheet.set_diagnostics(diagnostics, Some(load_context.asset_path().to_string()));
+            for statement in statements {
+                match statement {
+                    Statement::Rules(rules) => {
+                        rules.into_iter().for_each(|r| stylesheet.add_rule(r))
+                    }
+                    Statement::Keyframes(keyframes) => stylesheet.add_keyframes(keyframes),
+                    Statement::Layers(layers) => stylesheet.add_layers(layers),
+                    // fonts are resolved relative to the directory of the stylesheet too
+                    Statement::FontFace(face) => {
+                        let path = load_context
+                            .asset_path()
+                            .resolve_embed(&face.src)
+                            .map_err(Error::from)?;
+                        let font = load_context.load(path);
+                        stylesheet.add_font_face(face, font);
+                    }
+                    // imports are resolved relative to the directory of the stylesheet
+                    Statement::Import(import) => {
+                        let path = load_context
+                            .asset_path()
+                            .resolve_embed(&import)
+                            .map_err(Error::from)?;
+                        stylesheet.imports.push(load_context.load(path));
+                    }
+                }
             }
-            // load_context.set_default_asset(LoadedAsset::new(stylesheet));
             Ok(stylesheet)
         })
     }
 }
 
+/// The layer of the widget defaults and the rules of the default stylesheet:
+/// `@layer widgets, app;` makes the rules of `app` layer win over the defaults
+/// regardless of the selector weight.
+pub const DEFAULTS_LAYER: &str = "widgets";
+
 #[derive(Default, TypePath, Asset)]
 // #[uuid = "93767098-caca-4f2b-b1d3-cdc91919be75"]
 pub struct StyleSheet {
     weight: usize,
     rules: Vec<StyleRule>,
+    keyframes: Vec<Keyframes>,
+    font_faces: Vec<(FontFace, Handle<Font>)>,
+    layers: Vec<Tag>,
+    diagnostics: Vec<Diagnostic>,
+    #[dependency]
+    imports: Vec<Handle<StyleSheet>>,
 }
 
 unsafe impl Send for StyleSheet {}
@@ -110,6 +204,16 @@ unsafe impl Sync for StyleSheet {}
 
 pub struct LoadCommand {
     path: String,
+    name: Option<String>,
+}
+
+pub struct UnloadCommand {
+    handle: Handle<StyleSheet>,
+}
+
+pub struct ReplaceCommand {
+    old: Handle<StyleSheet>,
+    new: Handle<StyleSheet>,
 }
 
 pub struct ParseCommand {
@@ -122,8 +226,25 @@ impl Command for ParseCommand {
         let extractor = world.resource::<PropertyExtractor>().clone();
         let validator = world.resource::<PropertyTransformer>().clone();
         let parser = StyleSheetParser::new(validator, extractor);
-        let rules = parser.parse(&self.source);
-        let stylesheet = StyleSheet::new(rules);
+        let mut stylesheet = StyleSheet::default();
+        let asset_server = world.resource::<AssetServer>();
+        let (statements, diagnostics) = parser.parse_with_diagnostics(&self.source);
+        stylesheet.set_diagnostics(diagnostics, None);
+        for statement in statements {
+            match statement {
+                Statement::Rules(rules) => rules.into_iter().for_each(|r| stylesheet.add_rule(r)),
+                Statement::Keyframes(keyframes) => stylesheet.add_keyframes(keyframes),
+                Statement::Layers(layers) => stylesheet.add_layers(layers),
+                Statement::Import(path) => stylesheet.imports.push(asset_server.load(path)),
+                Statement::FontFace(face) => {
+                    let font = asset_server.load(face.src.clone());
+                    stylesheet.add_font_face(face, font);
+                }
+            }
+        }
+        if self.default {
+            stylesheet.set_defaults_layer();
+        }
         let mut assets = world.resource_mut::<Assets<StyleSheet>>();
         let handle = assets.add(stylesheet);
         if self.default {
@@ -141,7 +262,10 @@ pub struct AddCommand {
 
 impl Command for AddCommand {
     fn apply(self, world: &mut bevy::prelude::World) {
-        let stylesheet = StyleSheet::new(self.rules);
+        let mut stylesheet = StyleSheet::new(self.rules);
+        if self.default {
+            stylesheet.set_defaults_layer();
+        }
         let mut assets = world.resource_mut::<Assets<StyleSheet>>();
         let handle = assets.add(stylesheet);
         if self.default {
@@ -157,7 +281,29 @@ impl Command for LoadCommand {
         let world = world;
         let handle = world.resource::<AssetServer>().load(&self.path);
         let mut styles = world.resource_mut::<Styles>();
-        styles.insert(handle);
+        match self.name {
+            Some(name) => styles.insert_named(name, handle),
+            None => styles.insert(handle),
+        };
+    }
+}
+
+impl Command for UnloadCommand {
+    fn apply(self, world: &mut bevy::prelude::World) {
+        let mut styles = world.resource_mut::<Styles>();
+        if !styles.remove(&self.handle) {
+            warn!(
+                "Unable to unload {:?}: stylesheet isn't loaded",
+                self.handle
+            );
+        }
+    }
+}
+
+impl Command for ReplaceCommand {
+    fn apply(self, world: &mut bevy::prelude::World) {
+        let mut styles = world.resource_mut::<Styles>();
+        styles.replace(&self.old, self.new);
     }
 }
 
@@ -172,8 +318,34 @@ impl StyleSheet {
     pub fn load(path: &str) -> LoadCommand {
         LoadCommand {
             path: path.to_string(),
+            name: None,
+        }
+    }
+    /// Loads the stylesheet registered by `name`. The stylesheet previously
+    /// loaded with the same `name` is replaced, so switching themes doesn't
+    /// stack their rules:
+    /// ```rust
+    /// # use belly_core::prelude::*;
+    /// # use bevy::prelude::*;
+    /// fn switch_theme(mut commands: Commands) {
+    ///     commands.add(StyleSheet::load_as("theme", "themes/light.ess"));
+    /// }
+    /// ```
+    pub fn load_as(name: &str, path: &str) -> LoadCommand {
+        LoadCommand {
+            path: path.to_string(),
+            name: Some(name.to_string()),
         }
     }
+    /// Removes the stylesheet, so its rules are no longer applied
+    pub fn unload(handle: Handle<StyleSheet>) -> UnloadCommand {
+        UnloadCommand { handle }
+    }
+    /// Puts the `new` stylesheet in place of the `old` one: the rules
+    /// of the `old` stylesheet are removed and the `new` one takes its order
+    pub fn replace(old: Handle<StyleSheet>, new: Handle<StyleSheet>) -> ReplaceCommand {
+        ReplaceCommand { old, new }
+    }
     pub fn parse(source: &str) -> ParseCommand {
         ParseCommand {
             source: source.to_string(),
@@ -200,9 +372,75 @@ impl StyleSheet {
     }
     pub fn add_rule(&mut self, rule: StyleRule) {
         // rule.selector.index = SelectorIndex::new(self.rules.len());
+        if let Some(layer) = rule.layer {
+            self.add_layers([layer]);
+        }
         self.rules.push(rule);
     }
 
+    /// Declares the `layers` (if they aren't declared yet) in the order of
+    /// `@layer base, widgets, app;` statement
+    pub fn add_layers<T: IntoIterator<Item = Tag>>(&mut self, layers: T) {
+        for layer in layers {
+            if !self.layers.contains(&layer) {
+                self.layers.push(layer);
+            }
+        }
+    }
+
+    pub fn add_keyframes(&mut self, keyframes: Keyframes) {
+        self.keyframes.push(keyframes);
+    }
+
+    /// Keyframes defined with `@keyframes` in the order of definition
+    pub fn keyframes(&self) -> &[Keyframes] {
+        &self.keyframes
+    }
+
+    pub fn add_font_face(&mut self, face: FontFace, font: Handle<Font>) {
+        self.font_faces.push((face, font));
+    }
+
+    /// Font faces defined with `@font-face` and their fonts in the order of definition
+    pub fn font_faces(&self) -> &[(FontFace, Handle<Font>)] {
+        &self.font_faces
+    }
+
+    /// Layers in the order of the first declaration with `@layer` statement or block
+    pub fn layers(&self) -> &[Tag] {
+        &self.layers
+    }
+
+    /// Errors and warnings produced by parsing the stylesheet, ordered by location
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Logs and stores the `diagnostics` of the stylesheet loaded from `file`
+    fn set_diagnostics(&mut self, mut diagnostics: Vec<Diagnostic>, file: Option<String>) {
+        for diagnostic in diagnostics.iter_mut() {
+            diagnostic.file = file.clone();
+            diagnostic.log();
+        }
+        self.diagnostics = diagnostics;
+    }
+
+    /// Stylesheets imported with `@import` in the order of definition
+    pub fn imports(&self) -> &[Handle<StyleSheet>] {
+        &self.imports
+    }
+
+    /// Places the unlayered rules of the default stylesheet into the
+    /// [`DEFAULTS_LAYER`]. The layer isn't declared by the stylesheet, so until
+    /// some other stylesheet declares it, the rules keep the unlayered position.
+    fn set_defaults_layer(&mut self) {
+        let layer = DEFAULTS_LAYER.as_tag();
+        self.rules
+            .iter_mut()
+            .filter(|r| r.layer.is_none())
+            .for_each(|r| r.layer = Some(layer));
+    }
+
     pub(crate) fn extra_weight(&self) -> usize {
         self.weight
     }
@@ -228,16 +466,109 @@ pub struct StyleRule {
     pub selector: Selector,
     // pub properties: HashMap<Tag, StyleProperty>,
     pub properties: HashMap<Tag, PropertyValue>,
+    /// Queries of the enclosing `@media` rules
+    pub media: SmallVec<[MediaQuery; 1]>,
+    /// The rule holds the declarations marked with `!important`
+    pub important: bool,
+    /// Name of the enclosing `@layer` rule, `None` for unlayered rules
+    pub layer: Option<Tag>,
 }
 
 #[derive(Default, Resource)]
 pub struct Styles {
     last_id: usize,
     map: HashMap<Handle<StyleSheet>, usize>,
+    /// Stylesheets inserted by the user (not just imported or detected)
+    explicit: HashSet<Handle<StyleSheet>>,
+    /// Stylesheets imported by each of the stylesheets
+    imports: HashMap<Handle<StyleSheet>, Vec<Handle<StyleSheet>>>,
+    /// Stylesheets loaded with [`StyleSheet::load_as`]
+    names: HashMap<String, Handle<StyleSheet>>,
+    /// Removed stylesheets are ignored until they are inserted again
+    removed: HashSet<AssetId<StyleSheet>>,
 }
 
 impl Styles {
     pub fn insert(&mut self, handle: Handle<StyleSheet>) -> usize {
+        self.removed.remove(&handle.id());
+        self.explicit.insert(handle.clone());
+        self.insert_loaded(handle)
+    }
+
+    /// Inserts the stylesheet registered by `name`: the stylesheet
+    /// previously registered with the same `name` is replaced.
+    pub fn insert_named(&mut self, name: String, handle: Handle<StyleSheet>) -> usize {
+        let id = match self.names.get(&name).cloned() {
+            Some(old) => self.replace(&old, handle.clone()),
+            None => self.insert(handle.clone()),
+        };
+        self.names.insert(name, handle);
+        id
+    }
+
+    /// Returns the stylesheet loaded with [`StyleSheet::load_as`]
+    pub fn named(&self, name: &str) -> Option<&Handle<StyleSheet>> {
+        self.names.get(name)
+    }
+
+    /// Removes the stylesheet, so its rules are no longer applied. The imports
+    /// of the stylesheet are removed as well, unless they are inserted explicitly
+    /// or imported by other stylesheets. Returns `false` if there is no such stylesheet.
+    pub fn remove(&mut self, handle: &Handle<StyleSheet>) -> bool {
+        if self.map.remove(handle).is_none() {
+            return false;
+        }
+        self.removed.insert(handle.id());
+        self.explicit.remove(handle);
+        self.names.retain(|_, named| named != handle);
+        for import in self.imports.remove(handle).unwrap_or_default() {
+            let imported = self
+                .imports
+                .values()
+                .any(|imports| imports.contains(&import));
+            if !imported && !self.explicit.contains(&import) {
+                self.remove(&import);
+            }
+        }
+        true
+    }
+
+    /// Removes the `old` stylesheet and inserts the `new` one in its place,
+    /// so the `new` stylesheet keeps the order of the `old` one.
+    pub fn replace(&mut self, old: &Handle<StyleSheet>, new: Handle<StyleSheet>) -> usize {
+        if old == &new {
+            return self.insert(new);
+        }
+        let Some(&id) = self.map.get(old) else {
+            return self.insert(new);
+        };
+        self.remove(old);
+        self.remove(&new);
+        self.removed.remove(&new.id());
+        self.explicit.insert(new.clone());
+        self.map.insert(new, id);
+        id
+    }
+
+    /// Checks if the stylesheet was inserted only because its asset was loaded:
+    /// it wasn't inserted explicitly and isn't imported by other stylesheets.
+    pub fn is_detected(&self, handle: &Handle<StyleSheet>) -> bool {
+        self.map.contains_key(handle)
+            && !self.explicit.contains(handle)
+            && !self
+                .imports
+                .values()
+                .any(|imports| imports.contains(handle))
+    }
+
+    /// Checks if the stylesheet was removed and shouldn't be inserted
+    /// when its asset is loaded or modified.
+    pub fn is_removed(&self, handle: &Handle<StyleSheet>) -> bool {
+        self.removed.contains(&handle.id())
+    }
+
+    /// Inserts the loaded stylesheet without marking it as explicitly inserted
+    fn insert_loaded(&mut self, handle: Handle<StyleSheet>) -> usize {
         let default = self.last_id + 1;
         let id = *self.map.entry(handle).or_insert(default);
         if id > self.last_id {
@@ -253,8 +584,50 @@ impl Styles {
     pub fn weight(&self, handle: &Handle<StyleSheet>) -> usize {
         *self.map.get(handle).unwrap_or(&0)
     }
+
+    /// Moves the `imports` right before the `handle` keeping their order,
+    /// so the rules of the importing stylesheet win over the imported ones.
+    /// Returns `true` if the weights were changed.
+    pub fn insert_imports(
+        &mut self,
+        handle: &Handle<StyleSheet>,
+        imports: &[Handle<StyleSheet>],
+    ) -> bool {
+        let Some(&weight) = self.map.get(handle) else {
+            return false;
+        };
+        self.imports.insert(handle.clone(), imports.to_vec());
+        for import in imports {
+            self.removed.remove(&import.id());
+        }
+        let weights: Vec<_> = imports.iter().map(|h| self.map.get(h).copied()).collect();
+        let ordered = weights.iter().all(|w| w.is_some())
+            && weights.windows(2).all(|w| w[0] < w[1])
+            && weights.last().map(|w| w.unwrap() < weight).unwrap_or(true);
+        if ordered {
+            return false;
+        }
+        let mut order: Vec<_> = self.map.keys().filter(|h| !imports.contains(h)).collect();
+        order.sort_by_key(|h| self.map[*h]);
+        let position = order.iter().position(|h| *h == handle).unwrap();
+        let order: Vec<_> = order[..position]
+            .iter()
+            .copied()
+            .chain(imports.iter())
+            .chain(order[position..].iter().copied())
+            .cloned()
+            .collect();
+        self.map = order
+            .into_iter()
+            .enumerate()
+            .map(|(idx, handle)| (handle, idx + 1))
+            .collect();
+        self.last_id = self.map.len();
+        true
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_styles_system(
     asset_server: Res<AssetServer>,
     mut styles: ResMut<Styles>,
@@ -262,8 +635,18 @@ fn process_styles_system(
     mut events: EventReader<AssetEvent<StyleSheet>>,
     mut elements: Elements,
     defaults: Res<Defaults>,
+    scoped: Query<(Entity, &ScopedStyleSheet)>,
+    changed_scopes: Query<(), Changed<ScopedStyleSheet>>,
+    mut removed_scopes: RemovedComponents<ScopedStyleSheet>,
 ) {
-    let mut styles_changed = false;
+    // stylesheets removed or replaced with commands
+    let mut styles_changed =
+        styles.is_changed() || !changed_scopes.is_empty() || removed_scopes.read().count() > 0;
+    // stylesheets attached to subtrees are not applied globally
+    let scoped: Vec<_> = scoped_stylesheets(scoped.iter(), &assets)
+        .into_iter()
+        .map(|(handle, _)| handle)
+        .collect();
     for event in events.read() {
         styles_changed = true;
         match event {
@@ -276,19 +659,107 @@ fn process_styles_system(
                         if assets.get(*id).unwrap().extra_weight() != 0 {
                             assets.get_mut(*id).unwrap().set_extra_weight(0);
                         }
-                    } else {
-                        let handle = asset_server.get_id_handle(*id).unwrap();
-                        let weight = styles.insert(handle);
-                        if assets.get(*id).unwrap().extra_weight() != weight {
-                            assets.get_mut(*id).unwrap().set_extra_weight(weight);
-                        }
+                    } else if !styles.is_removed(&handle) && !scoped.contains(&handle) {
+                        styles.insert_loaded(handle);
                     }
                 }
             },
             _ => { info!("Unused") }
         }
     }
-    if styles_changed {
-        elements.invalidate_all();
+    if !styles_changed {
+        return;
+    }
+    // imports of the scoped stylesheet may be loaded before the stylesheet itself
+    for handle in scoped.iter() {
+        if styles.is_detected(handle) {
+            styles.remove(handle);
+        }
+    }
+    // imported stylesheets go right before the importing ones,
+    // so the weights follow the order of `@import` statements
+    let mut handles: Vec<_> = styles.iter().cloned().collect();
+    handles.sort_by_key(|h| styles.weight(h));
+    for handle in handles.iter() {
+        if let Some(sheet) = assets.get(handle) {
+            let imports = sheet.imports.clone();
+            styles.insert_imports(handle, &imports);
+        }
+    }
+    for handle in handles.iter().filter(|h| **h != defaults.style_sheet) {
+        let weight = styles.weight(handle);
+        if assets.get(handle).map(|s| s.extra_weight() != weight) == Some(true) {
+            assets.get_mut(handle).unwrap().set_extra_weight(weight);
+        }
+    }
+    elements.invalidate_all();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn styles_imports_order() {
+        let handle = |id| Handle::<StyleSheet>::weak_from_u128(id);
+        let (a, b, c, d) = (handle(1), handle(2), handle(3), handle(4));
+        let mut styles = Styles::default();
+        styles.insert(a.clone());
+        styles.insert(b.clone());
+        styles.insert(c.clone());
+
+        // `b` imports `c` and `d`
+        assert!(styles.insert_imports(&b, &[c.clone(), d.clone()]));
+        let weights: Vec<_> = [&a, &c, &d, &b].iter().map(|h| styles.weight(h)).collect();
+        assert_eq!(weights, vec![1, 2, 3, 4]);
+        assert!(!styles.insert_imports(&b, &[c.clone(), d.clone()]));
+
+        // `a` imports `b`, so `b` and its imports go before `a`
+        assert!(styles.insert_imports(&a, std::slice::from_ref(&b)));
+        assert!(styles.insert_imports(&b, &[c.clone(), d.clone()]));
+        let weights: Vec<_> = [&c, &d, &b, &a].iter().map(|h| styles.weight(h)).collect();
+        assert_eq!(weights, vec![1, 2, 3, 4]);
+        assert_eq!(styles.insert(handle(5)), 5);
+    }
+
+    #[test]
+    fn styles_remove_and_replace() {
+        let handle = |id| Handle::<StyleSheet>::weak_from_u128(id);
+        let (a, b, c, d) = (handle(1), handle(2), handle(3), handle(4));
+        let mut styles = Styles::default();
+        styles.insert(a.clone());
+        styles.insert(b.clone());
+        styles.insert(d.clone());
+        // `b` imports `c` and `d`, `d` is inserted explicitly
+        styles.insert_imports(&b, &[c.clone(), d.clone()]);
+
+        // imported `c` goes away with `b`
+        assert!(styles.remove(&b));
+        assert!(!styles.remove(&b));
+        assert!(styles.is_removed(&b));
+        assert!(styles.is_removed(&c));
+        let mut handles: Vec<_> = styles.iter().cloned().collect();
+        handles.sort_by_key(|h| styles.weight(h));
+        assert_eq!(handles, vec![a.clone(), d.clone()]);
+
+        // the new stylesheet takes the place of the old one
+        let weight = styles.weight(&a);
+        assert_eq!(styles.replace(&a, b.clone()), weight);
+        assert_eq!(styles.weight(&b), weight);
+        assert!(!styles.is_removed(&b));
+        assert_eq!(styles.weight(&a), 0);
+
+        // named stylesheets replace each other
+        let (dark, light) = (handle(5), handle(6));
+        let weight = styles.insert_named("theme".to_string(), dark.clone());
+        styles.insert(handle(7));
+        assert_eq!(
+            styles.insert_named("theme".to_string(), light.clone()),
+            weight
+        );
+        assert_eq!(styles.named("theme"), Some(&light));
+        assert_eq!(styles.weight(&dark), 0);
+        assert!(styles.remove(&light));
+        assert_eq!(styles.named("theme"), None);
     }
 }
@@ -1,20 +1,30 @@
-use bevy::log::*;
+use bevy::utils::HashMap;
 use smallvec::{smallvec, SmallVec};
+use std::cell::RefCell;
 
 use cssparser::*;
 use tagstr::{AsTag, Tag};
 
 use crate::{
-    eml::Variant, ess::PropertyExtractor, ess::PropertyTransformer, ess::Selector,
-    ess::SelectorElement, ess::StyleProperty, ess::StyleRule, ElementsError,
+    eml::Variant, ess::AttributeMatcher, ess::Keyframe, ess::Keyframes, ess::MediaCondition,
+    ess::MediaFeature, ess::MediaOrientation, ess::MediaQuery, ess::MediaRange,
+    ess::PropertyExtractor, ess::PropertyTransformer, ess::PropertyValue, ess::Selector,
+    ess::SelectorElement, ess::SelectorElements, ess::StyleProperty, ess::StyleRule, ElementsError,
 };
 
+use super::impls::text::{FontFamilyParser, FontWeightParser};
+use super::parse::IdentifierParser;
+use super::{FontFace, FontStyle, PropertyParser as _, StylePropertyMethods};
+
+use super::{Diagnostic, Severity};
+
 use super::StylePropertyFunction;
 use super::StylePropertyToken;
 
 pub struct StyleSheetParser {
     transformer: PropertyTransformer,
     extractor: PropertyExtractor,
+    diagnostics: RefCell<Vec<(Severity, String, SourceLocation)>>,
 }
 
 impl StyleSheetParser {
@@ -22,30 +32,264 @@ impl StyleSheetParser {
         StyleSheetParser {
             extractor,
             transformer,
+            diagnostics: Default::default(),
         }
     }
     pub fn parse(&self, content: &str) -> SmallVec<[StyleRule; 8]> {
+        self.parse_statements(content)
+            .into_iter()
+            .flat_map(|statement| match statement {
+                Statement::Rules(rules) => rules,
+                _ => smallvec![],
+            })
+            .collect()
+    }
+
+    /// Parses the `content` and returns the top-level statements
+    /// (rules, `@import`, `@keyframes` and `@font-face`) in the order of definition.
+    /// The errors and the warnings are logged.
+    pub fn parse_statements(&self, content: &str) -> Vec<Statement> {
+        let (statements, diagnostics) = self.parse_with_diagnostics(content);
+        diagnostics.iter().for_each(Diagnostic::log);
+        statements
+    }
+
+    /// Same as [`StyleSheetParser::parse_statements`], but returns the errors
+    /// and the warnings ordered by location instead of logging them.
+    pub fn parse_with_diagnostics(&self, content: &str) -> (Vec<Statement>, Vec<Diagnostic>) {
         let mut input = ParserInput::new(content);
         let mut parser = Parser::new(&mut input);
-        RuleListParser::new_for_stylesheet(&mut parser, self)
+        let mut statements = vec![];
+        for result in RuleListParser::new_for_stylesheet(&mut parser, self) {
+            match result {
+                Ok(statement) => statements.push(statement),
+                Err((err, _)) => self.report_error("Failed to parse rule", err),
+            }
+        }
+        let mut diagnostics: Vec<_> = self
+            .diagnostics
+            .take()
             .into_iter()
-            .filter_map(|result| match result {
-                Ok(rule) => Some(rule),
-                Err((err, rule)) => {
-                    error!(
-                        "Failed to parse rule: {}. Error: {}",
-                        rule,
-                        format_error(err)
+            .map(|(severity, message, location)| {
+                Diagnostic::new(severity, message, content, location)
+            })
+            .collect();
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        (statements, diagnostics)
+    }
+
+    fn report(&self, severity: Severity, message: String, location: SourceLocation) {
+        self.diagnostics
+            .borrow_mut()
+            .push((severity, message, location));
+    }
+
+    fn report_error(&self, context: &str, error: ParseError<ElementsError>) {
+        let location = error.location;
+        let message = format!("{context}: {}", format_error(error));
+        self.report(Severity::Error, message, location);
+    }
+
+    /// Parses the block of declarations: compound properties
+    /// are extracted, the rest of them are transformed. Returns
+    /// the regular declarations and the ones marked with `!important`.
+    fn parse_declarations<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<(Declarations, Declarations), ParseError<'i, ElementsError>> {
+        let mut regular = HashMap::default();
+        let mut important = HashMap::default();
+
+        let mut declarations = DeclarationListParser::new(input, PropertyParser);
+        loop {
+            declarations.input.skip_whitespace();
+            let location = declarations.input.current_source_location();
+            let Some(property) = declarations.next() else {
+                break;
+            };
+            match property {
+                Ok((name, property, is_important)) => {
+                    let variant = Variant::style(property);
+                    let warning = self
+                        .extractor
+                        .warning(name, &variant)
+                        .or_else(|| self.transformer.warning(name, &variant));
+                    if let Some(warning) = warning {
+                        self.report(Severity::Warning, warning, location);
+                    }
+                    let properties = if is_important {
+                        &mut important
+                    } else {
+                        &mut regular
+                    };
+                    if self.extractor.is_compound_property(name) {
+                        let extracted = match self.extractor.extract(name, variant) {
+                            Err(e) => return Err(location.new_custom_error(e)),
+                            Ok(extracted) => extracted,
+                        };
+                        for (name, property) in extracted {
+                            properties.insert(name, property);
+                        }
+                    } else {
+                        match self.transformer.transform(name, variant) {
+                            Ok(variant) => {
+                                properties.insert(name, variant);
+                            }
+                            Err(e) => return Err(location.new_custom_error(e)),
+                        }
+                    }
+                }
+                Err((err, _)) => self.report_error("Failed to parse declaration", err),
+            }
+        }
+        Ok((regular, important))
+    }
+
+    /// Parses the rules nested into `@media` or `@layer` block
+    fn parse_nested_rules<'i, 't>(&self, input: &mut Parser<'i, 't>) -> SmallVec<[StyleRule; 2]> {
+        let mut rules: SmallVec<[StyleRule; 2]> = smallvec![];
+        let mut nested = RuleListParser::new_for_nested_rule(input, self);
+        loop {
+            nested.input.skip_whitespace();
+            let location = nested.input.current_source_location();
+            let Some(result) = nested.next() else {
+                break;
+            };
+            let ignored = |message| self.report(Severity::Warning, message, location);
+            match result {
+                Ok(Statement::Rules(nested)) => rules.extend(nested),
+                Ok(Statement::Import(path)) => ignored(format!(
+                    "@import \"{path}\" is allowed only at the top level of stylesheet"
+                )),
+                Ok(Statement::Keyframes(keyframes)) => ignored(format!(
+                    "@keyframes {} is allowed only at the top level of stylesheet",
+                    keyframes.name
+                )),
+                Ok(Statement::FontFace(face)) => ignored(format!(
+                    "@font-face of \"{}\" is allowed only at the top level of stylesheet",
+                    face.family
+                )),
+                Ok(Statement::Layers(layers)) => ignored(format!(
+                    "@layer {} statement is allowed only at the top level of stylesheet",
+                    layers
+                        .iter()
+                        .map(|l| l.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+                Err((err, _)) => self.report_error("Failed to parse rule", err),
+            }
+        }
+        rules
+    }
+
+    /// Parses the descriptors of `@font-face` rule. `font-family` and `src`
+    /// are required, `font-weight` and `font-style` are `normal` by default.
+    fn parse_font_face<'i, 't>(
+        &self,
+        start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<FontFace, ParseError<'i, ElementsError>> {
+        let mut family = None;
+        let mut src = None;
+        let mut weight = 400;
+        let mut style = FontStyle::Normal;
+        let mut descriptors = DeclarationListParser::new(input, PropertyParser);
+        loop {
+            descriptors.input.skip_whitespace();
+            let location = descriptors.input.current_source_location();
+            let Some(descriptor) = descriptors.next() else {
+                break;
+            };
+            let (name, value) = match descriptor {
+                Ok((name, value, _)) => (name, value),
+                Err((err, _)) => {
+                    self.report_error("Failed to parse @font-face descriptor", err);
+                    continue;
+                }
+            };
+            let parsed = match name.as_str() {
+                "font-family" => FontFamilyParser::parse(&value).and_then(|mut families| {
+                    if families.len() != 1 {
+                        return Err(ElementsError::InvalidPropertyValue(format!(
+                            "Expected single font family, got `{}`",
+                            value.to_string()
+                        )));
+                    }
+                    family = families.pop();
+                    Ok(())
+                }),
+                "src" => font_src(&value).map(|path| src = Some(path)),
+                "font-weight" => FontWeightParser::parse(&value).map(|parsed| weight = parsed),
+                "font-style" => {
+                    IdentifierParser::<FontStyle>::parse(&value).map(|parsed| style = parsed)
+                }
+                name => {
+                    self.report(
+                        Severity::Warning,
+                        format!("Unknown @font-face descriptor `{name}` is ignored"),
+                        location,
                     );
-                    None
+                    Ok(())
                 }
-            })
-            .collect()
+            };
+            if let Err(err) = parsed {
+                self.report_error(
+                    "Failed to parse @font-face descriptor",
+                    location.new_custom_error(err),
+                );
+            }
+        }
+        let (Some(family), Some(src)) = (family, src) else {
+            let error = ElementsError::InvalidPropertyValue(
+                "@font-face requires font-family and src".to_string(),
+            );
+            return Err(start.source_location().new_custom_error(error));
+        };
+        Ok(FontFace {
+            family,
+            src,
+            weight,
+            style,
+        })
     }
 }
 
+/// Parses `src` descriptor of `@font-face` rule: `"path"` or `url("path")`
+fn font_src(value: &StyleProperty) -> Result<String, ElementsError> {
+    match value.as_slice() {
+        [StylePropertyToken::String(path)] => Ok(path.clone()),
+        [StylePropertyToken::Function(url)] if url.name == "url" => match url.args.as_slice() {
+            [StylePropertyToken::String(path)] => Ok(path.clone()),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected url($string), got `{}`",
+                value.to_string()
+            ))),
+        },
+        _ => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $string|url($string), got `{}`",
+            value.to_string()
+        ))),
+    }
+}
+
+/// Values of the declarations block by property name
+type Declarations = HashMap<Tag, PropertyValue>;
+
+/// Top-level statement of the stylesheet
+#[allow(clippy::large_enum_variant)]
+pub enum Statement {
+    Rules(SmallVec<[StyleRule; 2]>),
+    Import(String),
+    Keyframes(Keyframes),
+    FontFace(FontFace),
+    /// `@layer base, widgets, app;` statement declaring the order of layers
+    Layers(Vec<Tag>),
+}
+
+/// Describes the parse `error` without its location
 fn format_error(error: ParseError<ElementsError>) -> String {
-    let error_description = match error.kind {
+    match error.kind {
         cssparser::ParseErrorKind::Basic(b) => match b {
             cssparser::BasicParseErrorKind::UnexpectedToken(token) => {
                 format!("Unexpected token {}", token.to_css_string())
@@ -60,12 +304,7 @@ fn format_error(error: ParseError<ElementsError>) -> String {
             cssparser::BasicParseErrorKind::QualifiedRuleInvalid => "Invalid rule".to_string(),
         },
         cssparser::ParseErrorKind::Custom(c) => c.to_string(),
-    };
-
-    format!(
-        "{} at {}:{}",
-        error_description, error.location.line, error.location.column
-    )
+    }
 }
 
 #[derive(Default)]
@@ -76,87 +315,230 @@ enum NextElement {
     Attribute,
 }
 
-impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
-    type Prelude = Selector;
-    type QualifiedRule = StyleRule;
-    type Error = ElementsError;
+/// Structural pseudo-classes (`:first-child`, `:only-of-type`, ...)
+/// or the element state (`:hover`, `:pressed`, ...)
+fn pseudo_class(name: &str) -> SelectorElement {
+    let nth = |of_type, from_end| SelectorElement::Nth {
+        a: 0,
+        b: 1,
+        of_type,
+        from_end,
+    };
+    match name {
+        "first-child" => nth(false, false),
+        "last-child" => nth(false, true),
+        "first-of-type" => nth(true, false),
+        "last-of-type" => nth(true, true),
+        "only-child" => SelectorElement::Only { of_type: false },
+        "only-of-type" => SelectorElement::Only { of_type: true },
+        "scope" => SelectorElement::Scope,
+        state => SelectorElement::State(state.as_tag()),
+    }
+}
 
-    fn parse_prelude<'t>(
-        &mut self,
-        input: &mut Parser<'i, 't>,
-    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
-        let mut elements: SmallVec<[SelectorElement; 8]> = smallvec![];
+/// Parses a single complex selector until the end of the input
+/// (or until the comma when called from `parse_comma_separated`).
+fn parse_selector<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Selector, ParseError<'i, ElementsError>> {
+    let mut elements: SmallVec<[SelectorElement; 8]> = smallvec![];
 
-        let mut next = NextElement::Tag;
+    let mut next = NextElement::Tag;
 
-        while let Ok(token) = input.next_including_whitespace() {
-            use cssparser::Token::*;
-            match token {
-                Ident(v) => {
-                    match next {
-                        NextElement::Tag => {
-                            elements.insert(0, SelectorElement::Tag(v.to_string().as_tag()))
-                        }
-                        NextElement::Class => {
-                            elements.insert(0, SelectorElement::Class(v.to_string().as_tag()))
-                        }
-                        NextElement::Attribute => {
-                            elements.insert(0, SelectorElement::State(v.to_string().as_tag()))
-                        }
-                    };
-                    next = NextElement::Tag;
-                }
-                IDHash(v) => {
-                    if v.is_empty() {
-                        return Err(input.new_custom_error(ElementsError::InvalidSelector));
-                    } else {
-                        elements.insert(0, SelectorElement::Id(v.to_string().as_tag()));
+    while let Ok(token) = input.next_including_whitespace() {
+        use cssparser::Token::*;
+        match token {
+            Ident(v) => {
+                match next {
+                    NextElement::Tag => {
+                        elements.insert(0, SelectorElement::Tag(v.to_string().as_tag()))
                     }
-                }
-                WhiteSpace(_) => {
-                    if let Some(token) = elements.first() {
-                        if token.is_separator() {
-                            continue;
-                        }
+                    NextElement::Class => {
+                        elements.insert(0, SelectorElement::Class(v.to_string().as_tag()))
                     }
-                    elements.insert(0, SelectorElement::AnyChild);
+                    NextElement::Attribute => elements.insert(0, pseudo_class(v)),
+                };
+                next = NextElement::Tag;
+            }
+            Function(name) if matches!(next, NextElement::Attribute) && name.as_ref() == "not" => {
+                let compounds = input.parse_nested_block(|input| {
+                    input.parse_entirely(|input| input.parse_comma_separated(parse_compound))
+                })?;
+                elements.insert(0, SelectorElement::Not(compounds));
+                next = NextElement::Tag;
+            }
+            Function(name) if matches!(next, NextElement::Attribute) && name.as_ref() == "has" => {
+                let selectors = input.parse_nested_block(|input| {
+                    input.parse_entirely(|input| {
+                        input.parse_comma_separated(parse_relative_selector)
+                    })
+                })?;
+                elements.insert(0, SelectorElement::Has(selectors));
+                next = NextElement::Tag;
+            }
+            Function(name) if matches!(next, NextElement::Attribute) => {
+                let (of_type, from_end) = match name.as_ref() {
+                    "nth-child" => (false, false),
+                    "nth-last-child" => (false, true),
+                    "nth-of-type" => (true, false),
+                    "nth-last-of-type" => (true, true),
+                    _ => return Err(input.new_custom_error(ElementsError::UnsupportedSelector)),
+                };
+                let (a, b) = input.parse_nested_block(|input| {
+                    input.parse_entirely(|input| parse_nth(input).map_err(Into::into))
+                })?;
+                elements.insert(
+                    0,
+                    SelectorElement::Nth {
+                        a,
+                        b,
+                        of_type,
+                        from_end,
+                    },
+                );
+                next = NextElement::Tag;
+            }
+            SquareBracketBlock => {
+                let attribute = input.parse_nested_block(parse_attribute)?;
+                elements.insert(0, attribute);
+            }
+            IDHash(v) => {
+                if v.is_empty() {
+                    return Err(input.new_custom_error(ElementsError::InvalidSelector));
+                } else {
+                    elements.insert(0, SelectorElement::Id(v.to_string().as_tag()));
                 }
-                Delim(c) if *c == '.' => next = NextElement::Class,
-                Delim(c) if *c == '*' => elements.insert(0, SelectorElement::Any),
-                Delim(c) if *c == '>' => {
-                    if let Some(token) = elements.first() {
-                        if token.is_any_child() {
-                            elements[0] = SelectorElement::DirectChild;
-                            continue;
-                        }
+            }
+            WhiteSpace(_) => {
+                if let Some(token) = elements.first() {
+                    if token.is_separator() {
+                        continue;
                     }
-                    elements.insert(0, SelectorElement::DirectChild);
                 }
-                Colon => next = NextElement::Attribute,
-                _ => {
-                    warn!("Unexpected token: {:?}", token);
-                    let token = token.to_css_string();
-                    return Err(input.new_custom_error(ElementsError::UnexpectedToken(token)));
+                elements.insert(0, SelectorElement::AnyChild);
+            }
+            Delim(c) if *c == '.' => next = NextElement::Class,
+            Delim(c) if *c == '*' => elements.insert(0, SelectorElement::Any),
+            Delim(c) if *c == '>' || *c == '+' || *c == '~' => {
+                let combinator = match c {
+                    '>' => SelectorElement::DirectChild,
+                    '+' => SelectorElement::AdjacentSibling,
+                    _ => SelectorElement::GeneralSibling,
+                };
+                if let Some(token) = elements.first() {
+                    if token.is_any_child() {
+                        elements[0] = combinator;
+                        continue;
+                    }
                 }
+                elements.insert(0, combinator);
+            }
+            Colon => next = NextElement::Attribute,
+            _ => {
+                let token = token.to_css_string();
+                return Err(input.new_custom_error(ElementsError::UnexpectedToken(token)));
             }
         }
+    }
 
-        if elements.is_empty() {
-            return Err(input.new_custom_error(ElementsError::InvalidSelector));
+    // Remove noise the trailing white spaces, if any
+    while !elements.is_empty() {
+        if elements.last().unwrap().is_any_child() {
+            elements.pop();
+        } else if elements.first().unwrap().is_any_child() {
+            elements.remove(0);
+        } else {
+            break;
         }
+    }
 
-        // Remove noise the trailing white spaces, if any
-        while !elements.is_empty() {
-            if elements.last().unwrap().is_any_child() {
-                elements.pop();
-            } else if elements.first().unwrap().is_any_child() {
-                elements.remove(0);
-            } else {
-                break;
-            }
+    // Selector can't start or end with combinator
+    if elements.is_empty()
+        || elements.first().unwrap().is_separator()
+        || elements.last().unwrap().is_separator()
+    {
+        return Err(input.new_custom_error(ElementsError::InvalidSelector));
+    }
+
+    Ok(Selector::new(elements))
+}
+
+/// Parses attribute selector content (without brackets):
+/// `mode`, `mode=toggle`, `value^="tab"`
+fn parse_attribute<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<SelectorElement, ParseError<'i, ElementsError>> {
+    input.skip_whitespace();
+    let name = input.expect_ident()?.to_string().as_tag();
+    input.skip_whitespace();
+    if input.is_exhausted() {
+        return Ok(SelectorElement::Attribute(name, AttributeMatcher::Exists));
+    }
+    let location = input.current_source_location();
+    let matcher: fn(String) -> AttributeMatcher = match input.next()? {
+        Token::Delim('=') => AttributeMatcher::Equals,
+        Token::IncludeMatch => AttributeMatcher::Includes,
+        Token::DashMatch => AttributeMatcher::DashMatch,
+        Token::PrefixMatch => AttributeMatcher::Prefix,
+        Token::SuffixMatch => AttributeMatcher::Suffix,
+        Token::SubstringMatch => AttributeMatcher::Substring,
+        token => {
+            let token = token.to_css_string();
+            return Err(location.new_custom_error(ElementsError::UnexpectedToken(token)));
         }
+    };
+    input.skip_whitespace();
+    let value = input.expect_ident_or_string()?.to_string();
+    input.skip_whitespace();
+    input.expect_exhausted()?;
+    Ok(SelectorElement::Attribute(name, matcher(value)))
+}
 
-        Ok(Selector::new(elements))
+/// Parses compound selector (without combinators): `span.class:hover`
+fn parse_compound<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<SelectorElements, ParseError<'i, ElementsError>> {
+    let location = input.current_source_location();
+    let selector = parse_selector(input)?;
+    if selector.elements.iter().any(|e| e.is_separator()) {
+        return Err(location.new_custom_error(ElementsError::InvalidSelector));
+    }
+    Ok(selector.elements)
+}
+
+/// Parses relative selector (`> .icon`, `+ .tab`, `.child`) and anchors
+/// it with `:scope` element
+fn parse_relative_selector<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Selector, ParseError<'i, ElementsError>> {
+    input.skip_whitespace();
+    let combinator = input
+        .try_parse(|input| match input.next()? {
+            Token::Delim('>') => Ok(SelectorElement::DirectChild),
+            Token::Delim('+') => Ok(SelectorElement::AdjacentSibling),
+            Token::Delim('~') => Ok(SelectorElement::GeneralSibling),
+            token => {
+                let token = token.clone();
+                Err(input.new_unexpected_token_error::<()>(token))
+            }
+        })
+        .unwrap_or(SelectorElement::AnyChild);
+    let mut elements = parse_selector(input)?.elements;
+    elements.push(combinator);
+    elements.push(SelectorElement::Scope);
+    Ok(Selector::new(elements))
+}
+
+impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
+    type Prelude = SmallVec<[Selector; 2]>;
+    type QualifiedRule = Statement;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        Ok(input.parse_comma_separated(parse_selector)?.into())
     }
 
     fn parse_block<'t>(
@@ -165,50 +547,327 @@ impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
         _start: &cssparser::ParserState,
         input: &mut Parser<'i, 't>,
     ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
-        let mut rule = StyleRule {
-            selector: prelude,
-            properties: Default::default(),
-        };
+        let (properties, important) = self.parse_declarations(input)?;
+
+        // Every selector of the list produces its own rule, so the usual
+        // weight & resolving order is applied for each of them. Declarations
+        // marked with `!important` go to the separate rule with the same selector.
+        let mut rules: SmallVec<[StyleRule; 2]> = smallvec![];
+        for selector in prelude {
+            if !important.is_empty() {
+                rules.push(StyleRule {
+                    selector: selector.clone(),
+                    properties: important.clone(),
+                    media: smallvec![],
+                    important: true,
+                    layer: None,
+                });
+            }
+            rules.push(StyleRule {
+                selector,
+                properties: properties.clone(),
+                media: smallvec![],
+                important: false,
+                layer: None,
+            });
+        }
+        Ok(Statement::Rules(rules))
+    }
+}
 
-        for property in DeclarationListParser::new(input, PropertyParser) {
-            match property {
-                Ok((name, property)) => {
-                    if self.extractor.is_compound_property(name) {
-                        let extracted = match self.extractor.extract(name, Variant::style(property))
-                        {
-                            Err(e) => return Err(input.new_custom_error(e)),
-                            Ok(extracted) => extracted,
-                        };
-                        for (name, property) in extracted {
-                            rule.properties.insert(name, property);
-                        }
-                    } else {
-                        match self.transformer.transform(name, Variant::style(property)) {
-                            Ok(variant) => {
-                                rule.properties.insert(name, variant);
-                            }
-                            Err(e) => return Err(input.new_custom_error(e)),
+pub enum AtRulePrelude {
+    Media(MediaQuery),
+    Import(String),
+    Keyframes(Tag),
+    Layer(SmallVec<[Tag; 2]>),
+    FontFace,
+}
+
+impl<'i> AtRuleParser<'i> for &StyleSheetParser {
+    type Prelude = AtRulePrelude;
+    type AtRule = Statement;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        match name.as_ref() {
+            "media" => Ok(AtRulePrelude::Media(parse_media_query(input)?)),
+            "import" => {
+                let path = input.expect_url_or_string()?.to_string();
+                input.expect_exhausted()?;
+                Ok(AtRulePrelude::Import(path))
+            }
+            "keyframes" => {
+                let location = input.current_source_location();
+                let name = match input.next()? {
+                    Token::Ident(name) | Token::QuotedString(name) => name.as_ref().as_tag(),
+                    token => {
+                        let token = token.clone();
+                        return Err(location.new_unexpected_token_error(token));
+                    }
+                };
+                input.expect_exhausted()?;
+                Ok(AtRulePrelude::Keyframes(name))
+            }
+            "font-face" => {
+                input.expect_exhausted()?;
+                Ok(AtRulePrelude::FontFace)
+            }
+            "layer" => {
+                let names = input
+                    .parse_comma_separated(|input| Ok(input.expect_ident()?.as_ref().as_tag()))?;
+                Ok(AtRulePrelude::Layer(names.into()))
+            }
+            _ => Err(input.new_error(BasicParseErrorKind::AtRuleInvalid(name))),
+        }
+    }
+
+    fn rule_without_block(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+    ) -> Result<Self::AtRule, ()> {
+        match prelude {
+            AtRulePrelude::Import(path) => Ok(Statement::Import(path)),
+            AtRulePrelude::Layer(names) => Ok(Statement::Layers(names.into_vec())),
+            _ => Err(()),
+        }
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, Self::Error>> {
+        let query = match prelude {
+            AtRulePrelude::Media(query) => query,
+            AtRulePrelude::Keyframes(name) => {
+                let mut frames = vec![];
+                for result in RuleListParser::new_for_nested_rule(input, KeyframesParser(self)) {
+                    match result {
+                        Ok(parsed) => frames.extend(parsed),
+                        Err((err, _)) => self.report_error("Failed to parse keyframe", err),
+                    }
+                }
+                return Ok(Statement::Keyframes(Keyframes::new(name, frames)));
+            }
+            AtRulePrelude::FontFace => {
+                return Ok(Statement::FontFace(self.parse_font_face(start, input)?));
+            }
+            // the block of `@layer` rule declares a single layer
+            AtRulePrelude::Layer(names) if names.len() == 1 => {
+                let mut rules = self.parse_nested_rules(input);
+                let mut nested_layers: SmallVec<[Tag; 1]> = smallvec![];
+                rules.retain(|rule| {
+                    if let Some(nested) = rule.layer {
+                        if !nested_layers.contains(&nested) {
+                            nested_layers.push(nested);
                         }
                     }
+                    rule.layer.is_none()
+                });
+                for nested in nested_layers {
+                    self.report(
+                        Severity::Warning,
+                        format!(
+                            "Nested @layer {nested} in @layer {} isn't supported",
+                            names[0]
+                        ),
+                        start.source_location(),
+                    );
+                }
+                for rule in rules.iter_mut() {
+                    rule.layer = Some(names[0]);
                 }
-                Err((err, a)) => println!("Failed: {:?} ({})", err, a),
+                return Ok(Statement::Rules(rules));
+            }
+            AtRulePrelude::Import(_) | AtRulePrelude::Layer(_) => {
+                return Err(input.new_error(BasicParseErrorKind::AtRuleBodyInvalid))
             }
+        };
+        let mut rules = self.parse_nested_rules(input);
+        for rule in rules.iter_mut() {
+            rule.media.insert(0, query.clone());
         }
+        Ok(Statement::Rules(rules))
+    }
+}
 
-        Ok(rule)
+/// Parses the keyframes of `@keyframes` rule: `from`, `to` or
+/// percentages with the block of declarations
+struct KeyframesParser<'a>(&'a StyleSheetParser);
+
+impl<'a, 'i> QualifiedRuleParser<'i> for KeyframesParser<'a> {
+    type Prelude = SmallVec<[f32; 1]>;
+    type QualifiedRule = SmallVec<[Keyframe; 1]>;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        let offsets = input.parse_comma_separated(|input| {
+            let location = input.current_source_location();
+            match input.next()? {
+                Token::Ident(ident) if ident.eq_ignore_ascii_case("from") => Ok(0.),
+                Token::Ident(ident) if ident.eq_ignore_ascii_case("to") => Ok(1.),
+                Token::Percentage { unit_value, .. } if (0. ..=1.).contains(unit_value) => {
+                    Ok(*unit_value)
+                }
+                token => {
+                    let token = token.clone();
+                    Err(location.new_unexpected_token_error(token))
+                }
+            }
+        })?;
+        Ok(offsets.into())
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
+        // `!important` declarations are ignored within keyframes
+        let (properties, important) = self.0.parse_declarations(input)?;
+        if !important.is_empty() {
+            self.0.report(
+                Severity::Warning,
+                "!important declarations are ignored within @keyframes".to_string(),
+                start.source_location(),
+            );
+        }
+        Ok(prelude
+            .into_iter()
+            .map(|offset| Keyframe {
+                offset,
+                properties: properties.clone(),
+            })
+            .collect())
     }
 }
 
-impl<'i> AtRuleParser<'i> for &StyleSheetParser {
+impl<'a, 'i> AtRuleParser<'i> for KeyframesParser<'a> {
     type Prelude = ();
-    type AtRule = StyleRule;
+    type AtRule = SmallVec<[Keyframe; 1]>;
     type Error = ElementsError;
 }
 
+/// Parses the prelude of `@media` rule:
+/// `screen and (max-width: 800px), (orientation: portrait)`
+fn parse_media_query<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<MediaQuery, ParseError<'i, ElementsError>> {
+    let conditions = input.parse_comma_separated(|input| {
+        let mut condition = MediaCondition {
+            screen: true,
+            ..Default::default()
+        };
+        let mut expect_feature = true;
+        if input
+            .try_parse(|input| input.expect_ident_matching("not"))
+            .is_ok()
+        {
+            condition.negated = true;
+        } else {
+            let _ = input.try_parse(|input| input.expect_ident_matching("only"));
+        }
+        if let Ok(media_type) = input.try_parse(|input| input.expect_ident_cloned()) {
+            condition.screen = matches!(media_type.as_ref(), "all" | "screen");
+            expect_feature = false;
+        }
+        while !input.is_exhausted() {
+            if !expect_feature {
+                input.expect_ident_matching("and")?;
+            }
+            input.expect_parenthesis_block()?;
+            let feature = input.parse_nested_block(parse_media_feature)?;
+            condition.features.push(feature);
+            expect_feature = false;
+        }
+        Ok(condition)
+    })?;
+    Ok(MediaQuery(conditions.into()))
+}
+
+/// Parses media feature (without parentheses): `max-width: 800px`
+fn parse_media_feature<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<MediaFeature, ParseError<'i, ElementsError>> {
+    let location = input.current_source_location();
+    let feature = input.expect_ident_cloned()?;
+    input.expect_colon()?;
+    let (range, name): (fn(f32) -> MediaRange, _) = match feature.as_ref() {
+        name if name.starts_with("min-") => (MediaRange::Min, &name[4..]),
+        name if name.starts_with("max-") => (MediaRange::Max, &name[4..]),
+        name => (MediaRange::Exact, name),
+    };
+    let invalid = |value: &dyn std::fmt::Debug| {
+        location.new_custom_error(ElementsError::InvalidPropertyValue(format!(
+            "Invalid value of `{name}` media feature: {value:?}"
+        )))
+    };
+    let feature = match name {
+        "width" | "height" => {
+            let value = match input.next()?.clone() {
+                Token::Dimension { value, unit, .. } if unit.eq_ignore_ascii_case("px") => value,
+                Token::Number { value, .. } if value == 0. => value,
+                token => return Err(invalid(&token)),
+            };
+            if name == "width" {
+                MediaFeature::Width(range(value))
+            } else {
+                MediaFeature::Height(range(value))
+            }
+        }
+        "aspect-ratio" => {
+            let width = input.expect_number()?;
+            input.expect_delim('/')?;
+            let height = input.expect_number()?;
+            MediaFeature::AspectRatio(range(width / height))
+        }
+        "resolution" => {
+            let value = match input.next()?.clone() {
+                Token::Dimension { value, unit, .. } => match unit.as_ref() {
+                    "dppx" | "x" => value,
+                    "dpi" => value / 96.,
+                    "dpcm" => value * 2.54 / 96.,
+                    _ => return Err(invalid(&unit)),
+                },
+                token => return Err(invalid(&token)),
+            };
+            MediaFeature::Resolution(range(value))
+        }
+        "orientation" if feature.as_ref() == name => {
+            let value = input.expect_ident_cloned()?;
+            match value.as_ref() {
+                "portrait" => MediaFeature::Orientation(MediaOrientation::Portrait),
+                "landscape" => MediaFeature::Orientation(MediaOrientation::Landscape),
+                _ => return Err(invalid(&value)),
+            }
+        }
+        _ => {
+            return Err(
+                location.new_custom_error(ElementsError::UnsupportedProperty(format!(
+                    "Unsupported media feature `{name}`"
+                ))),
+            )
+        }
+    };
+    input.expect_exhausted()?;
+    Ok(feature)
+}
+
 struct PropertyParser;
 
 impl<'i> DeclarationParser<'i> for PropertyParser {
-    type Declaration = (Tag, StyleProperty);
+    /// The name, the value and the `!important` flag of the declaration
+    type Declaration = (Tag, StyleProperty, bool);
 
     type Error = ElementsError;
 
@@ -217,16 +876,16 @@ impl<'i> DeclarationParser<'i> for PropertyParser {
         name: cssparser::CowRcStr<'i>,
         parser: &mut Parser<'i, 't>,
     ) -> Result<Self::Declaration, ParseError<'i, ElementsError>> {
-        Ok((
-            name.to_string().as_tag(),
-            StyleProperty(parse_values(parser)?),
-        ))
+        let values = parser.parse_until_before(Delimiter::Bang, parse_values)?;
+        let important = parser.try_parse(parse_important).is_ok();
+        parser.expect_exhausted()?;
+        Ok((name.to_string().as_tag(), StyleProperty(values), important))
     }
 }
 
 impl<'i> AtRuleParser<'i> for PropertyParser {
     type Prelude = ();
-    type AtRule = (Tag, StyleProperty);
+    type AtRule = (Tag, StyleProperty, bool);
     type Error = ElementsError;
 }
 
@@ -252,7 +911,12 @@ fn parse_value<'i, 'tt>(
     let loc = parser.current_source_location();
     if let Ok(token) = parser.next().cloned() {
         match token {
-            Token::Function(func) => {
+            // parentheses are stored as a function without name: `calc((1px + 2px) * 2)`
+            Token::Function(_) | Token::ParenthesisBlock => {
+                let name = match &token {
+                    Token::Function(func) => func.to_string(),
+                    _ => String::new(),
+                };
                 let args = parser.parse_nested_block(|parser| {
                     parser.parse_comma_separated(|parser| {
                         let mut tokens = parse_values(parser)?;
@@ -264,7 +928,7 @@ fn parse_value<'i, 'tt>(
                     })
                 })?;
                 Ok(StylePropertyToken::Function(StylePropertyFunction {
-                    name: func.to_string(),
+                    name,
                     args,
                 }))
             }
@@ -282,6 +946,7 @@ fn parse_value<'i, 'tt>(
             )),
             Token::Comma => Ok(StylePropertyToken::Comma),
             Token::Delim(d) if d == '/' => Ok(StylePropertyToken::Slash),
+            Token::Delim(d @ ('+' | '-' | '*')) => Ok(StylePropertyToken::Operator(d)),
             token => Err(loc.new_custom_error(ElementsError::UnexpectedToken(format!(
                 "Invalid token: {:?}",
                 token
@@ -308,6 +973,24 @@ pub fn parse_style_property_value<T: AsRef<str>>(value: T) -> Result<StyleProper
     }
 }
 
+pub(crate) fn parse_selector_list<T: AsRef<str>>(
+    value: T,
+) -> Result<SmallVec<[Selector; 2]>, ElementsError> {
+    let mut input = cssparser::ParserInput::new(value.as_ref());
+    let mut parser = cssparser::Parser::new(&mut input);
+    match parser.parse_entirely(|input| input.parse_comma_separated(parse_selector)) {
+        Ok(selectors) => Ok(selectors.into()),
+        Err(ParseError {
+            kind: ParseErrorKind::Custom(err),
+            ..
+        }) => Err(err),
+        Err(ParseError { location, .. }) => Err(ElementsError::UnexpectedToken(format!(
+            "Invalid selector at {}:{}",
+            location.line, location.column,
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -651,6 +1334,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_selector_list() {
+        let rules = TestParser::new().parse(".a, #b > span,c{d: e}");
+        assert_eq!(rules.len(), 3, "Should have a rule per selector");
+
+        assert_eq!(rules[0].selector.to_string(), ".a");
+        assert_eq!(rules[1].selector.to_string(), "#b > span");
+        assert_eq!(rules[2].selector.to_string(), "c");
+
+        for rule in rules {
+            match rule
+                .properties
+                .get(&"d".as_tag())
+                .expect("Should have a property named \"d\"")
+                .downcast_ref::<StyleProperty>()
+                .unwrap()
+                .iter()
+                .next()
+                .expect("Should have a single property value")
+            {
+                StylePropertyToken::Identifier(e) => assert_eq!(e, "e"),
+                _ => panic!("Should have only a single property value of type identifier"),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_selector_list() {
+        let parser = TestParser::new();
+        assert!(
+            parser.parse(".a, {d: e}").is_empty(),
+            "Should reject the rule with an empty selector in the list"
+        );
+        assert_eq!(
+            parser.parse(".a, $ {d: e} .b {d: e}").len(),
+            1,
+            "Should reject only the rule with invalid selector in the list"
+        );
+    }
+
+    #[test]
+    fn parse_sibling_combinators() {
+        let rules = TestParser::new().parse(".tab:pressed + .panel, a~b, a > b ~ c + d {}");
+        assert_eq!(rules.len(), 3, "Should have a rule per selector");
+
+        assert_eq!(rules[0].selector.to_string(), ".tab:pressed + .panel");
+        assert_eq!(rules[1].selector.to_string(), "a ~ b");
+        assert_eq!(rules[2].selector.to_string(), "a > b ~ c + d");
+
+        assert!(
+            TestParser::new().parse("+ a {} a ~ {}").is_empty(),
+            "Should reject selectors with dangling combinators"
+        );
+    }
+
+    #[test]
+    fn parse_structural_pseudo_classes() {
+        let rules = TestParser::new().parse(
+            "a:first-child, a:last-of-type, a:only-child, a:hover, \
+            a:nth-child(2n + 1), a:nth-last-child(-n+3), a:nth-of-type(even), a:nth-child(4) {}",
+        );
+        let selectors: Vec<_> = rules.iter().map(|r| r.selector.to_string()).collect();
+        assert_eq!(
+            selectors,
+            vec![
+                "a:first-child",
+                "a:last-of-type",
+                "a:only-child",
+                "a:hover",
+                "a:nth-child(2n+1)",
+                "a:nth-last-child(-n+3)",
+                "a:nth-of-type(2n)",
+                "a:nth-child(4)",
+            ]
+        );
+
+        assert!(
+            TestParser::new()
+                .parse("a:nth-child(x) {} a:nth-child(2n 1) {} a:nth-sibling(1) {}")
+                .is_empty(),
+            "Should reject invalid structural pseudo-classes"
+        );
+    }
+
+    #[test]
+    fn parse_negation_and_relational_pseudo_classes() {
+        let rules = TestParser::new().parse(
+            "a:not(.b), a:not(b.c, :hover), a:has(b), a:has(> b), a:has(+ b ~ c d), \
+            a:has(b:not(.c)) {}",
+        );
+        let selectors: Vec<_> = rules.iter().map(|r| r.selector.to_string()).collect();
+        assert_eq!(
+            selectors,
+            vec![
+                "a:not(.b)",
+                "a:not(b.c, :hover)",
+                "a:has(b)",
+                "a:has(> b)",
+                "a:has(+ b ~ c d)",
+                "a:has(b:not(.c))",
+            ]
+        );
+
+        assert!(
+            TestParser::new()
+                .parse("a:not(b c) {} a:not() {} a:has() {} a:has(b >) {}")
+                .is_empty(),
+            "Should reject invalid :not() and :has() arguments"
+        );
+    }
+
+    #[test]
+    fn parse_attribute_selectors() {
+        let rules = TestParser::new().parse(
+            "button[mode=toggle], [value^=\"tab\"], [ disabled ], [a~=b], [a|=b], \
+            [a$='b'], [a*=b] span {}",
+        );
+        let selectors: Vec<_> = rules.iter().map(|r| r.selector.to_string()).collect();
+        assert_eq!(
+            selectors,
+            vec![
+                "button[mode=\"toggle\"]",
+                "[value^=\"tab\"]",
+                "[disabled]",
+                "[a~=\"b\"]",
+                "[a|=\"b\"]",
+                "[a$=\"b\"]",
+                "[a*=\"b\"] span",
+            ]
+        );
+
+        assert!(
+            TestParser::new()
+                .parse("[] {} [a=] {} [a b] {} [a=b c] {} [a>b] {}")
+                .is_empty(),
+            "Should reject invalid attribute selectors"
+        );
+    }
+
     #[test]
     fn parse_compound_properties() {
         let rules = TestParser::new().parse("a { compound: valid }");
@@ -733,4 +1555,294 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn parse_media_rules() {
+        let rules = TestParser::new().parse(
+            "a { b: c } \
+            @media (max-width: 800px), print and (orientation: portrait) { \
+                a, b { c: d } \
+                @media not screen and (min-resolution: 2dppx) { e { f: g } } \
+            } \
+            @media (aspect-ratio: 16/9) and (min-height: 0) {}",
+        );
+        assert_eq!(rules.len(), 4);
+        assert!(rules[0].media.is_empty());
+        let query = &rules[1].media[0];
+        assert_eq!(query, &rules[2].media[0]);
+        assert_eq!(query.0.len(), 2);
+        assert_eq!(
+            query.0[0].features.as_slice(),
+            &[MediaFeature::Width(MediaRange::Max(800.))]
+        );
+        assert!(!query.0[1].screen);
+
+        let nested = &rules[3].media;
+        assert_eq!(nested.len(), 2);
+        assert_eq!(&nested[0], query);
+        assert!(nested[1].0[0].negated);
+        assert_eq!(
+            nested[1].0[0].features.as_slice(),
+            &[MediaFeature::Resolution(MediaRange::Min(2.))]
+        );
+
+        let media = |width, height, scale_factor| crate::ess::Media {
+            width,
+            height,
+            scale_factor,
+        };
+        assert!(query.matches(&media(600., 400., 1.)));
+        assert!(!query.matches(&media(1000., 2000., 1.)));
+        assert!(nested[1].matches(&media(600., 400., 1.)));
+        assert!(!nested[1].matches(&media(600., 400., 2.)));
+
+        assert!(
+            TestParser::new()
+                .parse(
+                    "@media (max-width: 10em) { a {} } @media (hover: hover) { a {} } \
+                    @media (orientation: up) { a {} } @media (width: 1px) (height: 1px) { a {} } \
+                    @font { a {} }"
+                )
+                .is_empty(),
+            "Should reject unsupported media queries"
+        );
+    }
+
+    #[test]
+    fn parse_imports() {
+        let parser = TestParser::new();
+        let parser = StyleSheetParser::new(parser.transformer, parser.extractor);
+        let statements = parser.parse_statements(
+            "@import \"theme/colors.ess\"; @import url(../base.ess); a { b: c } \
+            @media (max-width: 100px) { @import \"nested.ess\"; d { e: f } } \
+            @import; @import \"a.ess\" \"b.ess\";",
+        );
+        let imports: Vec<_> = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Import(path) => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(imports, vec!["theme/colors.ess", "../base.ess"]);
+        let rules: usize = statements
+            .iter()
+            .map(|s| match s {
+                Statement::Rules(rules) => rules.len(),
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(rules, 2);
+    }
+
+    #[test]
+    fn parse_keyframes() {
+        let parser = TestParser::new();
+        let parser = StyleSheetParser::new(parser.transformer, parser.extractor);
+        let statements = parser.parse_statements(
+            "@keyframes pulse { to { a: b } from { a: c; compound: x } 25%, 75% { d: e } } \
+            @keyframes \"quoted\" { 50% { a: b } 150% { a: c } } \
+            @keyframes { from { a: b } } \
+            @media (max-width: 100px) { @keyframes nested { to { a: b } } } \
+            a { b: c }",
+        );
+        let keyframes: Vec<_> = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Keyframes(keyframes) => Some(keyframes),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keyframes.len(), 2);
+        let pulse = keyframes[0];
+        assert_eq!(pulse.name, "pulse".as_tag());
+        let offsets: Vec<_> = pulse.frames.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![0., 0.25, 0.75, 1.]);
+        // compound properties are extracted
+        assert!(pulse.frames[0].properties.contains_key(&"b".as_tag()));
+        assert!(pulse.frames[1].properties.contains_key(&"d".as_tag()));
+        assert_eq!(pulse.properties().len(), 3);
+        // out of range keyframes are skipped
+        assert_eq!(keyframes[1].name, "quoted".as_tag());
+        assert_eq!(keyframes[1].frames.len(), 1);
+        assert_eq!(
+            parser
+                .parse("@keyframes pulse { to { a: b } } a { b: c }")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn parse_important_and_layers() {
+        let parser = TestParser::new();
+        let parser = StyleSheetParser::new(parser.transformer, parser.extractor);
+        let statements = parser.parse_statements(
+            "a, b { c: d; e: f !important; compound: x ! important } \
+            @layer base, app; \
+            @layer app { g { h: i } @media (max-width: 100px) { j { k: l } } } \
+            @media (max-width: 100px) { @layer base { m { } } @layer base, app; } \
+            @layer base { @layer nested { n { } } } \
+            @layer base, app { o { } } \
+            p { a: b !notimportant }",
+        );
+        let layers: Vec<_> = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Layers(layers) => Some(layers.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(layers, vec![vec!["base".as_tag(), "app".as_tag()]]);
+        let rules: Vec<_> = statements
+            .into_iter()
+            .flat_map(|s| match s {
+                Statement::Rules(rules) => rules,
+                _ => smallvec![],
+            })
+            .collect();
+        assert_eq!(rules.len(), 8);
+
+        // `!important` declarations go to the separate rule
+        assert!(rules[0].important);
+        assert_eq!(rules[0].selector.to_string(), "a");
+        let mut important: Vec<_> = rules[0].properties.keys().map(|k| k.to_string()).collect();
+        important.sort();
+        assert_eq!(important, vec!["a", "b", "e"]);
+        assert!(!rules[1].important);
+        assert_eq!(rules[1].properties.len(), 1);
+        assert!(rules[2].important);
+        assert_eq!(rules[2].selector.to_string(), "b");
+        assert!(rules[..4].iter().all(|r| r.layer.is_none()));
+
+        assert_eq!(rules[4].layer, Some("app".as_tag()));
+        assert_eq!(rules[5].layer, Some("app".as_tag()));
+        assert_eq!(rules[5].media.len(), 1);
+        assert_eq!(rules[6].layer, Some("base".as_tag()));
+        assert_eq!(rules[6].media.len(), 1);
+        // invalid declaration is skipped
+        assert!(rules[7].properties.is_empty());
+    }
+
+    #[test]
+    fn parse_diagnostics() {
+        let parser = TestParser::new();
+        let parser = StyleSheetParser::new(parser.transformer, parser.extractor);
+        let (statements, diagnostics) = parser.parse_with_diagnostics(
+            "a { b: c }\n\
+            a { b c; d: e }\n\
+            @font { a { } }\n\
+            a > { b: c }\n\
+            @media (max-width: 100px) {\n  @import \"nested.ess\";\n}\n\
+            @keyframes pulse { to { a: b !important } }",
+        );
+        // invalid declarations are dropped, the rest of the rule is kept
+        assert_eq!(statements.len(), 4);
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.column))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Error, 2, 6),
+                (Severity::Error, 3, 6),
+                (Severity::Error, 4, 5),
+                (Severity::Warning, 6, 3),
+                (Severity::Warning, 8, 20),
+            ]
+        );
+        assert!(diagnostics[..3].iter().all(|d| d.is_error()));
+        assert_eq!(
+            diagnostics[2].message,
+            "Failed to parse rule: Invalid selector"
+        );
+        assert_eq!(diagnostics[3].excerpt, "  @import \"nested.ess\";");
+        assert_eq!(
+            diagnostics[2].to_string(),
+            "error: Failed to parse rule: Invalid selector at 4:5\na > { b: c }\n    ^"
+        );
+    }
+
+    #[test]
+    fn warn_elliptical_radius() {
+        use crate::ess::property::impls::border::*;
+        let transformer = PropertyTransformer::default();
+        transformer.register::<BorderTopLeftRadiusProperty>();
+        transformer.register::<BorderBottomLeftRadiusProperty>();
+        let extractor = PropertyExtractor::default();
+        extractor.register::<BorderRadiusProperty>();
+        let parser = StyleSheetParser::new(transformer.clone(), extractor.clone());
+        let (_, diagnostics) = parser.parse_with_diagnostics(
+            "a { border-radius: 10px / 10px; border-top-left-radius: 5px }\n\
+            a { border-radius: 10px 20px / 10px 5px }\n\
+            a { border-bottom-left-radius: 4px 8px }",
+        );
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+        let summary: Vec<_> = diagnostics.iter().map(|d| (d.line, d.column)).collect();
+        assert_eq!(summary, vec![(2, 5), (3, 5)]);
+        assert!(diagnostics[0].message.contains("Px(5.0)"));
+        assert!(diagnostics[1].message.contains("border-bottom-left-radius"));
+
+        // inline styles are checked by the same hooks
+        let inline = |value: &str| Variant::String(value.to_string());
+        let name = "border-radius".as_tag();
+        assert!(extractor.warning(name, &inline("4px / 8px")).is_some());
+        assert!(extractor.warning(name, &inline("4px")).is_none());
+        let name = "border-top-left-radius".as_tag();
+        assert!(transformer.warning(name, &inline("4px 8px")).is_some());
+        assert!(transformer.warning(name, &inline("4px 4px")).is_none());
+    }
+
+    #[test]
+    fn parse_font_face() {
+        let parser = TestParser::new();
+        let parser = StyleSheetParser::new(parser.transformer, parser.extractor);
+        let (statements, diagnostics) = parser.parse_with_diagnostics(
+            "@font-face { font-family: \"Inter\"; src: \"fonts/Inter-Bold.ttf\"; font-weight: 700; font-style: italic }\n\
+            @font-face { font-family: Open Sans; src: url(\"fonts/OpenSans.ttf\"); unicode-range: U+0-7F }\n\
+            @font-face { src: \"fonts/Nameless.ttf\" }\n\
+            @font-face { font-family: A; src: \"a.ttf\"; font-weight: 1200 }",
+        );
+        let faces: Vec<_> = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::FontFace(face) => Some(face.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            faces,
+            vec![
+                FontFace {
+                    family: "Inter".to_string(),
+                    src: "fonts/Inter-Bold.ttf".to_string(),
+                    weight: 700,
+                    style: FontStyle::Italic,
+                },
+                FontFace {
+                    family: "Open Sans".to_string(),
+                    src: "fonts/OpenSans.ttf".to_string(),
+                    weight: 400,
+                    style: FontStyle::Normal,
+                },
+                // invalid descriptors are dropped
+                FontFace {
+                    family: "A".to_string(),
+                    src: "a.ttf".to_string(),
+                    weight: 400,
+                    style: FontStyle::Normal,
+                },
+            ]
+        );
+        let summary: Vec<_> = diagnostics.iter().map(|d| (d.severity, d.line)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Warning, 2),
+                (Severity::Error, 3),
+                (Severity::Error, 4),
+            ]
+        );
+    }
 }
@@ -0,0 +1,835 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use smallvec::{smallvec, SmallVec};
+use tagstr::*;
+
+use crate::ess::{ComputedStyle, RuleIndex};
+use crate::ElementsError;
+
+use super::transition::seconds;
+use super::{Easing, PropertyParser, PropertyValue, StyleProperty, StylePropertyToken};
+
+/// Single keyframe of the `@keyframes` rule: `50% { width: 20px }`.
+/// The offset is in `0..=1` range: `from` is `0.` and `to` is `1.`
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub offset: f32,
+    pub properties: HashMap<Tag, PropertyValue>,
+}
+
+/// Keyframes defined by the `@keyframes` rule ordered by the offset
+#[derive(Debug, Clone)]
+pub struct Keyframes {
+    pub name: Tag,
+    pub frames: Vec<Keyframe>,
+}
+
+impl Keyframes {
+    pub fn new(name: Tag, mut frames: Vec<Keyframe>) -> Keyframes {
+        // the stable sort keeps the definition order of the same offsets
+        frames.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        Keyframes { name, frames }
+    }
+
+    /// Returns the names of the animated properties
+    pub fn properties(&self) -> SmallVec<[Tag; 4]> {
+        let mut properties = smallvec![];
+        for property in self.frames.iter().flat_map(|f| f.properties.keys()) {
+            if !properties.contains(property) {
+                properties.push(*property);
+            }
+        }
+        properties
+    }
+
+    /// Returns the values the `property` is interpolated between at the
+    /// `progress` of the iteration. The `easing` is applied to each pair of
+    /// keyframes. If there is no `from` or `to` keyframe with the property,
+    /// the `computed` value of the element is used instead.
+    pub fn sample(
+        &self,
+        property: Tag,
+        progress: f32,
+        easing: Easing,
+        computed: Option<&PropertyValue>,
+    ) -> Option<AnimatedValue> {
+        let mut frames: SmallVec<[(f32, &PropertyValue); 4]> = smallvec![];
+        for frame in self.frames.iter() {
+            let Some(value) = frame.properties.get(&property) else {
+                continue;
+            };
+            // the last defined keyframe with the same offset wins
+            match frames.last_mut() {
+                Some(last) if last.0 == frame.offset => last.1 = value,
+                _ => frames.push((frame.offset, value)),
+            }
+        }
+        if let Some(computed) = computed {
+            if frames.first().map(|f| f.0 > 0.).unwrap_or(true) {
+                frames.insert(0, (0., computed));
+            }
+            if frames.last().map(|f| f.0 < 1.).unwrap_or(true) {
+                frames.push((1., computed));
+            }
+        }
+        let next = frames
+            .iter()
+            .position(|(offset, _)| *offset > progress)
+            .unwrap_or(frames.len());
+        if next == 0 {
+            return frames.first().map(|(_, value)| AnimatedValue::at(value));
+        }
+        if next == frames.len() {
+            return frames.last().map(|(_, value)| AnimatedValue::at(value));
+        }
+        let (from_offset, from) = frames[next - 1];
+        let (to_offset, to) = frames[next];
+        let t = (progress - from_offset) / (to_offset - from_offset);
+        Some(AnimatedValue {
+            from: from.clone(),
+            to: to.clone(),
+            t: easing.ease(t),
+        })
+    }
+}
+
+/// The value of the animated property: `from` and `to` values of the
+/// current pair of keyframes and the eased progress between them
+#[derive(Debug, Clone)]
+pub struct AnimatedValue {
+    pub from: PropertyValue,
+    pub to: PropertyValue,
+    pub t: f32,
+}
+
+impl AnimatedValue {
+    fn at(value: &PropertyValue) -> AnimatedValue {
+        AnimatedValue {
+            from: value.clone(),
+            to: value.clone(),
+            t: 1.,
+        }
+    }
+}
+
+impl PartialEq for AnimatedValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.from.ptr_eq(&other.from) && self.to.ptr_eq(&other.to) && self.t == other.t
+    }
+}
+
+/// Single value of the `animation-*` properties list
+pub trait AnimationValue: Default + Clone + Send + Sync + 'static {
+    fn parse(token: &StylePropertyToken) -> Option<Self>;
+}
+
+/// Value of the `animation-name` property: `none` or the name of `@keyframes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnimationName(pub Option<Tag>);
+
+impl AnimationValue for AnimationName {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        match token {
+            StylePropertyToken::Identifier(ident) if ident == "none" => Some(AnimationName(None)),
+            StylePropertyToken::Identifier(ident) => Some(AnimationName(Some(ident.as_tag()))),
+            StylePropertyToken::String(name) => Some(AnimationName(Some(name.as_tag()))),
+            _ => None,
+        }
+    }
+}
+
+/// Durations and delays are stored in seconds
+impl AnimationValue for f32 {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        seconds(token)
+    }
+}
+
+impl AnimationValue for Easing {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        Easing::parse(token)
+    }
+}
+
+/// Value of the `animation-iteration-count` property,
+/// `f32::INFINITY` for `infinite`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationCount(pub f32);
+
+impl Default for IterationCount {
+    fn default() -> Self {
+        IterationCount(1.)
+    }
+}
+
+impl AnimationValue for IterationCount {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        match token {
+            StylePropertyToken::Identifier(ident) if ident == "infinite" => {
+                Some(IterationCount(f32::INFINITY))
+            }
+            StylePropertyToken::Number(count) if count.to_float() >= 0. => {
+                Some(IterationCount(count.to_float()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationDirection {
+    #[default]
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+impl AnimationValue for AnimationDirection {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        let StylePropertyToken::Identifier(ident) = token else {
+            return None;
+        };
+        match ident.as_str() {
+            "normal" => Some(AnimationDirection::Normal),
+            "reverse" => Some(AnimationDirection::Reverse),
+            "alternate" => Some(AnimationDirection::Alternate),
+            "alternate-reverse" => Some(AnimationDirection::AlternateReverse),
+            _ => None,
+        }
+    }
+}
+
+/// Determines if the values of the animation are applied
+/// before it starts (`backwards`) and after it ends (`forwards`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationFillMode {
+    #[default]
+    None,
+    Forwards,
+    Backwards,
+    Both,
+}
+
+impl AnimationValue for AnimationFillMode {
+    fn parse(token: &StylePropertyToken) -> Option<Self> {
+        let StylePropertyToken::Identifier(ident) = token else {
+            return None;
+        };
+        match ident.as_str() {
+            "none" => Some(AnimationFillMode::None),
+            "forwards" => Some(AnimationFillMode::Forwards),
+            "backwards" => Some(AnimationFillMode::Backwards),
+            "both" => Some(AnimationFillMode::Both),
+            _ => None,
+        }
+    }
+}
+
+/// Comma-separated value of the `animation-*` properties
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnimationList<T: AnimationValue>(pub SmallVec<[T; 1]>);
+
+impl<T: AnimationValue> AnimationList<T> {
+    /// Returns the value of the `index`-th animation: the list
+    /// is repeated when there are more animations than values
+    pub fn nth(&self, index: usize) -> T {
+        if self.0.is_empty() {
+            T::default()
+        } else {
+            self.0[index % self.0.len()].clone()
+        }
+    }
+}
+
+/// <!-- @property-type=$animation-list -->
+/// Comma-separated list of the `animation-*` property values:
+/// the names of `@keyframes`, times suffixed with `s` or `ms`, easing functions,
+/// iteration counts (or `infinite`), directions or fill modes: `1s, 300ms`
+pub struct AnimationListParser;
+impl<T: AnimationValue> PropertyParser<AnimationList<T>> for AnimationListParser {
+    fn parse(value: &StyleProperty) -> Result<AnimationList<T>, ElementsError> {
+        let mut list = AnimationList::default();
+        for tokens in value.split(|t| matches!(t, StylePropertyToken::Comma)) {
+            match tokens {
+                [token] => list.0.push(T::parse(token).ok_or_else(|| {
+                    ElementsError::InvalidPropertyValue(format!(
+                        "Unexpected animation value `{}`",
+                        token.to_string()
+                    ))
+                })?),
+                tokens => {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Expected single animation value, got `{}`",
+                        tokens
+                            .iter()
+                            .map(|t| t.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )))
+                }
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// The `animation-*` property values of the single animation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub name: Tag,
+    /// Duration of the iteration in seconds
+    pub duration: f32,
+    /// Delay in seconds
+    pub delay: f32,
+    pub easing: Easing,
+    /// `f32::INFINITY` for `infinite` animations
+    pub iterations: f32,
+    pub direction: AnimationDirection,
+    pub fill_mode: AnimationFillMode,
+}
+
+impl Animation {
+    /// Collects the animations declared by the `animation-*` properties of
+    /// the element: the lists of values are repeated to match the number of
+    /// `animation-name` values, the `none` names are skipped.
+    pub fn from_computed(computed: &ComputedStyle) -> SmallVec<[Animation; 1]> {
+        let Some(names) = computed.value::<AnimationList<AnimationName>>(tag!("animation-name"))
+        else {
+            return smallvec![];
+        };
+        fn nth<T: AnimationValue>(computed: &ComputedStyle, property: Tag, index: usize) -> T {
+            computed
+                .value::<AnimationList<T>>(property)
+                .map(|list| list.nth(index))
+                .unwrap_or_default()
+        }
+        names
+            .0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| {
+                Some(Animation {
+                    name: name.0?,
+                    duration: nth(computed, tag!("animation-duration"), index),
+                    delay: nth(computed, tag!("animation-delay"), index),
+                    easing: nth(computed, tag!("animation-timing-function"), index),
+                    iterations: nth::<IterationCount>(
+                        computed,
+                        tag!("animation-iteration-count"),
+                        index,
+                    )
+                    .0,
+                    direction: nth(computed, tag!("animation-direction"), index),
+                    fill_mode: nth(computed, tag!("animation-fill-mode"), index),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Extracts the `animation` shorthand into the `animation-*` properties.
+/// Each of the comma-separated animations is `name duration easing delay
+/// iteration-count direction fill-mode` in any order, the first time is
+/// the duration and the second one is the delay.
+pub(crate) fn extract_animations(
+    value: &StyleProperty,
+) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
+    let mut names = AnimationList::<AnimationName>::default();
+    let mut durations = AnimationList::<f32>::default();
+    let mut delays = AnimationList::<f32>::default();
+    let mut easings = AnimationList::<Easing>::default();
+    let mut counts = AnimationList::<IterationCount>::default();
+    let mut directions = AnimationList::<AnimationDirection>::default();
+    let mut fill_modes = AnimationList::<AnimationFillMode>::default();
+    for tokens in value.split(|t| matches!(t, StylePropertyToken::Comma)) {
+        let mut name = None;
+        let mut times: SmallVec<[f32; 2]> = smallvec![];
+        let mut easing = None;
+        let mut count = None;
+        let mut direction = None;
+        let mut fill_mode = None;
+        for token in tokens {
+            if let Some(time) = seconds(token) {
+                times.push(time);
+            } else if let (None, Some(parsed)) = (easing, Easing::parse(token)) {
+                easing = Some(parsed);
+            } else if let (None, Some(parsed)) = (count, IterationCount::parse(token)) {
+                count = Some(parsed);
+            } else if let (None, Some(parsed)) = (direction, AnimationDirection::parse(token)) {
+                direction = Some(parsed);
+            } else if let (None, Some(parsed)) = (fill_mode, AnimationFillMode::parse(token)) {
+                fill_mode = Some(parsed);
+            } else if let (None, Some(parsed)) = (name, AnimationName::parse(token)) {
+                name = Some(parsed);
+            } else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unexpected token `{}` in animation",
+                    token.to_string()
+                )));
+            }
+        }
+        if tokens.is_empty() || times.len() > 2 {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected `name duration easing delay count direction fill-mode` animation, got `{}`",
+                tokens
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )));
+        }
+        names.0.push(name.unwrap_or_default());
+        durations.0.push(times.first().copied().unwrap_or_default());
+        delays.0.push(times.get(1).copied().unwrap_or_default());
+        easings.0.push(easing.unwrap_or_default());
+        counts.0.push(count.unwrap_or_default());
+        directions.0.push(direction.unwrap_or_default());
+        fill_modes.0.push(fill_mode.unwrap_or_default());
+    }
+    let mut result = HashMap::default();
+    result.insert(tag!("animation-name"), PropertyValue::new(names));
+    result.insert(tag!("animation-duration"), PropertyValue::new(durations));
+    result.insert(tag!("animation-delay"), PropertyValue::new(delays));
+    result.insert(
+        tag!("animation-timing-function"),
+        PropertyValue::new(easings),
+    );
+    result.insert(
+        tag!("animation-iteration-count"),
+        PropertyValue::new(counts),
+    );
+    result.insert(tag!("animation-direction"), PropertyValue::new(directions));
+    result.insert(tag!("animation-fill-mode"), PropertyValue::new(fill_modes));
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationPhase {
+    /// Waiting for the delay
+    Before,
+    Active,
+    /// All the iterations are done
+    After,
+}
+
+/// Animation of the element with the time elapsed since it was declared
+#[derive(Debug, Clone)]
+pub struct RunningAnimation {
+    pub animation: Animation,
+    pub elapsed: f32,
+    pub phase: AnimationPhase,
+    pub iteration: u32,
+}
+
+impl RunningAnimation {
+    pub fn new(animation: Animation) -> RunningAnimation {
+        RunningAnimation {
+            animation,
+            elapsed: 0.,
+            phase: AnimationPhase::Before,
+            iteration: 0,
+        }
+    }
+
+    /// Returns the phase, the current iteration and the progress
+    /// of the iteration according to the animation direction
+    pub fn state(&self) -> (AnimationPhase, u32, f32) {
+        let animation = &self.animation;
+        let active = self.elapsed - animation.delay;
+        let total = if animation.duration > 0. && animation.iterations > 0. {
+            animation.duration * animation.iterations
+        } else {
+            0.
+        };
+        let (phase, iteration, progress) = if active < 0. {
+            (AnimationPhase::Before, 0, 0.)
+        } else if active >= total {
+            let iterations = if total > 0. { animation.iterations } else { 0. };
+            let iteration = (iterations.ceil() as u32).max(1) - 1;
+            (
+                AnimationPhase::After,
+                iteration,
+                iterations - iteration as f32,
+            )
+        } else {
+            let position = active / animation.duration;
+            (
+                AnimationPhase::Active,
+                position.floor() as u32,
+                position.fract(),
+            )
+        };
+        let forward = match animation.direction {
+            AnimationDirection::Normal => true,
+            AnimationDirection::Reverse => false,
+            AnimationDirection::Alternate => iteration % 2 == 0,
+            AnimationDirection::AlternateReverse => iteration % 2 == 1,
+        };
+        let progress = if forward { progress } else { 1. - progress };
+        (phase, iteration, progress)
+    }
+
+    /// Returns the progress of the iteration if the
+    /// values of the animation should be applied
+    pub fn progress(&self) -> Option<f32> {
+        let (phase, _, progress) = self.state();
+        let fill = self.animation.fill_mode;
+        match phase {
+            AnimationPhase::Active => Some(progress),
+            AnimationPhase::Before
+                if matches!(fill, AnimationFillMode::Backwards | AnimationFillMode::Both) =>
+            {
+                Some(progress)
+            }
+            AnimationPhase::After
+                if matches!(fill, AnimationFillMode::Forwards | AnimationFillMode::Both) =>
+            {
+                Some(progress)
+            }
+            _ => None,
+        }
+    }
+
+    /// Advances the animation and returns the kinds of the events it produced
+    fn advance(&mut self, delta: f32) -> SmallVec<[AnimationEventKind; 2]> {
+        self.elapsed += delta;
+        let (phase, iteration, _) = self.state();
+        let mut events = smallvec![];
+        if self.phase == AnimationPhase::Before && phase != AnimationPhase::Before {
+            events.push(AnimationEventKind::Start);
+        } else if phase == AnimationPhase::Active && iteration > self.iteration {
+            events.push(AnimationEventKind::Iteration);
+        }
+        if self.phase != AnimationPhase::After && phase == AnimationPhase::After {
+            events.push(AnimationEventKind::End);
+        }
+        self.phase = phase;
+        self.iteration = iteration;
+        events
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationEventKind {
+    /// `animationstart`: the delay is over
+    Start,
+    /// `animationiteration`: the next iteration began
+    Iteration,
+    /// `animationend`: all the iterations are done
+    End,
+}
+
+/// Sent when the animation of the element starts, begins the next iteration
+/// or ends. Connected with `on:animationstart`, `on:animationiteration` and
+/// `on:animationend`.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    /// The name of `@keyframes`
+    pub name: Tag,
+    pub kind: AnimationEventKind,
+    /// Seconds since the animation started, excluding the delay
+    pub elapsed: f32,
+}
+
+/// Animations of the element declared by `animation-*` properties and
+/// the values of the animated properties for the current frame
+#[derive(Component, Default)]
+pub struct StyleAnimations {
+    pub running: SmallVec<[RunningAnimation; 1]>,
+    pub values: HashMap<Tag, AnimatedValue>,
+}
+
+impl StyleAnimations {
+    /// Syncs the running animations with the `declared` ones, advances them by
+    /// `delta` seconds and samples the values of the animated properties.
+    /// Returns `true` if any property is not animated anymore, so the
+    /// computed value should be applied again.
+    fn update(
+        &mut self,
+        entity: Entity,
+        declared: SmallVec<[Animation; 1]>,
+        computed: &ComputedStyle,
+        index: &RuleIndex,
+        delta: f32,
+        events: &mut EventWriter<AnimationEvent>,
+    ) -> bool {
+        let mut previous = std::mem::take(&mut self.running);
+        for animation in declared {
+            // the animation with the same name keeps running
+            let running = match previous
+                .iter()
+                .position(|r| r.animation.name == animation.name)
+            {
+                Some(position) => {
+                    let mut running = previous.remove(position);
+                    running.animation = animation;
+                    running
+                }
+                None => RunningAnimation::new(animation),
+            };
+            self.running.push(running);
+        }
+        let mut values = HashMap::default();
+        for running in self.running.iter_mut() {
+            for kind in running.advance(delta) {
+                let animation = &running.animation;
+                let elapsed = (running.elapsed - animation.delay).max(0.);
+                events.send(AnimationEvent {
+                    entity,
+                    name: animation.name,
+                    kind,
+                    elapsed,
+                });
+            }
+            let (Some(progress), Some(keyframes)) =
+                (running.progress(), index.keyframes(running.animation.name))
+            else {
+                continue;
+            };
+            // the animations defined later override the values of the former ones
+            for property in keyframes.properties() {
+                let computed = computed.get(property).map(|c| &c.value);
+                let easing = running.animation.easing;
+                if let Some(value) = keyframes.sample(property, progress, easing, computed) {
+                    values.insert(property, value);
+                }
+            }
+        }
+        let released = self.values.keys().any(|p| !values.contains_key(p));
+        self.values = values;
+        released
+    }
+}
+
+/// Runs the animations declared by `animation-*` properties: sends the
+/// [`AnimationEvent`]s and samples the values of the animated properties,
+/// so each property applies them with `animate_keyframes` system.
+#[allow(clippy::type_complexity)]
+pub fn run_animations_system(
+    mut elements: Query<
+        (Entity, &mut ComputedStyle, Option<&mut StyleAnimations>),
+        Or<(Changed<ComputedStyle>, With<StyleAnimations>)>,
+    >,
+    index: Res<RuleIndex>,
+    time: Res<Time>,
+    mut events: EventWriter<AnimationEvent>,
+    mut commands: Commands,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut computed, animations) in elements.iter_mut() {
+        let declared = Animation::from_computed(&computed);
+        let released = match animations {
+            Some(mut animations) => {
+                let previous = animations.values.clone();
+                let state = animations.bypass_change_detection();
+                let released =
+                    state.update(entity, declared, &computed, &index, delta, &mut events);
+                if state.running.is_empty() && state.values.is_empty() {
+                    commands.entity(entity).remove::<StyleAnimations>();
+                } else if state.values != previous {
+                    animations.set_changed();
+                }
+                released
+            }
+            None if declared.is_empty() => false,
+            None => {
+                let mut animations = StyleAnimations::default();
+                animations.update(entity, declared, &computed, &index, delta, &mut events);
+                commands.entity(entity).insert(animations);
+                false
+            }
+        };
+        if released {
+            // the released properties apply the computed values again
+            computed.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::element::Element;
+    use crate::ess::{
+        impls::BackgroundColorProperty, Interpolate, Property, PropertyRegistry, StyleSheet,
+    };
+    use bevy::ecs::event::ManualEventReader;
+    use std::time::Duration;
+
+    fn extract(value: &str) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
+        extract_animations(&StyleProperty::try_from(value).unwrap())
+    }
+
+    fn compute(inline: &HashMap<Tag, PropertyValue>) -> ComputedStyle {
+        ComputedStyle::compute(
+            Some(inline),
+            &[],
+            &RuleIndex::default(),
+            &Handle::default(),
+            None,
+            None,
+            &PropertyRegistry::default(),
+        )
+    }
+
+    #[test]
+    fn parse_animations() {
+        let inline =
+            extract("pulse 1s infinite alternate ease-in-out, fade 300ms 1s 2 forwards").unwrap();
+        let animations = Animation::from_computed(&compute(&inline));
+        assert_eq!(
+            animations.as_slice(),
+            &[
+                Animation {
+                    name: "pulse".as_tag(),
+                    duration: 1.,
+                    delay: 0.,
+                    easing: Easing::CubicBezier(0.42, 0., 0.58, 1.),
+                    iterations: f32::INFINITY,
+                    direction: AnimationDirection::Alternate,
+                    fill_mode: AnimationFillMode::None,
+                },
+                Animation {
+                    name: "fade".as_tag(),
+                    duration: 0.3,
+                    delay: 1.,
+                    easing: Easing::Ease,
+                    iterations: 2.,
+                    direction: AnimationDirection::Normal,
+                    fill_mode: AnimationFillMode::Forwards,
+                }
+            ]
+        );
+
+        // longhands are repeated to match the names
+        let mut inline = extract("none, a 1s, b 2s").unwrap();
+        let durations: AnimationList<f32> =
+            AnimationListParser::parse(&StyleProperty::try_from("3s").unwrap()).unwrap();
+        inline.insert(tag!("animation-duration"), PropertyValue::new(durations));
+        let animations = Animation::from_computed(&compute(&inline));
+        assert_eq!(animations.len(), 2);
+        assert!(animations.iter().all(|a| a.duration == 3.));
+
+        assert!(extract("none").is_ok());
+        assert!(extract("pulse 1s 2s 3s").is_err());
+        assert!(extract("pulse fade").is_err());
+        let parse = |value: &str| -> Result<AnimationList<IterationCount>, ElementsError> {
+            AnimationListParser::parse(&StyleProperty::try_from(value).unwrap())
+        };
+        assert_eq!(parse("infinite, 2").unwrap().nth(3).0, 2.);
+        assert!(parse("1 2").is_err());
+        assert!(parse("-1").is_err());
+    }
+
+    #[test]
+    fn sample_keyframes() {
+        let frame = |offset, value: Option<f32>| {
+            let mut properties = HashMap::default();
+            if let Some(value) = value {
+                properties.insert(tag!("width"), PropertyValue::new(Val::Px(value)));
+            }
+            properties.insert(tag!("height"), PropertyValue::new(Val::Px(0.)));
+            Keyframe { offset, properties }
+        };
+        let keyframes = Keyframes::new(
+            tag!("grow"),
+            vec![
+                frame(0.5, Some(10.)),
+                frame(0., Some(0.)),
+                frame(0.5, Some(20.)),
+                frame(1., None),
+            ],
+        );
+        let computed = PropertyValue::new(Val::Px(100.));
+        let sample = |progress| {
+            let value = keyframes
+                .sample(tag!("width"), progress, Easing::Linear, Some(&computed))
+                .unwrap();
+            let from = *value.from.downcast_ref::<Val>().unwrap();
+            let to = *value.to.downcast_ref::<Val>().unwrap();
+            Val::interpolate(&from, &to, value.t).unwrap()
+        };
+        assert_eq!(sample(0.), Val::Px(0.));
+        // the last keyframe with the same offset wins
+        assert_eq!(sample(0.25), Val::Px(10.));
+        // the missing `to` keyframe takes the computed value
+        assert_eq!(sample(0.75), Val::Px(60.));
+        assert_eq!(sample(1.), Val::Px(100.));
+        assert!(keyframes
+            .sample(tag!("color"), 0.5, Easing::Linear, None)
+            .is_none());
+        assert_eq!(keyframes.properties().len(), 2);
+    }
+
+    #[test]
+    fn animation_runs_keyframes() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        app.init_resource::<Time>();
+        app.add_event::<AnimationEvent>();
+        let frame = |offset, color: Color| {
+            let mut properties = HashMap::default();
+            properties.insert(tag!("background-color"), PropertyValue::new(color));
+            Keyframe { offset, properties }
+        };
+        let mut sheet = StyleSheet::default();
+        sheet.add_keyframes(Keyframes::new(
+            tag!("pulse"),
+            vec![frame(0., Color::BLACK), frame(1., Color::WHITE)],
+        ));
+        app.insert_resource(RuleIndex::new([(Handle::default(), &sheet)]));
+        app.add_systems(
+            Update,
+            (
+                run_animations_system,
+                BackgroundColorProperty::apply_defaults,
+                BackgroundColorProperty::animate_keyframes,
+            )
+                .chain(),
+        );
+        let mut inline = extract("pulse 1s 2 alternate linear").unwrap();
+        let blue = Color::srgb(0., 0., 1.);
+        inline.insert(tag!("background-color"), PropertyValue::new(blue));
+        let entity = app
+            .world_mut()
+            .spawn((
+                Element {
+                    names: smallvec!["div".as_tag()],
+                    ..default()
+                },
+                Node::default(),
+                BackgroundColor::default(),
+                compute(&inline),
+            ))
+            .id();
+        let mut reader = ManualEventReader::<AnimationEvent>::default();
+        let mut update = |seconds: f32| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+            let events = app.world().resource::<Events<AnimationEvent>>();
+            let kinds: Vec<_> = reader.read(events).map(|e| e.kind).collect();
+            let color = app.world().get::<BackgroundColor>(entity).unwrap().0;
+            (Srgba::from(color), kinds)
+        };
+        let (color, kinds) = update(0.);
+        assert_eq!((color.red, color.blue), (0., 0.));
+        assert_eq!(kinds, vec![AnimationEventKind::Start]);
+        let (color, kinds) = update(0.25);
+        assert!((color.red - 0.25).abs() < 0.01);
+        assert!(kinds.is_empty());
+        // the second iteration runs backwards
+        let (color, kinds) = update(1.);
+        assert!((color.red - 0.75).abs() < 0.01);
+        assert_eq!(kinds, vec![AnimationEventKind::Iteration]);
+        // the computed value is applied when the animation ends
+        let (color, kinds) = update(1.);
+        assert_eq!((color.red, color.blue), (0., 1.));
+        assert_eq!(kinds, vec![AnimationEventKind::End]);
+        let (_, kinds) = update(1.);
+        assert!(kinds.is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use bevy::window::WindowResized;
+use tagstr::*;
+
+use crate::{ess::ComputedStyle, ElementsError};
+
+use super::{StyleProperty, StylePropertyFunction, StylePropertyToken};
+
+/// Returns the [`Val`] of the dimension with the supported `unit`:
+/// `px`, `vw`, `vh`, `vmin` and `vmax`
+pub fn dimension_val(value: f32, unit: &str) -> Option<Val> {
+    match unit {
+        "px" => Some(Val::Px(value)),
+        "vw" => Some(Val::Vw(value)),
+        "vh" => Some(Val::Vh(value)),
+        "vmin" => Some(Val::VMin(value)),
+        "vmax" => Some(Val::VMax(value)),
+        _ => None,
+    }
+}
+
+/// Checks if any of the `tokens` uses font-relative units: `em` or `rem`
+pub fn has_font_units(tokens: &[StylePropertyToken]) -> bool {
+    tokens.iter().any(|token| match token {
+        StylePropertyToken::Dimension(_, unit) => unit == "em" || unit == "rem",
+        StylePropertyToken::Function(f) => has_font_units(&f.args),
+        StylePropertyToken::Tokens(tokens) => has_font_units(tokens),
+        _ => false,
+    })
+}
+
+/// Replaces `em` dimensions with `em * font_size` pixels
+/// and `rem` dimensions with `rem * root_font_size` pixels.
+pub fn resolve_font_units(
+    value: &StyleProperty,
+    font_size: f32,
+    root_font_size: f32,
+) -> StyleProperty {
+    let mut result = StyleProperty::default();
+    for token in value.iter() {
+        result
+            .0
+            .push(resolve_token_font_units(token, font_size, root_font_size));
+    }
+    result
+}
+
+fn resolve_token_font_units(
+    token: &StylePropertyToken,
+    font_size: f32,
+    root_font_size: f32,
+) -> StylePropertyToken {
+    let resolve = |token| resolve_token_font_units(token, font_size, root_font_size);
+    match token {
+        StylePropertyToken::Dimension(value, unit) if unit == "em" => {
+            StylePropertyToken::Dimension((value.to_float() * font_size).into(), "px".into())
+        }
+        StylePropertyToken::Dimension(value, unit) if unit == "rem" => {
+            StylePropertyToken::Dimension((value.to_float() * root_font_size).into(), "px".into())
+        }
+        StylePropertyToken::Function(f) => StylePropertyToken::Function(StylePropertyFunction {
+            name: f.name.clone(),
+            args: f.args.iter().map(resolve).collect(),
+        }),
+        StylePropertyToken::Tokens(tokens) => {
+            StylePropertyToken::Tokens(tokens.iter().map(resolve).collect())
+        }
+        token => token.clone(),
+    }
+}
+
+/// The result of `calc()` expression: sum of the lengths of different units.
+///
+/// When the result uses a single unit it is converted to the plain [`Val`] while
+/// parsing. Otherwise the value is kept until it is applied and evaluated
+/// against the size of the parent node and the size of the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CalcValue {
+    pub px: f32,
+    pub percent: f32,
+    pub vw: f32,
+    pub vh: f32,
+    pub vmin: f32,
+    pub vmax: f32,
+}
+
+impl CalcValue {
+    /// Evaluates the arguments of `calc()` function
+    pub fn parse(args: &[StylePropertyToken]) -> Result<CalcValue, ElementsError> {
+        let tokens = match args {
+            [StylePropertyToken::Tokens(tokens)] => tokens.as_slice(),
+            args => args,
+        };
+        let mut parser = CalcParser { tokens, offset: 0 };
+        let value = parser.sum()?;
+        if let Some(token) = parser.tokens.get(parser.offset) {
+            return Err(calc_error(format!("unexpected `{}`", token.to_string())));
+        }
+        match value {
+            Term::Length(value) => Ok(value),
+            Term::Number(0.) => Ok(CalcValue::default()),
+            Term::Number(n) => Err(calc_error(format!("expected length, got number {n}"))),
+        }
+    }
+
+    /// Returns the value of `calc()` if the `property` is the single `calc()` function
+    pub fn from_property(property: &StyleProperty) -> Option<CalcValue> {
+        match property.as_slice() {
+            [StylePropertyToken::Function(f)] if f.name == "calc" => CalcValue::parse(&f.args).ok(),
+            _ => None,
+        }
+    }
+
+    /// Converts the value to [`Val`] if it uses a single unit.
+    pub fn to_val(&self) -> Option<Val> {
+        let units = [
+            (self.px, Val::Px as fn(f32) -> Val),
+            (self.percent, Val::Percent),
+            (self.vw, Val::Vw),
+            (self.vh, Val::Vh),
+            (self.vmin, Val::VMin),
+            (self.vmax, Val::VMax),
+        ];
+        let mut used = units.iter().filter(|(value, _)| *value != 0.);
+        match (used.next(), used.next()) {
+            (None, _) => Some(Val::Px(0.)),
+            (Some((value, val)), None) => Some(val(*value)),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the value in pixels: percents are taken from `basis`,
+    /// viewport units from the `viewport` size.
+    pub fn evaluate(&self, basis: f32, viewport: Vec2) -> f32 {
+        self.px
+            + self.percent * basis / 100.
+            + self.vw * viewport.x / 100.
+            + self.vh * viewport.y / 100.
+            + self.vmin * viewport.min_element() / 100.
+            + self.vmax * viewport.max_element() / 100.
+    }
+
+    /// Checks if percents of the `property` are taken from the height of
+    /// the parent node. Others (including vertical margins and paddings)
+    /// are taken from the width.
+    pub fn percent_of_height(property: Tag) -> bool {
+        matches!(
+            property.as_str(),
+            "height" | "min-height" | "max-height" | "top" | "bottom" | "row-gap"
+        )
+    }
+
+    fn map(self, f: impl Fn(f32) -> f32) -> CalcValue {
+        CalcValue {
+            px: f(self.px),
+            percent: f(self.percent),
+            vw: f(self.vw),
+            vh: f(self.vh),
+            vmin: f(self.vmin),
+            vmax: f(self.vmax),
+        }
+    }
+
+    fn add(self, other: CalcValue) -> CalcValue {
+        CalcValue {
+            px: self.px + other.px,
+            percent: self.percent + other.percent,
+            vw: self.vw + other.vw,
+            vh: self.vh + other.vh,
+            vmin: self.vmin + other.vmin,
+            vmax: self.vmax + other.vmax,
+        }
+    }
+}
+
+/// Marks the elements with properties evaluated from `calc()`:
+/// the styles are applied again when the parent node, the window or [`UiScale`] is resized.
+#[derive(Component, Default)]
+pub struct CalcStyle;
+
+pub fn reapply_calc_system(
+    mut resized: EventReader<WindowResized>,
+    ui_scale: Res<UiScale>,
+    changed_nodes: Query<(), Changed<Node>>,
+    mut styles: Query<(&Parent, &mut ComputedStyle), With<CalcStyle>>,
+) {
+    let resized = resized.read().count() > 0 || ui_scale.is_changed();
+    for (parent, mut style) in styles.iter_mut() {
+        if resized || changed_nodes.contains(parent.get()) {
+            style.set_changed();
+        }
+    }
+}
+
+fn calc_error(message: String) -> ElementsError {
+    ElementsError::InvalidPropertyValue(format!("Invalid calc() expression: {message}"))
+}
+
+#[derive(Clone, Copy)]
+enum Term {
+    Number(f32),
+    Length(CalcValue),
+}
+
+struct CalcParser<'a> {
+    tokens: &'a [StylePropertyToken],
+    offset: usize,
+}
+
+impl<'a> CalcParser<'a> {
+    fn peek(&self) -> Option<&'a StylePropertyToken> {
+        self.tokens.get(self.offset)
+    }
+
+    fn sum(&mut self) -> Result<Term, ElementsError> {
+        let mut result = self.product()?;
+        while let Some(StylePropertyToken::Operator(op @ ('+' | '-'))) = self.peek() {
+            self.offset += 1;
+            let sign = if *op == '-' { -1. } else { 1. };
+            result = match (result, self.product()?) {
+                (Term::Number(a), Term::Number(b)) => Term::Number(a + sign * b),
+                (Term::Length(a), Term::Length(b)) => Term::Length(a.add(b.map(|v| sign * v))),
+                _ => return Err(calc_error("can't add number to length".into())),
+            };
+        }
+        Ok(result)
+    }
+
+    fn product(&mut self) -> Result<Term, ElementsError> {
+        let mut result = self.factor()?;
+        loop {
+            let divide = match self.peek() {
+                Some(StylePropertyToken::Operator('*')) => false,
+                Some(StylePropertyToken::Slash) => true,
+                _ => return Ok(result),
+            };
+            self.offset += 1;
+            result = match (result, self.factor()?, divide) {
+                (_, Term::Number(0.), true) => return Err(calc_error("division by zero".into())),
+                (Term::Number(a), Term::Number(b), true) => Term::Number(a / b),
+                (Term::Length(a), Term::Number(b), true) => Term::Length(a.map(|v| v / b)),
+                (Term::Number(a), Term::Number(b), false) => Term::Number(a * b),
+                (Term::Length(a), Term::Number(b), false)
+                | (Term::Number(b), Term::Length(a), false) => Term::Length(a.map(|v| v * b)),
+                _ => return Err(calc_error("can't multiply or divide by length".into())),
+            };
+        }
+    }
+
+    fn factor(&mut self) -> Result<Term, ElementsError> {
+        let Some(token) = self.peek() else {
+            return Err(calc_error("unexpected end of expression".into()));
+        };
+        self.offset += 1;
+        match token {
+            StylePropertyToken::Number(n) => Ok(Term::Number(n.to_float())),
+            StylePropertyToken::Percentage(p) => Ok(Term::Length(CalcValue {
+                percent: p.to_float(),
+                ..default()
+            })),
+            StylePropertyToken::Dimension(value, unit) => {
+                let value = value.to_float();
+                let mut length = CalcValue::default();
+                match unit.as_str() {
+                    "px" => length.px = value,
+                    "vw" => length.vw = value,
+                    "vh" => length.vh = value,
+                    "vmin" => length.vmin = value,
+                    "vmax" => length.vmax = value,
+                    unit => return Err(calc_error(format!("unsupported unit `{unit}`"))),
+                }
+                Ok(Term::Length(length))
+            }
+            // nested parentheses or calc()
+            StylePropertyToken::Function(f) if f.name.is_empty() || f.name == "calc" => {
+                let tokens = match f.args.as_slice() {
+                    [StylePropertyToken::Tokens(tokens)] => tokens.as_slice(),
+                    args => args,
+                };
+                let mut parser = CalcParser { tokens, offset: 0 };
+                let value = parser.sum()?;
+                match parser.peek() {
+                    None => Ok(value),
+                    Some(token) => Err(calc_error(format!("unexpected `{}`", token.to_string()))),
+                }
+            }
+            token => Err(calc_error(format!("unexpected `{}`", token.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn calc(value: &str) -> Result<CalcValue, ElementsError> {
+        let property = StyleProperty::try_from(value)?;
+        match property.as_slice() {
+            [StylePropertyToken::Function(f)] => CalcValue::parse(&f.args),
+            _ => panic!("Expected calc() function, got {value}"),
+        }
+    }
+
+    #[test]
+    fn calc_expressions() {
+        assert_eq!(
+            calc("calc(10px + 5px)").unwrap().to_val(),
+            Some(Val::Px(15.))
+        );
+        assert_eq!(
+            calc("calc(2 * 25%)").unwrap().to_val(),
+            Some(Val::Percent(50.))
+        );
+        assert_eq!(
+            calc("calc(100vw / 4)").unwrap().to_val(),
+            Some(Val::Vw(25.))
+        );
+        assert_eq!(
+            calc("calc(10px + 2 * (5px + 5px))").unwrap().to_val(),
+            Some(Val::Px(30.))
+        );
+        let value = calc("calc(100% - 20px)").unwrap();
+        assert_eq!(value.to_val(), None);
+        assert_eq!(value.evaluate(200., Vec2::ZERO), 180.);
+        let value = calc("calc(50vh - 10% + 1px)").unwrap();
+        assert_eq!(value.evaluate(100., Vec2::new(400., 200.)), 91.);
+
+        assert!(calc("calc(100% - 2)").is_err());
+        assert!(calc("calc(10px * 2px)").is_err());
+        assert!(calc("calc(10px / 0)").is_err());
+        assert!(calc("calc(2em)").is_err());
+    }
+
+    #[test]
+    fn relative_units() {
+        let value = StyleProperty::try_from("2em calc(1rem + 1px)").unwrap();
+        assert!(has_font_units(&value));
+        let resolved = resolve_font_units(&value, 10., 20.);
+        assert!(!has_font_units(&resolved));
+        assert_eq!(
+            resolved,
+            StyleProperty::try_from("20px calc(20px + 1px)").unwrap()
+        );
+        assert_eq!(dimension_val(5., "vmin"), Some(Val::VMin(5.)));
+        assert_eq!(dimension_val(5., "em"), None);
+    }
+}
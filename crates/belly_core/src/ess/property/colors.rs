@@ -1,7 +1,12 @@
-use bevy::{color::Alpha, prelude::Color};
+use bevy::{
+    color::{Alpha, Hsla, LinearRgba, Mix, Oklaba, Oklcha, Srgba},
+    prelude::Color,
+};
 
 use crate::ElementsError;
 
+use super::{StylePropertyFunction, StylePropertyToken};
+
 pub trait ColorFromHexExtension {
     fn from_hex<T: AsRef<str>>(color: T) -> Color {
         let color = color.as_ref().trim().trim_start_matches('#');
@@ -50,6 +55,199 @@ pub(super) fn parse_hex_color(hex: &str) -> Result<Color, ElementsError> {
     }
 }
 
+fn invalid(message: impl Into<String>) -> ElementsError {
+    ElementsError::InvalidPropertyValue(message.into())
+}
+
+/// Parses the single color token: named color, hex code or color function
+pub(super) fn parse_color_token(token: &StylePropertyToken) -> Result<Color, ElementsError> {
+    match token {
+        StylePropertyToken::Identifier(name) => parse_named_color(name.as_str())
+            .ok_or_else(|| invalid(format!("Unknown color name `{name}`"))),
+        StylePropertyToken::Hash(hash) => parse_hex_color(hash.as_str()),
+        StylePropertyToken::Function(function) => parse_color_function(function),
+        token => Err(invalid(format!(
+            "Expected $color, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// Parses `rgb()`, `rgba()`, `hsl()`, `hsla()` and `color-mix()` functions.
+/// Both the legacy (`rgb(255, 0, 0, 0.5)`) and the modern
+/// (`rgb(255 0 0 / 50%)`) syntaxes are accepted.
+pub(super) fn parse_color_function(
+    function: &StylePropertyFunction,
+) -> Result<Color, ElementsError> {
+    match function.name.as_str() {
+        "rgb" | "rgba" => {
+            let ([r, g, b], alpha) = color_channels(function)?;
+            let channel = |token: &StylePropertyToken| match token {
+                StylePropertyToken::Number(n) => Ok(n.to_float() / 255.),
+                StylePropertyToken::Percentage(p) => Ok(p.to_float() / 100.),
+                token => Err(invalid(format!(
+                    "Expected number or percentage in `{}()`, got `{}`",
+                    function.name,
+                    token.to_string()
+                ))),
+            };
+            Ok(Color::srgba(
+                channel(r)?.clamp(0., 1.),
+                channel(g)?.clamp(0., 1.),
+                channel(b)?.clamp(0., 1.),
+                alpha,
+            ))
+        }
+        "hsl" | "hsla" => {
+            let ([h, s, l], alpha) = color_channels(function)?;
+            let hue = match h {
+                StylePropertyToken::Number(n) => n.to_float(),
+                StylePropertyToken::Dimension(n, unit) => match unit.as_str() {
+                    "deg" => n.to_float(),
+                    "rad" => n.to_float().to_degrees(),
+                    "grad" => n.to_float() * 0.9,
+                    "turn" => n.to_float() * 360.,
+                    _ => return Err(invalid(format!("Unsupported hue unit `{unit}`"))),
+                },
+                token => {
+                    return Err(invalid(format!(
+                        "Expected hue, got `{}`",
+                        token.to_string()
+                    )))
+                }
+            };
+            // numbers are accepted as percentages by the modern syntax
+            let percent = |token: &StylePropertyToken| match token {
+                StylePropertyToken::Percentage(p) | StylePropertyToken::Number(p) => {
+                    Ok((p.to_float() / 100.).clamp(0., 1.))
+                }
+                token => Err(invalid(format!(
+                    "Expected percentage in `{}()`, got `{}`",
+                    function.name,
+                    token.to_string()
+                ))),
+            };
+            Ok(Color::hsla(
+                hue.rem_euclid(360.),
+                percent(s)?,
+                percent(l)?,
+                alpha,
+            ))
+        }
+        "color-mix" => parse_color_mix(function),
+        name => Err(invalid(format!("Unsupported color function `{name}()`"))),
+    }
+}
+
+/// Returns three channels and the alpha of the color function
+fn color_channels(
+    function: &StylePropertyFunction,
+) -> Result<([&StylePropertyToken; 3], f32), ElementsError> {
+    let (channels, alpha) = match function.args.as_slice() {
+        [StylePropertyToken::Tokens(tokens)] => {
+            match tokens.iter().position(|t| t == &StylePropertyToken::Slash) {
+                Some(slash) => match &tokens[slash + 1..] {
+                    [alpha] => (&tokens[..slash], Some(alpha)),
+                    _ => return Err(invalid("Expected single alpha value after `/`")),
+                },
+                None => (tokens.as_slice(), None),
+            }
+        }
+        [_, _, _, alpha] => (&function.args[..3], Some(alpha)),
+        args => (args, None),
+    };
+    let [r, g, b] = channels else {
+        return Err(invalid(format!(
+            "Expected 3 channels in `{}()`, got {}",
+            function.name,
+            channels.len()
+        )));
+    };
+    let alpha = match alpha {
+        None => 1.,
+        Some(StylePropertyToken::Number(n)) => n.to_float(),
+        Some(StylePropertyToken::Percentage(p)) => p.to_float() / 100.,
+        Some(token) => {
+            return Err(invalid(format!(
+                "Expected alpha value, got `{}`",
+                token.to_string()
+            )))
+        }
+    };
+    Ok(([r, g, b], alpha.clamp(0., 1.)))
+}
+
+/// Parses `color-mix(in srgb, red 30%, blue)`: the colors are mixed in
+/// `srgb`, `srgb-linear`, `hsl`, `oklab` or `oklch` color space. The missing
+/// percentage complements the other one to `100%`, the percentages summing
+/// up to less than `100%` make the result transparent.
+fn parse_color_mix(function: &StylePropertyFunction) -> Result<Color, ElementsError> {
+    let [StylePropertyToken::Tokens(space), first, second] = function.args.as_slice() else {
+        return Err(invalid(
+            "Expected `color-mix(in <space>, <color> [<percentage>], <color> [<percentage>])`",
+        ));
+    };
+    let space = match space.as_slice() {
+        [StylePropertyToken::Identifier(in_), StylePropertyToken::Identifier(space)]
+            if in_ == "in" =>
+        {
+            space.as_str()
+        }
+        _ => return Err(invalid("Expected `in <color-space>` in `color-mix()`")),
+    };
+    let (first, p1) = mix_component(first)?;
+    let (second, p2) = mix_component(second)?;
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (0.5, 0.5),
+        (Some(p1), None) => (p1, 1. - p1),
+        (None, Some(p2)) => (1. - p2, p2),
+        (Some(p1), Some(p2)) => (p1, p2),
+    };
+    let sum = p1 + p2;
+    if sum <= 0. {
+        return Err(invalid(
+            "Percentages of `color-mix()` should sum up to more than 0%",
+        ));
+    }
+    let t = p2 / sum;
+    let mixed = match space {
+        "srgb" => Srgba::from(first).mix(&Srgba::from(second), t).into(),
+        "srgb-linear" => LinearRgba::from(first)
+            .mix(&LinearRgba::from(second), t)
+            .into(),
+        "hsl" => Hsla::from(first).mix(&Hsla::from(second), t).into(),
+        "oklab" => Oklaba::from(first).mix(&Oklaba::from(second), t).into(),
+        "oklch" => Oklcha::from(first).mix(&Oklcha::from(second), t).into(),
+        space => return Err(invalid(format!("Unsupported color space `{space}`"))),
+    };
+    let mixed: Color = mixed;
+    Ok(mixed.with_alpha(mixed.alpha() * sum.min(1.)))
+}
+
+/// Returns the color of the `color-mix()` argument and its percentage
+fn mix_component(token: &StylePropertyToken) -> Result<(Color, Option<f32>), ElementsError> {
+    let percent = |p: &super::style::Number| {
+        let p = p.to_float();
+        if (0. ..=100.).contains(&p) {
+            Ok(Some(p / 100.))
+        } else {
+            Err(invalid("Percentage of `color-mix()` should be in 0%..100%"))
+        }
+    };
+    match token {
+        StylePropertyToken::Tokens(tokens) => match tokens.as_slice() {
+            [StylePropertyToken::Percentage(p), color]
+            | [color, StylePropertyToken::Percentage(p)] => {
+                Ok((parse_color_token(color)?, percent(p)?))
+            }
+            _ => Err(invalid(
+                "Expected `<color> [<percentage>]` in `color-mix()`",
+            )),
+        },
+        color => Ok((parse_color_token(color)?, None)),
+    }
+}
+
 // Source: https://developer.mozilla.org/en-US/docs/Web/CSS/named-color
 
 /// Parses a named color, like "silver" or "azure" into a [`Color`]
@@ -216,3 +414,71 @@ pub(super) fn parse_named_color(name: &str) -> Option<Color> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::StyleProperty;
+
+    fn parse(value: &str) -> Result<Srgba, ElementsError> {
+        let property = StyleProperty::try_from(value).unwrap();
+        parse_color_token(&property[0]).map(Srgba::from)
+    }
+
+    fn assert_color(value: &str, expected: Srgba) {
+        let color = parse(value).unwrap();
+        let distance = [
+            color.red - expected.red,
+            color.green - expected.green,
+            color.blue - expected.blue,
+            color.alpha - expected.alpha,
+        ];
+        assert!(
+            distance.iter().all(|d| d.abs() < 0.005),
+            "{value}: {color:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn parse_color_functions() {
+        assert_color("rgb(255, 0, 51)", Srgba::new(1., 0., 0.2, 1.));
+        assert_color("rgba(255, 0, 0, 0.5)", Srgba::new(1., 0., 0., 0.5));
+        assert_color("rgb(100% 50% 0% / 25%)", Srgba::new(1., 0.5, 0., 0.25));
+        assert_color("rgb(300 0 0)", Srgba::new(1., 0., 0., 1.));
+        assert_color("hsl(120, 100%, 50%)", Srgba::new(0., 1., 0., 1.));
+        assert_color("hsla(0.5turn 100% 50% / 0.6)", Srgba::new(0., 1., 1., 0.6));
+        assert_color("hsl(-120deg 100 50)", Srgba::new(0., 0., 1., 1.));
+        assert_color("hsl(210 80% 50% / 0.6)", Srgba::new(0.1, 0.5, 0.9, 0.6));
+
+        assert!(parse("rgb(1, 2)").is_err());
+        assert!(parse("rgb(1 2 3 / 4 5)").is_err());
+        assert!(parse("rgb(red, 2, 3)").is_err());
+        assert!(parse("hsl(10px, 50%, 50%)").is_err());
+        assert!(parse("lab(50% 40 59)").is_err());
+    }
+
+    #[test]
+    fn parse_color_mix() {
+        assert_color(
+            "color-mix(in srgb, white 30%, black)",
+            Srgba::new(0.3, 0.3, 0.3, 1.),
+        );
+        assert_color(
+            "color-mix(in srgb, #ff0000, rgb(0 0 255))",
+            Srgba::new(0.5, 0., 0.5, 1.),
+        );
+        assert_color(
+            "color-mix(in srgb, 25% white, black 25%)",
+            Srgba::new(0.5, 0.5, 0.5, 0.5),
+        );
+        assert_color(
+            "color-mix(in hsl, hsl(0 100% 50%), hsl(120 100% 50%))",
+            Srgba::new(1., 1., 0., 1.),
+        );
+        assert!(parse("color-mix(in oklch, red, blue 10%)").is_ok());
+        assert!(parse("color-mix(in cmyk, red, blue)").is_err());
+        assert!(parse("color-mix(red, blue)").is_err());
+        assert!(parse("color-mix(in srgb, red 0%, blue 0%)").is_err());
+        assert!(parse("color-mix(in srgb, red 120%, blue)").is_err());
+    }
+}
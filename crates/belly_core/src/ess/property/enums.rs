@@ -1,6 +1,8 @@
+use super::text_layout::{TextOverflow, WhiteSpace, WordBreak};
 use super::StyleProperty;
 use super::StylePropertyMethods;
 use super::StylePropertyToken;
+use crate::ess::FontStyle;
 use crate::ElementsError;
 use bevy::prelude::*;
 
@@ -134,3 +136,30 @@ prop_to_enum! { GridAutoFlow,
     "row-dense" => RowDense,
     "column-dense" => ColumnDense,
 }
+
+prop_to_enum! { JustifyText,
+    "left" => Left,
+    "center" => Center,
+    "right" => Right,
+}
+
+prop_to_enum! { WhiteSpace,
+    "normal" => Normal,
+    "nowrap" => NoWrap,
+}
+
+prop_to_enum! { WordBreak,
+    "normal" => Normal,
+    "break-all" => BreakAll,
+}
+
+prop_to_enum! { TextOverflow,
+    "clip" => Clip,
+    "ellipsis" => Ellipsis,
+}
+
+prop_to_enum! { FontStyle,
+    "normal" => Normal,
+    "italic" => Italic,
+    "oblique" => Italic,
+}
@@ -0,0 +1,151 @@
+use crate::compound_style_property;
+use crate::eml::Variant;
+use crate::ess::{
+    extract_animations, AnimationDirection, AnimationFillMode, AnimationList, AnimationListParser,
+    AnimationName, Easing, IterationCount, StyleProperty,
+};
+use crate::style_property;
+
+compound_style_property! {
+    #[doc = " Runs the animations defined by `@keyframes` rules. Each of the comma-separated"]
+    #[doc = " animations is the name of `@keyframes`, the duration, the easing function, the"]
+    #[doc = " delay, the iteration count, the direction and the fill mode:"]
+    #[doc = " ```css"]
+    #[doc = " animation: pulse 1s infinite alternate ease-in-out;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$animation -->"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationProperty("animation", value)
+        Longhands = [
+            "animation-name",
+            "animation-duration",
+            "animation-delay",
+            "animation-timing-function",
+            "animation-iteration-count",
+            "animation-direction",
+            "animation-fill-mode",
+        ];
+    {
+        let props = match value {
+            Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
+            Variant::Style(prop) => prop,
+            v => return Self::error(format!("Don't know how to extract animation from {v:?}"))
+        };
+        extract_animations(&props)
+    }
+}
+
+style_property! {
+    #[doc = " Specifies the names of `@keyframes` the element is animated with:"]
+    #[doc = " ```css"]
+    #[doc = " animation-name: pulse, fade-in;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationNameProperty("animation-name") {
+        Default = "none";
+        Item = AnimationList<AnimationName>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies the duration of the single iteration of the animations:"]
+    #[doc = " ```css"]
+    #[doc = " animation-duration: 1s, 300ms;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationDurationProperty("animation-duration") {
+        Default = "0s";
+        Item = AnimationList<f32>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies the delay before the animations start:"]
+    #[doc = " ```css"]
+    #[doc = " animation-delay: 500ms;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationDelayProperty("animation-delay") {
+        Default = "0s";
+        Item = AnimationList<f32>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies the easing function applied between each pair of keyframes: `linear`,"]
+    #[doc = " `ease`, `ease-in`, `ease-out`, `ease-in-out` or `cubic-bezier(x1, y1, x2, y2)`:"]
+    #[doc = " ```css"]
+    #[doc = " animation-timing-function: ease-in-out;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationTimingFunctionProperty("animation-timing-function") {
+        Default = "ease";
+        Item = AnimationList<Easing>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies how many times the animations are played, `infinite` repeats them forever:"]
+    #[doc = " ```css"]
+    #[doc = " animation-iteration-count: 3, infinite;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationIterationCountProperty("animation-iteration-count") {
+        Default = "1";
+        Item = AnimationList<IterationCount>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies if the iterations of the animations are played forwards or backwards:"]
+    #[doc = " `normal`, `reverse`, `alternate` or `alternate-reverse`:"]
+    #[doc = " ```css"]
+    #[doc = " animation-direction: alternate;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationDirectionProperty("animation-direction") {
+        Default = "normal";
+        Item = AnimationList<AnimationDirection>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
+
+style_property! {
+    #[doc = " Specifies if the values of the animations are applied before they start"]
+    #[doc = " (`backwards`), after they end (`forwards`), both or none of them:"]
+    #[doc = " ```css"]
+    #[doc = " animation-fill-mode: forwards;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Animation -->"]
+    AnimationFillModeProperty("animation-fill-mode") {
+        Default = "none";
+        Item = AnimationList<AnimationFillMode>;
+        Components = ();
+        Filters = ();
+        Parser = AnimationListParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
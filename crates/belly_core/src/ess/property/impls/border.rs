@@ -0,0 +1,249 @@
+use super::parse;
+use crate::compound_style_property;
+use crate::eml::Variant;
+use crate::ess::{PropertyValue, StyleProperty};
+use crate::{style_property, tag};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+style_property! {
+    #[doc = " Specify the color of element borders defined by `border-width`:"]
+    #[doc = " ```css"]
+    #[doc = " border-color: #4a4a4a;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderColorProperty("border-color") {
+        Default = "transparent";
+        Item = Color;
+        Components = Option<&'static mut BorderColor>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = parse::ColorParser;
+        Apply = |value, border, _assets, commands, entity| {
+            match border {
+                Some(mut border) if &border.0 != value => border.0 = *value,
+                Some(_) => {}
+                None => {
+                    commands.entity(entity).insert(BorderColor(*value));
+                }
+            }
+        };
+    }
+}
+
+compound_style_property! {
+    #[doc = " Rounds the corners of the element background, borders and stylebox."]
+    #[doc = " Accepts 1 to 4 [`$val`](#$val) radii in top-left, top-right, bottom-right,"]
+    #[doc = " bottom-left order, like `margin` accepts the edges. The radii after `/`"]
+    #[doc = " specify the vertical radii of elliptical corners:"]
+    #[doc = " ```css"]
+    #[doc = " border-radius: 8px;"]
+    #[doc = " border-radius: 50% 0px;"]
+    #[doc = " border-radius: 10px 4px / 20px;"]
+    #[doc = " ```"]
+    #[doc = " Bevy renders circular corners only, see [`$radius`](#$radius)."]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderRadiusProperty("border-radius", value)
+        Longhands = [
+            "border-top-left-radius",
+            "border-top-right-radius",
+            "border-bottom-right-radius",
+            "border-bottom-left-radius",
+        ];
+        Warning = parse::border_radius_warning;
+    {
+        let radius = match value {
+            Variant::String(unparsed) => {
+                parse::border_radius(&StyleProperty::try_from(unparsed)?)?
+            }
+            Variant::Style(prop) => parse::border_radius(&prop)?,
+            variant => variant.take::<BorderRadius>().ok_or_else(|| {
+                crate::ElementsError::InvalidPropertyValue(
+                    "Can't extract border-radius from variant".to_string(),
+                )
+            })?,
+        };
+        let mut props = HashMap::default();
+        let corners = [
+            (tag!("border-top-left-radius"), radius.top_left),
+            (tag!("border-top-right-radius"), radius.top_right),
+            (tag!("border-bottom-right-radius"), radius.bottom_right),
+            (tag!("border-bottom-left-radius"), radius.bottom_left),
+        ];
+        for (name, corner) in corners {
+            props.insert(name, PropertyValue::new(corner));
+        }
+        Ok(props)
+    }
+}
+
+/// Sets the corner of [`BorderRadius`]. The component is inserted if the
+/// element doesn't have it, so the longhands of the same element don't
+/// override each other.
+fn set_corner(
+    border_radius: Option<Mut<BorderRadius>>,
+    commands: &mut Commands,
+    entity: Entity,
+    value: Val,
+    corner: fn(&mut BorderRadius) -> &mut Val,
+) {
+    match border_radius {
+        Some(mut border_radius) => {
+            let mut updated = *border_radius;
+            *corner(&mut updated) = value;
+            border_radius.set_if_neq(updated);
+        }
+        None if value != Val::Px(0.) => commands.add(move |world: &mut World| {
+            let Some(mut entity) = world.get_entity_mut(entity) else {
+                return;
+            };
+            if !entity.contains::<BorderRadius>() {
+                entity.insert(BorderRadius::ZERO);
+            }
+            if let Some(mut border_radius) = entity.get_mut::<BorderRadius>() {
+                *corner(&mut border_radius) = value;
+            }
+        }),
+        None => {}
+    }
+}
+
+style_property! {
+    #[doc = " Specify the radius of the top-left corner:"]
+    #[doc = " ```css"]
+    #[doc = " border-top-left-radius: 8px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$radius -->"]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderTopLeftRadiusProperty("border-top-left-radius") {
+        Default = "0px";
+        Item = Val;
+        Components = Option<&'static mut BorderRadius>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = parse::RadiusParser;
+        Apply = |value, border_radius, _assets, commands, entity| {
+            set_corner(border_radius, commands, entity, *value, |r| &mut r.top_left);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the radius of the top-right corner:"]
+    #[doc = " ```css"]
+    #[doc = " border-top-right-radius: 8px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$radius -->"]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderTopRightRadiusProperty("border-top-right-radius") {
+        Default = "0px";
+        Item = Val;
+        Components = Option<&'static mut BorderRadius>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = parse::RadiusParser;
+        Apply = |value, border_radius, _assets, commands, entity| {
+            set_corner(border_radius, commands, entity, *value, |r| &mut r.top_right);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the radius of the bottom-right corner:"]
+    #[doc = " ```css"]
+    #[doc = " border-bottom-right-radius: 8px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$radius -->"]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderBottomRightRadiusProperty("border-bottom-right-radius") {
+        Default = "0px";
+        Item = Val;
+        Components = Option<&'static mut BorderRadius>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = parse::RadiusParser;
+        Apply = |value, border_radius, _assets, commands, entity| {
+            set_corner(border_radius, commands, entity, *value, |r| &mut r.bottom_right);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the radius of the bottom-left corner:"]
+    #[doc = " ```css"]
+    #[doc = " border-bottom-left-radius: 8px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$radius -->"]
+    #[doc = " <!-- @property-category=Border -->"]
+    BorderBottomLeftRadiusProperty("border-bottom-left-radius") {
+        Default = "0px";
+        Item = Val;
+        Components = Option<&'static mut BorderRadius>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = parse::RadiusParser;
+        Apply = |value, border_radius, _assets, commands, entity| {
+            set_corner(border_radius, commands, entity, *value, |r| &mut r.bottom_left);
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ess::{CompoundProperty, PropertyParser};
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn corners(value: &str) -> Vec<Val> {
+        let props = BorderRadiusProperty::extract(Variant::String(value.to_string())).unwrap();
+        BorderRadiusProperty::longhands()
+            .iter()
+            .map(|name| {
+                *props
+                    .get(&crate::Tag::new(name))
+                    .and_then(|v| v.downcast_ref::<Val>())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_border_radius() {
+        assert_eq!(corners("8px"), vec![Val::Px(8.); 4]);
+        assert_eq!(
+            corners("50% 0px"),
+            vec![
+                Val::Percent(50.),
+                Val::Px(0.),
+                Val::Percent(50.),
+                Val::Px(0.)
+            ]
+        );
+        assert_eq!(
+            corners("1px 2px 3px"),
+            vec![Val::Px(1.), Val::Px(2.), Val::Px(3.), Val::Px(2.)]
+        );
+        assert_eq!(
+            corners("1px 2px 3px 4px"),
+            vec![Val::Px(1.), Val::Px(2.), Val::Px(3.), Val::Px(4.)]
+        );
+
+        // elliptical corners are rounded by the smaller radius
+        assert_eq!(
+            corners("10px 4px / 20px 2px"),
+            vec![Val::Px(10.), Val::Px(2.), Val::Px(10.), Val::Px(2.)]
+        );
+        assert_eq!(corners("10% / 20px"), vec![Val::Percent(10.); 4]);
+
+        assert!(
+            BorderRadiusProperty::extract(Variant::String("1px 2px 3px 4px 5px".into())).is_err()
+        );
+        assert!(BorderRadiusProperty::extract(Variant::String("1px /".into())).is_err());
+
+        let p = StyleProperty::from_str("20px 10px").unwrap();
+        assert_eq!(parse::RadiusParser::parse(&p), Ok(Val::Px(10.)));
+        let p = StyleProperty::from_str("1px 2px 3px").unwrap();
+        assert!(parse::RadiusParser::parse(&p).is_err());
+    }
+}
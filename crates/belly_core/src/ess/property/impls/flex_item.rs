@@ -35,6 +35,7 @@ style_property! {
         Item = f32;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::NumParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.flex_grow != value {
@@ -58,6 +59,7 @@ style_property! {
         Item = f32;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::NumParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.flex_shrink != value {
@@ -83,6 +85,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.flex_basis != value {
@@ -1,6 +1,6 @@
 use super::parse;
 use crate::compound_style_property;
-use crate::ess::ToRectMap;
+use crate::ess::{InlineContent, ToRectMap};
 use crate::style_property;
 use bevy::prelude::*;
 
@@ -41,7 +41,9 @@ compound_style_property! {
     #[doc = " ```"]
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Layout Control -->"]
-    PositionProperty("position", value) {
+    PositionProperty("position", value)
+        Longhands = ["left", "right", "top", "bottom"];
+    {
         let rect = UiRect::try_from(value)?;
         Ok(rect.to_rect_map(""))
     }
@@ -58,6 +60,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.left != value {
@@ -78,6 +81,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.right != value {
@@ -98,6 +102,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.top != value {
@@ -118,6 +123,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.bottom != value {
@@ -166,12 +172,17 @@ style_property! {
     DisplayProperty("display") {
         Default = "flex";
         Item = Display;
-        Components = &'static mut Style;
+        Components = (&'static mut Style, Option<&'static mut InlineContent>);
         Filters = With<Node>;
         Parser = parse::IdentifierParser<Display>;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.display != value {
-                style.display = *value;
+        Apply = |value, components, _assets, _commands, _entity| {
+            let (mut style, inline) = components;
+            match inline {
+                // the element is hidden while its content is merged into `InlineText`
+                Some(mut inline) if inline.0 != *value => inline.0 = *value,
+                Some(_) => (),
+                None if &style.display != value => style.display = *value,
+                None => (),
             }
         };
     }
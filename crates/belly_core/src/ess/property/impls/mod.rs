@@ -1,3 +1,5 @@
+pub mod animation;
+pub mod border;
 pub mod flex_container;
 pub mod flex_item;
 pub mod grid;
@@ -6,10 +8,13 @@ pub mod size_constraints;
 pub mod spacing;
 pub mod stylebox;
 pub mod text;
+pub mod transform;
 
 use super::parse;
+use super::Opacity;
 use super::PropertyParser;
 use super::StyleProperty;
+use super::StylePropertyMethods;
 use super::StylePropertyToken;
 use crate::style_property;
 use crate::ElementsError;
@@ -23,6 +28,7 @@ style_property! {
         Item = Color;
         Components = &'static mut BackgroundColor;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ColorParser;
         Apply = |value, background, _assets, _commands, _entity| {
             if &background.0 != value {
@@ -32,6 +38,48 @@ style_property! {
     }
 }
 
+/// $num|$percent
+pub struct OpacityParser;
+impl PropertyParser<f32> for OpacityParser {
+    fn parse(value: &StyleProperty) -> Result<f32, ElementsError> {
+        match value.first() {
+            Some(StylePropertyToken::Number(num)) => Ok(num.to_float().clamp(0., 1.)),
+            Some(StylePropertyToken::Percentage(num)) => Ok((num.to_float() / 100.).clamp(0., 1.)),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $num|$percent, got `{}`",
+                value.to_string()
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " Specify the opacity of the element and all of its descendants: the alpha"]
+    #[doc = " of the background, the border, the text, the image and the stylebox of"]
+    #[doc = " the subtree is multiplied by it, so the nested opacities are multiplied too:"]
+    #[doc = " ```css"]
+    #[doc = " opacity: 0.5;"]
+    #[doc = " opacity: 30%;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$num|$percent -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    OpacityProperty("opacity") {
+        Default = "1";
+        Item = f32;
+        Components = Option<&'static mut Opacity>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = OpacityParser;
+        Apply = |value, opacity, _assets, commands, entity| {
+            match (*value, opacity) {
+                (value, Some(mut opacity)) => { opacity.set_if_neq(Opacity(value)); }
+                (value, None) if value < 1. => { commands.entity(entity).insert(Opacity(value)); }
+                _ => { }
+            }
+        };
+    }
+}
+
 /// auto|$local|$global
 pub struct OptionalZIndexParser;
 impl PropertyParser<Option<ZIndex>> for OptionalZIndexParser {
@@ -88,3 +136,21 @@ style_property! {
         };
     }
 }
+
+style_property! {
+    #[doc = " Specifies the transitions of the property values: the property (or `all`),"]
+    #[doc = " the duration, the easing function and the delay. Properties of `$val`, `$color`"]
+    #[doc = " and `$num` types are interpolated, the rest of them are changed at once."]
+    #[doc = " ```css"]
+    #[doc = " transition: background-color 150ms ease-out, width 300ms;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=General -->"]
+    TransitionProperty("transition") {
+        Default = "none";
+        Item = super::Transitions;
+        Components = ();
+        Filters = ();
+        Parser = super::TransitionParser;
+        Apply = |_value, _components, _assets, _commands, _entity| {};
+    }
+}
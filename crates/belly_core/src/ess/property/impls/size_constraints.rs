@@ -14,6 +14,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.width != value {
@@ -34,6 +35,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.height != value {
@@ -55,6 +57,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.min_width != value {
@@ -75,6 +78,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.min_height != value {
@@ -96,6 +100,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.max_width != value {
@@ -116,6 +121,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.max_height != value {
@@ -14,7 +14,9 @@ compound_style_property! {
     #[doc = " any defined borders."]
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
-    MarginProperty("margin", value) {
+    MarginProperty("margin", value)
+        Longhands = ["margin-left", "margin-right", "margin-top", "margin-bottom"];
+    {
         let rect = UiRect::try_from(value)?;
         Ok(rect.to_rect_map("margin-"))
     }
@@ -34,6 +36,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.margin.left != value {
@@ -57,6 +60,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.margin.right != value {
@@ -80,6 +84,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.margin.top != value {
@@ -103,6 +108,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.margin.bottom != value {
@@ -122,7 +128,9 @@ compound_style_property! {
     #[doc = " any defined borders."]
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
-    PaddingProperty("padding", value) {
+    PaddingProperty("padding", value)
+        Longhands = ["padding-left", "padding-right", "padding-top", "padding-bottom"];
+    {
         let rect = UiRect::try_from(value)?;
         Ok(rect.to_rect_map("padding-"))
     }
@@ -142,6 +150,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.padding.left != value {
@@ -165,6 +174,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.padding.right != value {
@@ -188,6 +198,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.padding.top != value {
@@ -211,6 +222,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.padding.bottom != value {
@@ -229,7 +241,9 @@ compound_style_property! {
     #[doc = " The `border-width` property specifies the width of the four borders."]
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
-    BorderProperty("border-width", value) {
+    BorderProperty("border-width", value)
+        Longhands = ["border-width-left", "border-width-right", "border-width-top", "border-width-bottom"];
+    {
         let rect = UiRect::try_from(value)?;
         Ok(rect.to_rect_map("border-width-"))
     }
@@ -246,6 +260,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.border.left != value {
@@ -267,6 +282,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.border.right != value {
@@ -287,6 +303,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.border.top != value {
@@ -307,6 +324,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.border.bottom != value {
@@ -330,6 +348,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.column_gap != value {
@@ -353,6 +372,7 @@ style_property! {
         Item = Val;
         Components = &'static mut Style;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ValParser;
         Apply = |value, style, _assets, _commands, _entity| {
             if &style.row_gap != value {
@@ -34,7 +34,15 @@ compound_style_property! {
     /// ```
     /// <!-- @property-type=source, slice, region, width, modulate -->
     /// <!-- @property-category=Stylebox -->
-    StyleboxProperty("stylebox", value) {
+    StyleboxProperty("stylebox", value)
+        Longhands = [
+            "stylebox-source",
+            "stylebox-slice",
+            "stylebox-region",
+            "stylebox-width",
+            "stylebox-modulate",
+        ];
+    {
         let props = match value {
             Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
             Variant::Style(prop) => prop,
@@ -173,6 +181,7 @@ style_property! {
         Item = Color;
         Components = &'static mut Stylebox;
         Filters = With<Node>;
+        Interpolated = true;
         Parser = parse::ColorParser;
         Apply = |value, stylebox, _assets, _commands, _entity| {
             if stylebox.modulate != *value {
@@ -1,88 +1,214 @@
 use super::parse;
-use crate::ess::defaults::Defaults;
+use crate::compound_style_property;
+use crate::eml::Variant;
 use crate::ess::PropertyParser;
+use crate::ess::PropertyValue;
 use crate::ess::StyleProperty;
+use crate::ess::StylePropertyMethods;
 use crate::ess::StylePropertyToken;
-use crate::style_property;
+use crate::ess::{update_component, LineHeight, TextLayout, TextOverflow, WhiteSpace, WordBreak};
+use crate::ess::{FontDescriptor, FontStyle, DEFAULT_FONT_FAMILY};
 use crate::ElementsError;
+use crate::{style_property, tag};
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 
-#[derive(Default, Clone)]
-pub enum FontPath {
-    #[default]
-    Regular,
-    Bold,
-    Italic,
-    BoldItalic,
-    Custom(String),
-}
-
-/// regular|bold|italic|bold-italic|$string
-pub struct FontParser;
-impl PropertyParser<FontPath> for FontParser {
-    fn parse(prop: &StyleProperty) -> Result<FontPath, ElementsError> {
-        let Some(token) = prop.first() else {
-            return Err(ElementsError::InvalidPropertyValue(format!(
-                "Expected regular|bold|italic|bold-italic|$string, got nothing"
-            )));
-        };
-        match token {
-            StylePropertyToken::String(id) => Ok(FontPath::Custom(id.clone())),
-            StylePropertyToken::Identifier(ident) => match ident.as_str() {
-                "regular" => Ok(FontPath::Regular),
-                "bold" => Ok(FontPath::Bold),
-                "italic" => Ok(FontPath::Italic),
-                "bold-italic" => Ok(FontPath::BoldItalic),
-                ident => {
+/// $string|$ident, ...
+pub struct FontFamilyParser;
+impl PropertyParser<Vec<String>> for FontFamilyParser {
+    fn parse(prop: &StyleProperty) -> Result<Vec<String>, ElementsError> {
+        let mut families = vec![];
+        for tokens in prop.split(|t| matches!(t, StylePropertyToken::Comma)) {
+            let family = match tokens {
+                [StylePropertyToken::String(family)] => family.clone(),
+                // unquoted family names may consist of several identifiers
+                tokens if !tokens.is_empty() => tokens
+                    .iter()
+                    .map(|token| match token {
+                        StylePropertyToken::Identifier(ident) => Ok(ident.as_str()),
+                        token => Err(ElementsError::InvalidPropertyValue(format!(
+                            "Expected $string|$ident, got `{}`",
+                            token.to_string()
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(" "),
+                _ => {
                     return Err(ElementsError::InvalidPropertyValue(format!(
-                        "Expected regular|bold|italic|bold-italic|$string, got `{ident}`"
+                        "Expected $string|$ident, got `{}`",
+                        prop.to_string()
                     )))
                 }
-            },
-            ident => Err(ElementsError::InvalidPropertyValue(format!(
-                "Expected regulart|bold|italic|bold-italic|$string, got `{}`",
-                ident.to_string()
+            };
+            families.push(family);
+        }
+        Ok(families)
+    }
+}
+
+/// normal|bold|$num
+pub struct FontWeightParser;
+impl PropertyParser<u16> for FontWeightParser {
+    fn parse(prop: &StyleProperty) -> Result<u16, ElementsError> {
+        match prop.first() {
+            Some(token) if prop.len() == 1 => font_weight(token).ok_or_else(|| {
+                ElementsError::InvalidPropertyValue(format!(
+                    "Expected normal|bold|$num in 1..1000, got `{}`",
+                    token.to_string()
+                ))
+            }),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected normal|bold|$num, got `{}`",
+                prop.to_string()
             ))),
         }
     }
 }
 
+fn font_weight(token: &StylePropertyToken) -> Option<u16> {
+    match token {
+        StylePropertyToken::Identifier(ident) if ident == "normal" => Some(400),
+        StylePropertyToken::Identifier(ident) if ident == "bold" => Some(700),
+        StylePropertyToken::Number(num) if (1. ..=1000.).contains(&num.to_float()) => {
+            Some(num.to_float().round() as u16)
+        }
+        _ => None,
+    }
+}
+
+/// Parses the value of `font` shorthand: the optional style and
+/// weight followed by the optional comma-separated families
+fn font(prop: &StyleProperty) -> Result<(FontStyle, u16, Vec<String>), ElementsError> {
+    let mut style = FontStyle::Normal;
+    let mut weight = 400;
+    let mut tokens = prop.iter().peekable();
+    while let Some(token) = tokens.peek() {
+        match token {
+            StylePropertyToken::Identifier(ident) if ident == "normal" || ident == "regular" => {}
+            StylePropertyToken::Identifier(ident) if ident == "italic" || ident == "oblique" => {
+                style = FontStyle::Italic
+            }
+            StylePropertyToken::Identifier(ident) if ident == "bold-italic" => {
+                style = FontStyle::Italic;
+                weight = 700;
+            }
+            token => match font_weight(token) {
+                Some(parsed) => weight = parsed,
+                None => break,
+            },
+        }
+        tokens.next();
+    }
+    let rest: StyleProperty = StyleProperty(tokens.cloned().collect());
+    let family = if rest.is_empty() {
+        vec![DEFAULT_FONT_FAMILY.to_string()]
+    } else {
+        FontFamilyParser::parse(&rest)?
+    };
+    Ok((style, weight, family))
+}
+
+compound_style_property! {
+    #[doc = " Specify the style, the weight and the families of the font at once."]
+    #[doc = " The omitted parts take their initial values:"]
+    #[doc = " ```css"]
+    #[doc = " font: bold;"]
+    #[doc = " font: italic 300 \"Inter\", \"Exo 2\";"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Text -->"]
+    FontProperty("font", value)
+        Longhands = [
+            "font-style",
+            "font-weight",
+            "font-family",
+        ];
+    {
+        let (style, weight, family) = match value {
+            Variant::String(unparsed) => font(&StyleProperty::try_from(unparsed)?)?,
+            Variant::Style(prop) => font(&prop)?,
+            _ => {
+                return Err(ElementsError::InvalidPropertyValue(
+                    "Can't extract font from variant".to_string(),
+                ))
+            }
+        };
+        let mut props = HashMap::default();
+        props.insert(tag!("font-style"), PropertyValue::new(style));
+        props.insert(tag!("font-weight"), PropertyValue::new(weight));
+        props.insert(tag!("font-family"), PropertyValue::new(family));
+        Ok(props)
+    }
+}
+
 style_property! {
-    #[doc = " TODO: wtite FontProperty description"]
+    #[doc = " Specify the font families in the order of preference. The first family"]
+    #[doc = " registered with `@font-face` (or [`FontFaces::register`](crate::ess::FontFaces::register))"]
+    #[doc = " is used, the bundled `\"Exo 2\"` is used if none of them is registered:"]
+    #[doc = " ```css"]
+    #[doc = " font-family: \"Inter\", \"Exo 2\";"]
+    #[doc = " ```"]
     #[doc = " <!-- @property-category=Text -->"]
-    FontProperty("font") {
-        Default = "regular";
-        Item = FontPath;
-        Components = &'static mut Text;
-        Filters = With<Node>;
+    FontFamilyProperty("font-family") {
+        Default = "\"Exo 2\"";
+        Item = Vec<String>;
+        Components = Option<&'static mut FontDescriptor>;
+        Filters = With<Text>;
         AffectsVirtual = true;
-        Parser = FontParser;
-        Apply = |value, text, assets, commands, entity| {
-            if let FontPath::Custom(path) = value {
-                text
-                    .sections
-                    .iter_mut()
-                    .for_each(|section| section.style.font = assets.load(path));
-            } else {
-                let path = value.clone();
-                commands.add(move |world: &mut World| {
-                    let defaults = world.resource::<Defaults>();
-                    let font = match path {
-                        FontPath::Regular => defaults.regular_font.clone(),
-                        FontPath::Italic => defaults.italic_font.clone(),
-                        FontPath::Bold => defaults.bold_font.clone(),
-                        FontPath::BoldItalic => defaults.bold_italic_font.clone(),
-                        _ => defaults.regular_font.clone(),
-                    };
-                    world
-                        .entity_mut(entity)
-                        .get_mut::<Text>()
-                        .unwrap()
-                        .sections
-                        .iter_mut()
-                        .for_each(|section| section.style.font = font.clone());
-                });
-            }
+        Inherited = true;
+        Parser = FontFamilyParser;
+        Apply = |value, font, _assets, commands, entity| {
+            let value = value.clone();
+            update_component(font, commands, entity, move |f: &mut FontDescriptor| {
+                f.family.clone_from(&value)
+            });
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the weight of the font: `normal` is 400, `bold` is 700."]
+    #[doc = " The closest weight of the family is used:"]
+    #[doc = " ```css"]
+    #[doc = " font-weight: 600;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=Text -->"]
+    FontWeightProperty("font-weight") {
+        Default = "normal";
+        Item = u16;
+        Components = Option<&'static mut FontDescriptor>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = FontWeightParser;
+        Apply = |value, font, _assets, commands, entity| {
+            let value = *value;
+            update_component(font, commands, entity, move |f: &mut FontDescriptor| {
+                f.weight = value
+            });
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the style of the font:"]
+    #[doc = " ```css"]
+    #[doc = " font-style: italic;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=normal|italic|oblique -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    FontStyleProperty("font-style") {
+        Default = "normal";
+        Item = FontStyle;
+        Components = Option<&'static mut FontDescriptor>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<FontStyle>;
+        Apply = |value, font, _assets, commands, entity| {
+            let value = *value;
+            update_component(font, commands, entity, move |f: &mut FontDescriptor| {
+                f.style = value
+            });
         };
     }
 }
@@ -96,6 +222,8 @@ style_property! {
         Components = &'static mut Text;
         Filters = With<Node>;
         AffectsVirtual = true;
+        Inherited = true;
+        Interpolated = true;
         Parser = parse::ColorParser;
         Apply = |value, text, _assets, _commands, _entity| {
             // TODO: mark it deprecated
@@ -111,11 +239,13 @@ style_property! {
     #[doc = " TODO: write FontSizeProperty description"]
     #[doc = " <!-- @property-category=Text -->"]
     FontSizeProperty("font-size") {
-        Default = "24";
+        Default = "22px";
         Item = f32;
         Components = &'static mut Text;
         Filters = With<Node>;
         AffectsVirtual = true;
+        Inherited = true;
+        Interpolated = true;
         Parser = parse::NumParser;
         Apply = |value, text, _assets, _commands, _entity| {
             text
@@ -125,6 +255,186 @@ style_property! {
         };
     }
 }
+
+style_property! {
+    #[doc = " Specify the horizontal alignment of the text lines:"]
+    #[doc = " ```css"]
+    #[doc = " text-align: center;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=left|center|right -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    TextAlignProperty("text-align") {
+        Default = "left";
+        Item = JustifyText;
+        Components = &'static mut Text;
+        Filters = With<Node>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<JustifyText>;
+        Apply = |value, text, _assets, _commands, _entity| {
+            if &text.justify != value {
+                text.justify = *value;
+            }
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify if the text lines are wrapped to fit the element:"]
+    #[doc = " ```css"]
+    #[doc = " white-space: nowrap;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=normal|nowrap -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    WhiteSpaceProperty("white-space") {
+        Default = "normal";
+        Item = WhiteSpace;
+        Components = Option<&'static mut TextLayout>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<WhiteSpace>;
+        Apply = |value, layout, _assets, commands, entity| {
+            let value = *value;
+            update_component(layout, commands, entity, move |l: &mut TextLayout| l.white_space = value);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify where the wrapped text lines are broken: `normal` breaks"]
+    #[doc = " them between the words, `break-all` between any characters:"]
+    #[doc = " ```css"]
+    #[doc = " word-break: break-all;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=normal|break-all -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    WordBreakProperty("word-break") {
+        Default = "normal";
+        Item = WordBreak;
+        Components = Option<&'static mut TextLayout>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<WordBreak>;
+        Apply = |value, layout, _assets, commands, entity| {
+            let value = *value;
+            update_component(layout, commands, entity, move |l: &mut TextLayout| l.word_break = value);
+        };
+    }
+}
+
+/// normal|$num|$px|$percent
+pub struct LineHeightParser;
+impl PropertyParser<LineHeight> for LineHeightParser {
+    fn parse(prop: &StyleProperty) -> Result<LineHeight, ElementsError> {
+        match prop.first() {
+            Some(StylePropertyToken::Identifier(ident)) if ident == "normal" => {
+                Ok(LineHeight::Normal)
+            }
+            Some(StylePropertyToken::Number(num)) => Ok(LineHeight::Factor(num.to_float())),
+            Some(StylePropertyToken::Percentage(num)) => {
+                Ok(LineHeight::Factor(num.to_float() / 100.))
+            }
+            Some(StylePropertyToken::Dimension(num, unit)) if unit == "px" => {
+                Ok(LineHeight::Px(num.to_float()))
+            }
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected normal|$num|$px|$percent, got `{}`",
+                prop.to_string()
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " Specify the distance between the baselines of the text lines:"]
+    #[doc = " `normal` one of the font, the number or the percent of the font size,"]
+    #[doc = " or the pixels. The extra space is split above and below the glyphs:"]
+    #[doc = " ```css"]
+    #[doc = " line-height: 1.5;"]
+    #[doc = " line-height: 30px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=normal|$num|$px|$percent -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    LineHeightProperty("line-height") {
+        Default = "normal";
+        Item = LineHeight;
+        Components = Option<&'static mut TextLayout>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = LineHeightParser;
+        Apply = |value, layout, _assets, commands, entity| {
+            let value = *value;
+            update_component(layout, commands, entity, move |l: &mut TextLayout| l.line_height = value);
+        };
+    }
+}
+
+/// normal|$px
+pub struct LetterSpacingParser;
+impl PropertyParser<f32> for LetterSpacingParser {
+    fn parse(prop: &StyleProperty) -> Result<f32, ElementsError> {
+        match prop.first() {
+            Some(StylePropertyToken::Identifier(ident)) if ident == "normal" => Ok(0.),
+            Some(StylePropertyToken::Dimension(num, unit)) if unit == "px" => Ok(num.to_float()),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected normal|$px, got `{}`",
+                prop.to_string()
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " Specify the additional space after each character, the wrapped lines"]
+    #[doc = " and the size of the element take it into account:"]
+    #[doc = " ```css"]
+    #[doc = " letter-spacing: 2px;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=normal|$px -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    LetterSpacingProperty("letter-spacing") {
+        Default = "normal";
+        Item = f32;
+        Components = Option<&'static mut TextLayout>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = LetterSpacingParser;
+        Apply = |value, layout, _assets, commands, entity| {
+            let value = *value;
+            update_component(layout, commands, entity, move |l: &mut TextLayout| l.letter_spacing = value);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify how the text not fitting its parent is shown: `clip` cuts it,"]
+    #[doc = " `ellipsis` truncates it with `…`. Only the text with `white-space: nowrap`"]
+    #[doc = " is truncated:"]
+    #[doc = " ```css"]
+    #[doc = " white-space: nowrap;"]
+    #[doc = " overflow: clip;"]
+    #[doc = " text-overflow: ellipsis;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=clip|ellipsis -->"]
+    #[doc = " <!-- @property-category=Text -->"]
+    TextOverflowProperty("text-overflow") {
+        Default = "clip";
+        Item = TextOverflow;
+        Components = Option<&'static mut TextLayout>;
+        Filters = With<Text>;
+        AffectsVirtual = true;
+        Parser = parse::IdentifierParser<TextOverflow>;
+        Apply = |value, layout, _assets, commands, entity| {
+            let value = *value;
+            update_component(layout, commands, entity, move |l: &mut TextLayout| l.text_overflow = value);
+        };
+    }
+}
+
 //     /// Applies the `vertical-align` property on [`TextAlignment::vertical`](`TextAlignment`) property of matched [`Text`] components.
 //     #[derive(Default)]
 //     pub(crate) struct VerticalAlignProperty;
@@ -234,3 +544,88 @@ style_property! {
 //                 .for_each(|section| section.value = cache.clone());
 //         }
 //     }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ess::CompoundProperty;
+    use std::str::FromStr;
+
+    fn prop(value: &str) -> StyleProperty {
+        StyleProperty::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn parse_text_layout() {
+        assert_eq!(
+            parse::IdentifierParser::<JustifyText>::parse(&prop("center")),
+            Ok(JustifyText::Center)
+        );
+        assert_eq!(
+            parse::IdentifierParser::<WhiteSpace>::parse(&prop("nowrap")),
+            Ok(WhiteSpace::NoWrap)
+        );
+        assert_eq!(
+            parse::IdentifierParser::<WordBreak>::parse(&prop("break-all")),
+            Ok(WordBreak::BreakAll)
+        );
+        assert_eq!(
+            parse::IdentifierParser::<TextOverflow>::parse(&prop("ellipsis")),
+            Ok(TextOverflow::Ellipsis)
+        );
+        assert!(parse::IdentifierParser::<WhiteSpace>::parse(&prop("pre")).is_err());
+
+        assert_eq!(
+            LineHeightParser::parse(&prop("normal")),
+            Ok(LineHeight::Normal)
+        );
+        assert_eq!(
+            LineHeightParser::parse(&prop("1.5")),
+            Ok(LineHeight::Factor(1.5))
+        );
+        assert_eq!(
+            LineHeightParser::parse(&prop("150%")),
+            Ok(LineHeight::Factor(1.5))
+        );
+        assert_eq!(
+            LineHeightParser::parse(&prop("30px")),
+            Ok(LineHeight::Px(30.))
+        );
+        assert!(LineHeightParser::parse(&prop("2em")).is_err());
+
+        assert_eq!(LetterSpacingParser::parse(&prop("normal")), Ok(0.));
+        assert_eq!(LetterSpacingParser::parse(&prop("2px")), Ok(2.));
+        assert!(LetterSpacingParser::parse(&prop("2")).is_err());
+    }
+
+    #[test]
+    fn parse_font() {
+        assert_eq!(
+            FontFamilyParser::parse(&prop("\"Inter\", Open Sans")),
+            Ok(vec!["Inter".to_string(), "Open Sans".to_string()])
+        );
+        assert!(FontFamilyParser::parse(&prop("Inter, 12px")).is_err());
+        assert_eq!(FontWeightParser::parse(&prop("bold")), Ok(700));
+        assert_eq!(FontWeightParser::parse(&prop("300")), Ok(300));
+        assert!(FontWeightParser::parse(&prop("0")).is_err());
+
+        assert_eq!(
+            font(&prop("bold")),
+            Ok((FontStyle::Normal, 700, vec!["Exo 2".to_string()]))
+        );
+        assert_eq!(
+            font(&prop("italic 300 \"Inter\", \"Exo 2\"")),
+            Ok((
+                FontStyle::Italic,
+                300,
+                vec!["Inter".to_string(), "Exo 2".to_string()]
+            ))
+        );
+        assert_eq!(
+            font(&prop("bold-italic")),
+            Ok((FontStyle::Italic, 700, vec!["Exo 2".to_string()]))
+        );
+        let props = FontProperty::extract(Variant::String("bold Inter".to_string())).unwrap();
+        assert_eq!(props.len(), 3);
+    }
+}
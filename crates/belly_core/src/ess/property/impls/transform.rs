@@ -0,0 +1,221 @@
+use crate::ess::PropertyParser;
+use crate::ess::StyleProperty;
+use crate::ess::StylePropertyMethods;
+use crate::ess::StylePropertyToken;
+use crate::ess::{update_component, TransformFunction, UiTransform};
+use crate::style_property;
+use crate::ElementsError;
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+fn angle(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Dimension(num, unit) => match unit.as_str() {
+            "deg" => Ok(num.to_float().to_radians()),
+            "rad" => Ok(num.to_float()),
+            "grad" => Ok(num.to_float() * PI / 200.),
+            "turn" => Ok(num.to_float() * 2. * PI),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $angle, got `{}`",
+                token.to_string()
+            ))),
+        },
+        StylePropertyToken::Number(num) if num.to_float() == 0. => Ok(0.),
+        _ => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $angle, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+fn length(token: &StylePropertyToken) -> Result<Val, ElementsError> {
+    match token {
+        StylePropertyToken::Number(num) if num.to_float() == 0. => Ok(Val::Px(0.)),
+        token => match token.val()? {
+            Val::Auto => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $val, got `{}`",
+                token.to_string()
+            ))),
+            val => Ok(val),
+        },
+    }
+}
+
+fn number(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Number(num) => Ok(num.to_float()),
+        StylePropertyToken::Percentage(num) => Ok(num.to_float() / 100.),
+        _ => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $num, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// none|$transform
+pub struct TransformParser;
+impl PropertyParser<Vec<TransformFunction>> for TransformParser {
+    fn parse(prop: &StyleProperty) -> Result<Vec<TransformFunction>, ElementsError> {
+        if prop.len() == 1 && prop[0].is_ident("none") {
+            return Ok(vec![]);
+        }
+        let mut functions = vec![];
+        for token in prop.iter() {
+            let StylePropertyToken::Function(function) = token else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected none|$transform, got `{}`",
+                    token.to_string()
+                )));
+            };
+            let args = function.args.as_slice();
+            let function = match (function.name.to_lowercase().as_str(), args) {
+                ("translate", [x]) => TransformFunction::Translate(length(x)?, Val::Px(0.)),
+                ("translate", [x, y]) => TransformFunction::Translate(length(x)?, length(y)?),
+                ("translatex", [x]) => TransformFunction::Translate(length(x)?, Val::Px(0.)),
+                ("translatey", [y]) => TransformFunction::Translate(Val::Px(0.), length(y)?),
+                ("rotate", [a]) => TransformFunction::Rotate(angle(a)?),
+                ("scale", [s]) => TransformFunction::Scale(number(s)?, number(s)?),
+                ("scale", [x, y]) => TransformFunction::Scale(number(x)?, number(y)?),
+                ("scalex", [x]) => TransformFunction::Scale(number(x)?, 1.),
+                ("scaley", [y]) => TransformFunction::Scale(1., number(y)?),
+                _ => {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Unsupported transform function `{}`",
+                        token.to_string()
+                    )))
+                }
+            };
+            functions.push(function);
+        }
+        Ok(functions)
+    }
+}
+
+/// $val|left|center|right $val|top|center|bottom
+pub struct TransformOriginParser;
+impl PropertyParser<(Val, Val)> for TransformOriginParser {
+    fn parse(prop: &StyleProperty) -> Result<(Val, Val), ElementsError> {
+        let keyword = |token: &StylePropertyToken| match token {
+            StylePropertyToken::Identifier(ident) => match ident.as_str() {
+                "left" | "top" => Some(Val::Percent(0.)),
+                "center" => Some(Val::Percent(50.)),
+                "right" | "bottom" => Some(Val::Percent(100.)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let value = |token: &StylePropertyToken| match keyword(token) {
+            Some(val) => Ok(val),
+            None => length(token),
+        };
+        let vertical =
+            |token: &StylePropertyToken| token.is_ident("top") || token.is_ident("bottom");
+        let horizontal =
+            |token: &StylePropertyToken| token.is_ident("left") || token.is_ident("right");
+        match prop.as_slice() {
+            [y] if vertical(y) => Ok((Val::Percent(50.), value(y)?)),
+            [x] => Ok((value(x)?, Val::Percent(50.))),
+            // `top left` is the same as `left top`
+            [y, x] if vertical(y) || horizontal(x) => Ok((value(x)?, value(y)?)),
+            [x, y] => Ok((value(x)?, value(y)?)),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected 1 or 2 values of transform origin, got `{}`",
+                prop.to_string()
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " Specify the transformation of the element: the list of `translate(x, y)`,"]
+    #[doc = " `translateX(x)`, `translateY(y)`, `rotate(angle)`, `scale(x, y)`, `scaleX(x)`"]
+    #[doc = " and `scaleY(y)` functions or `none`. The element is transformed after the"]
+    #[doc = " layout, so the other elements aren't moved, but the pointer hits the element"]
+    #[doc = " where it is drawn. The percents of `translate` are relative to the size of the"]
+    #[doc = " element, the angles are in `deg`, `rad`, `grad` or `turn`:"]
+    #[doc = " ```css"]
+    #[doc = " transform: translate(10px, 0) rotate(15deg) scale(1.1);"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=none|$transform -->"]
+    #[doc = " <!-- @property-category=Transform -->"]
+    TransformProperty("transform") {
+        Default = "none";
+        Item = Vec<TransformFunction>;
+        Components = Option<&'static mut UiTransform>;
+        Filters = With<Node>;
+        Interpolated = true;
+        Parser = TransformParser;
+        Apply = |value, transform, _assets, commands, entity| {
+            if !value.is_empty() || transform.is_some() {
+                let value = value.clone();
+                update_component(transform, commands, entity, move |t: &mut UiTransform| t.functions.clone_from(&value));
+            }
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify the point of the element the `transform` is applied around,"]
+    #[doc = " relative to the top-left corner of the element:"]
+    #[doc = " ```css"]
+    #[doc = " transform-origin: left top;"]
+    #[doc = " transform-origin: 20px 50%;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$val|left|center|right $val|top|center|bottom -->"]
+    #[doc = " <!-- @property-category=Transform -->"]
+    TransformOriginProperty("transform-origin") {
+        Default = "center";
+        Item = (Val, Val);
+        Components = Option<&'static mut UiTransform>;
+        Filters = With<Node>;
+        Parser = TransformOriginParser;
+        Apply = |value, transform, _assets, commands, entity| {
+            if *value != UiTransform::default().origin || transform.is_some() {
+                let value = *value;
+                update_component(transform, commands, entity, move |t: &mut UiTransform| t.origin = value);
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn prop(value: &str) -> StyleProperty {
+        StyleProperty::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn parse_transform() {
+        let functions =
+            TransformParser::parse(&prop("translate(10px, 0) rotate(90deg) scale(1.1)"));
+        assert_eq!(
+            functions.unwrap(),
+            vec![
+                TransformFunction::Translate(Val::Px(10.), Val::Px(0.)),
+                TransformFunction::Rotate(PI / 2.),
+                TransformFunction::Scale(1.1, 1.1),
+            ]
+        );
+        let functions = TransformParser::parse(&prop("translateY(-50%) scaleX(2) rotate(0.5turn)"));
+        assert_eq!(
+            functions.unwrap(),
+            vec![
+                TransformFunction::Translate(Val::Px(0.), Val::Percent(-50.)),
+                TransformFunction::Scale(2., 1.),
+                TransformFunction::Rotate(PI),
+            ]
+        );
+        assert_eq!(TransformParser::parse(&prop("none")).unwrap(), vec![]);
+        assert!(TransformParser::parse(&prop("skew(10deg)")).is_err());
+        assert!(TransformParser::parse(&prop("rotate(10px)")).is_err());
+
+        let origin = |value| TransformOriginParser::parse(&prop(value)).unwrap();
+        assert_eq!(origin("center"), (Val::Percent(50.), Val::Percent(50.)));
+        assert_eq!(origin("top"), (Val::Percent(50.), Val::Percent(0.)));
+        assert_eq!(origin("top left"), (Val::Percent(0.), Val::Percent(0.)));
+        assert_eq!(origin("20px bottom"), (Val::Px(20.), Val::Percent(100.)));
+    }
+}
@@ -1,39 +1,94 @@
+mod animation;
+mod calc;
 pub mod colors;
 pub mod enums;
 pub mod impls;
+mod opacity;
 pub mod parse;
 mod style;
-use std::any::{type_name, Any};
+mod text_layout;
+mod transform;
+mod transition;
+mod vars;
+use std::any::{type_name, Any, TypeId};
 use std::sync::{Arc, RwLock};
 
+pub use self::animation::*;
+pub use self::calc::*;
 pub use self::colors::*;
+pub use self::opacity::*;
 pub use self::style::StyleProperty;
 pub use self::style::StylePropertyFunction;
 pub use self::style::StylePropertyMethods;
 pub use self::style::StylePropertyToken;
 pub use self::style::ToRectMap;
+pub use self::text_layout::*;
+pub use self::transform::*;
+pub use self::transition::*;
+pub use self::vars::*;
 use crate::tags::*;
 use crate::{
     element::*,
     eml::Variant,
-    ess::{ElementsBranch, StyleSheet, Styles},
-    ElementsError,
+    ess::{ComputeStyles, ComputedStyle},
+    tag, ElementsError,
 };
 use bevy::ecs::query::{QueryData, QueryFilter};
 use bevy::ui::UiSystem;
+use bevy::window::PrimaryWindow;
 use bevy::{
     ecs::query::QueryItem,
     prelude::*,
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
-use itertools::Itertools;
 
 pub struct PropertyPlugin;
 impl Plugin for PropertyPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<AnimationEvent>();
+        app.add_systems(PostUpdate, reapply_calc_system.after(UiSystem::Layout));
+        app.add_systems(
+            PostUpdate,
+            run_animations_system
+                .in_set(ApplyStyleProperties)
+                .after(ComputeStyles),
+        );
+        app.add_systems(
+            PostUpdate,
+            (
+                text_wrap_system
+                    .after(ApplyStyleProperties)
+                    .before(UiSystem::Layout),
+                text_leading_system
+                    .after(bevy::ui::widget::measure_text_system)
+                    .before(UiSystem::Layout),
+                text_overflow_system
+                    .after(UiSystem::Layout)
+                    .before(bevy::ui::widget::text_system),
+                opacity_system
+                    .after(ApplyStyleProperties)
+                    .before(UiSystem::Layout),
+                ui_transform_system
+                    .after(UiSystem::Layout)
+                    .before(TransformSystem::TransformPropagate),
+            ),
+        );
+
         // general
         app.register_property::<impls::BackgroundColorProperty>();
         app.register_property::<impls::ZIndexProperty>();
+        app.register_property::<impls::OpacityProperty>();
+        app.register_property::<impls::TransitionProperty>();
+
+        // animation
+        app.register_compound_property::<impls::animation::AnimationProperty>();
+        app.register_property::<impls::animation::AnimationNameProperty>();
+        app.register_property::<impls::animation::AnimationDurationProperty>();
+        app.register_property::<impls::animation::AnimationDelayProperty>();
+        app.register_property::<impls::animation::AnimationTimingFunctionProperty>();
+        app.register_property::<impls::animation::AnimationIterationCountProperty>();
+        app.register_property::<impls::animation::AnimationDirectionProperty>();
+        app.register_property::<impls::animation::AnimationFillModeProperty>();
 
         // layout control
         app.register_compound_property::<impls::layout_control::PositionProperty>();
@@ -77,6 +132,14 @@ impl Plugin for PropertyPlugin {
         app.register_property::<impls::spacing::ColumnGapProperty>();
         app.register_property::<impls::spacing::RowGapProperty>();
 
+        // border
+        app.register_property::<impls::border::BorderColorProperty>();
+        app.register_compound_property::<impls::border::BorderRadiusProperty>();
+        app.register_property::<impls::border::BorderTopLeftRadiusProperty>();
+        app.register_property::<impls::border::BorderTopRightRadiusProperty>();
+        app.register_property::<impls::border::BorderBottomRightRadiusProperty>();
+        app.register_property::<impls::border::BorderBottomLeftRadiusProperty>();
+
         // size constraints
         app.register_property::<impls::size_constraints::WidthProperty>();
         app.register_property::<impls::size_constraints::HeightProperty>();
@@ -88,8 +151,17 @@ impl Plugin for PropertyPlugin {
 
         // text
         app.register_property::<impls::text::ColorProperty>();
-        app.register_property::<impls::text::FontProperty>();
+        app.register_compound_property::<impls::text::FontProperty>();
+        app.register_property::<impls::text::FontFamilyProperty>();
+        app.register_property::<impls::text::FontWeightProperty>();
+        app.register_property::<impls::text::FontStyleProperty>();
         app.register_property::<impls::text::FontSizeProperty>();
+        app.register_property::<impls::text::TextAlignProperty>();
+        app.register_property::<impls::text::WhiteSpaceProperty>();
+        app.register_property::<impls::text::WordBreakProperty>();
+        app.register_property::<impls::text::LineHeightProperty>();
+        app.register_property::<impls::text::LetterSpacingProperty>();
+        app.register_property::<impls::text::TextOverflowProperty>();
 
         // stylebox
         app.register_compound_property::<impls::stylebox::StyleboxProperty>();
@@ -99,6 +171,10 @@ impl Plugin for PropertyPlugin {
         app.register_property::<impls::stylebox::StyleboxSliceProperty>();
         app.register_property::<impls::stylebox::StyleboxWidthProperty>();
 
+        // transform
+        app.register_property::<impls::transform::TransformProperty>();
+        app.register_property::<impls::transform::TransformOriginProperty>();
+
         // grid
         app.register_property::<impls::grid::GridAutoColumnsProperty>();
         app.register_property::<impls::grid::GridAutoRowsProperty>();
@@ -130,12 +206,44 @@ pub fn managed_default(default: &str) -> PropertyValue {
         }
     }
 }
-#[derive(Deref, Debug)]
-pub struct PropertyValue(Box<dyn Any + Send + Sync + 'static>);
+
+/// Updates the `component` of the `entity` used by several properties,
+/// inserting the default one if the entity doesn't have it yet. The
+/// component is marked changed only if the `update` changes it.
+pub(crate) fn update_component<C: Component + Clone + Default + PartialEq>(
+    component: Option<Mut<C>>,
+    commands: &mut Commands,
+    entity: Entity,
+    update: impl Fn(&mut C) + Send + 'static,
+) {
+    match component {
+        Some(mut component) => {
+            let mut updated = component.clone();
+            update(&mut updated);
+            component.set_if_neq(updated);
+        }
+        None => commands.add(move |world: &mut World| {
+            let Some(mut entity) = world.get_entity_mut(entity) else {
+                return;
+            };
+            if !entity.contains::<C>() {
+                entity.insert(C::default());
+            }
+            if let Some(mut component) = entity.get_mut::<C>() {
+                update(&mut component);
+            }
+        }),
+    }
+}
+
+/// Transformed property value. Cloning is cheap: the value is shared,
+/// so the same declaration block may be used by several rules.
+#[derive(Deref, Debug, Clone)]
+pub struct PropertyValue(Arc<dyn Any + Send + Sync + 'static>);
 
 impl PropertyValue {
     pub fn new<T: Any + Send + Sync + 'static>(value: T) -> PropertyValue {
-        PropertyValue(Box::new(value))
+        PropertyValue(Arc::new(value))
     }
 
     pub fn new_managed() -> PropertyValue {
@@ -149,6 +257,11 @@ impl PropertyValue {
         self.0.is::<ManagedPropertyValue>()
     }
 
+    /// Checks if both values share the same underlying value
+    pub fn ptr_eq(&self, other: &PropertyValue) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
     pub fn managed_default(&self) -> Option<&StyleProperty> {
         self.0.downcast_ref::<ManagedPropertyValue>().and_then(|s| {
             if s.0.is_empty() {
@@ -160,6 +273,39 @@ impl PropertyValue {
     }
 }
 
+/// CSS-wide keywords accepted by every property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleKeyword {
+    /// `inherit`: use the value of the parent element
+    Inherit,
+    /// `initial`: use the initial (default) value of the property
+    Initial,
+    /// `unset`: `inherit` for inherited properties, `initial` for the rest
+    Unset,
+}
+
+impl StyleKeyword {
+    pub fn parse(value: &str) -> Option<StyleKeyword> {
+        match value.trim() {
+            "inherit" => Some(StyleKeyword::Inherit),
+            "initial" => Some(StyleKeyword::Initial),
+            "unset" => Some(StyleKeyword::Unset),
+            _ => None,
+        }
+    }
+
+    pub fn from_variant(variant: &Variant) -> Option<StyleKeyword> {
+        match variant {
+            Variant::String(s) => StyleKeyword::parse(s),
+            Variant::Style(prop) if prop.len() == 1 => match &prop[0] {
+                StylePropertyToken::Identifier(ident) => StyleKeyword::parse(ident),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 impl From<PropertyValue> for Variant {
     fn from(v: PropertyValue) -> Self {
         Variant::Property(v)
@@ -169,6 +315,13 @@ impl From<PropertyValue> for Variant {
 /// Determines how a property should be parsed into exact value
 pub trait PropertyParser<T: Default + Any + Send + Sync> {
     fn parse(value: &StyleProperty) -> Result<T, ElementsError>;
+
+    /// Returns the warning about the valid `value` which isn't rendered
+    /// as declared, reported when the stylesheet or inline style is parsed.
+    fn warning(value: &StyleProperty) -> Option<String> {
+        let _ = value;
+        None
+    }
 }
 
 /// Determines how a property should interact and modify the [ecs world](`bevy::prelude::World`).
@@ -215,22 +368,75 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
         false
     }
 
+    /// Inherited properties take the value of the parent
+    /// element when there is no matching rule for the element.
+    fn inherited() -> bool {
+        false
+    }
+
+    /// The value used by `initial` keyword and by
+    /// inherited properties of the root elements.
+    fn initial() -> Option<PropertyValue> {
+        None
+    }
+
     fn docstring() -> &'static str {
         ""
     }
 
+    /// Interpolates the values of the property for the transitions declared
+    /// by `transition` property. The default implementation returns `None`,
+    /// so the new values are applied at once.
+    fn interpolate(from: &Self::Item, to: &Self::Item, t: f32) -> Option<Self::Item> {
+        let _ = (from, to, t);
+        None
+    }
+
+    /// Checks if the values of the property are equal, so the running
+    /// transition isn't restarted by the same target computed again. The
+    /// default implementation compares nothing: only the shared values
+    /// are the same.
+    fn same_value(a: &Self::Item, b: &Self::Item) -> bool {
+        let _ = (a, b);
+        false
+    }
+
+    /// Returns the warning about the valid `value` which isn't rendered
+    /// as declared, see [`PropertyParser::warning`].
+    fn warning(value: &StyleProperty) -> Option<String> {
+        Self::Parser::warning(value)
+    }
+
     /// Parses the [`PropertyValues`] into the [`Cache`](Property::Cache) value to be reused across multiple entities.
     ///
     /// This function is called only once, on the first time a matching property is found while applying style rule.
     /// If an error is returned, it is also cached so no more attempt are made.
     // fn parse(values: &StyleProperty) -> Result<Self::Item, ElementsError>;
 
+    /// Parses the value with the [`Parser`](Property::Parser). The `calc()` mixing
+    /// units is kept for the [`Val`] properties and evaluated when applied.
+    fn parse_value(value: &StyleProperty) -> Result<PropertyValue, ElementsError> {
+        Self::Parser::parse(value)
+            .map(PropertyValue::new)
+            .or_else(|e| match CalcValue::from_property(value) {
+                Some(calc) if TypeId::of::<Self::Item>() == TypeId::of::<Val>() => {
+                    Ok(PropertyValue::new(calc))
+                }
+                _ => Err(e),
+            })
+    }
+
     fn transform(variant: Variant) -> Result<PropertyValue, ElementsError> {
+        if let Some(keyword) = StyleKeyword::from_variant(&variant) {
+            return Ok(PropertyValue::new(keyword));
+        }
+        // transformed when the computed values of the element are known
+        if let Some(value) = DeferredValue::from_variant(Self::name(), &variant) {
+            return Ok(PropertyValue::new(value));
+        }
         match variant {
-            Variant::Style(p) => Self::Parser::parse(&p).map(|p| PropertyValue::new(p)),
-            Variant::String(s) => StyleProperty::try_from(s)
-                .and_then(|p| Self::Parser::parse(&p))
-                .map(|v| PropertyValue::new(v)),
+            Variant::Style(p) => Self::parse_value(&p),
+            Variant::String(s) => StyleProperty::try_from(s).and_then(|p| Self::parse_value(&p)),
             Variant::Boxed(b) => Ok(PropertyValue::new(*b.downcast::<Self::Item>().map_err(
                 |e| {
                     ElementsError::InvalidPropertyValue(format!(
@@ -265,110 +471,188 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
     /// [ecs world](`bevy::prelude::World`) and call [`apply`](Property::apply) function on every matched entity.
     ///
     /// The default implementation will cover most use cases, by just implementing [`apply`](Property::apply)
+    #[allow(clippy::type_complexity)]
     fn apply_defaults(
-        // mut cached_properties: Local<CachedProperties<Self>>,
-        mut components: Query<(Entity, Self::Components), (Changed<Element>, Self::Filters)>,
+        mut components: Query<
+            (
+                Entity,
+                &'static ComputedStyle,
+                Self::Components,
+                Option<&'static mut PropertyTransition<Self>>,
+            ),
+            (Changed<ComputedStyle>, Self::Filters),
+        >,
         mut commands: Commands,
         asset_server: Res<AssetServer>,
-        styles: Res<Styles>,
-        stylesheets: Res<Assets<StyleSheet>>,
-        parents: Query<&Parent>,
-        elements: Query<&Element>,
+        elements: Query<&'static Element>,
+        parents: Query<&'static Parent>,
+        nodes: Query<&'static Node>,
+        windows: Query<&'static Window, With<PrimaryWindow>>,
+        ui_scale: Option<Res<UiScale>>,
     ) {
         if components.is_empty() {
             return;
         }
-        // info!("[prop] changed {}", components.iter().count());
-        // TODO: this should be cached
-        let mut rules: Vec<_> = styles
-            .iter()
-            .filter_map(|h| stylesheets.get(h))
-            .flat_map(|s| s.iter())
-            .filter(|r| r.properties.contains_key(&Self::name()))
-            .collect();
-        rules.sort_by_key(|r| -r.selector.weight);
-
-        for (entity, components) in components.iter_mut() {
+        for (entity, computed, components, transition) in components.iter_mut() {
             let Ok(element) = elements.get(entity) else {
                 continue;
             };
             if element.is_virtual() && !Self::affects_virtual_elements() {
                 continue;
             }
-
-            // extract default value
-            let mut element_with_default = element;
-            let mut entity_with_default = entity;
-            let mut default = None;
-            loop {
-                if !element_with_default.is_virtual() {
-                    default = element_with_default.styles.get(&Self::name());
-                    break;
+            // the value is resolved by compute_styles_system: inline style,
+            // matched rule, inherited or initial value
+            let Some(property) = computed.get(Self::name()).map(|c| &c.value) else {
+                continue;
+            };
+            if let Some(calc) = property.downcast_ref::<CalcValue>() {
+                if let Some(mut transition) = transition {
+                    *transition = PropertyTransition::new(property.clone());
                 }
-                if let Ok(parent) = parents.get(entity_with_default) {
-                    entity_with_default = parent.get();
-                    if let Ok(element) = elements.get(entity_with_default) {
-                        element_with_default = element;
-                    } else {
-                        break;
+                // calc() mixing units is evaluated against the parent node
+                let size = parents
+                    .get(entity)
+                    .and_then(|parent| nodes.get(parent.get()))
+                    .map(|node| node.size())
+                    .unwrap_or_default();
+                let basis = if CalcValue::percent_of_height(Self::name()) {
+                    size.y
+                } else {
+                    size.x
+                };
+                let viewport = windows
+                    .get_single()
+                    .map(|window| window.size() / ui_scale.as_ref().map_or(1., |s| s.0))
+                    .unwrap_or_default();
+                let value = Val::Px(calc.evaluate(basis, viewport));
+                if let Some(value) = (&value as &dyn Any).downcast_ref::<Self::Item>() {
+                    Self::apply(value, components, &asset_server, &mut commands, entity);
+                    commands.entity(entity).insert(CalcStyle);
+                }
+                continue;
+            }
+            let Some(value) = property.downcast_ref::<Self::Item>() else {
+                error!(
+                    "Unable to apply {} property: inconsistent Variant {:?}",
+                    Self::name(),
+                    property
+                );
+                continue;
+            };
+            let spec = computed
+                .get(tag!("transition"))
+                .and_then(|t| t.value.downcast_ref::<Transitions>())
+                .and_then(|t| t.get(Self::name()))
+                .filter(|t| t.duration > 0.);
+            if let Some(mut transition) = transition {
+                let same = transition.target.ptr_eq(property)
+                    || transition
+                        .target
+                        .downcast_ref::<Self::Item>()
+                        .is_some_and(|target| Self::same_value(target, value));
+                if same {
+                    if transition.running.is_some() {
+                        // keep running to the same value
+                        continue;
                     }
                 } else {
-                    break;
+                    // start from the currently applied value, so the changed
+                    // target restarts the transition smoothly
+                    let from = transition.current();
+                    *transition = PropertyTransition::new(property.clone());
+                    if let (Some(spec), Some(from)) = (spec, from) {
+                        if Self::interpolate(&from, value, 0.).is_some() {
+                            transition.running = Some(RunningTransition {
+                                from,
+                                elapsed: 0.,
+                                transition: spec.clone(),
+                            });
+                            continue;
+                        }
+                    }
                 }
+            } else if spec.is_some() && Self::interpolate(value, value, 0.).is_some() {
+                commands
+                    .entity(entity)
+                    .insert(PropertyTransition::<Self>::new(property.clone()));
             }
-            if default.is_some() && default.unwrap().is_managed() {
+            Self::apply(value, components, &asset_server, &mut commands, entity);
+        }
+    }
+
+    /// Advances the running transitions of the property and applies
+    /// the interpolated values.
+    #[allow(clippy::type_complexity)]
+    fn animate_transitions(
+        mut transitions: Query<
+            (Entity, &'static mut PropertyTransition<Self>, Self::Components),
+            Self::Filters,
+        >,
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut transition, components) in transitions.iter_mut() {
+            let transition = transition.bypass_change_detection();
+            let Some(running) = transition.running.as_mut() else {
+                continue;
+            };
+            let Some(target) = transition.target.downcast_ref::<Self::Item>() else {
+                transition.running = None;
                 continue;
+            };
+            running.elapsed += time.delta_seconds();
+            let t = running.transition.progress(running.elapsed);
+            if t >= 1. {
+                Self::apply(target, components, &asset_server, &mut commands, entity);
+                transition.running = None;
+            } else if let Some(value) =
+                Self::interpolate(&running.from, target, running.transition.easing.ease(t))
+            {
+                Self::apply(&value, components, &asset_server, &mut commands, entity);
             }
+        }
+    }
 
-            // compute branch
-            let mut branch = ElementsBranch::new();
-            let mut tail = entity;
-            while let Ok(element) = elements.get(tail) {
-                if !element.is_virtual() {
-                    branch.insert(element);
-                }
-                if let Ok(parent) = parents.get(tail) {
-                    tail = parent.get();
-                } else {
-                    break;
-                }
+    /// Applies the values of the property sampled from `@keyframes` by the
+    /// running animations. The values `P` can't interpolate are switched
+    /// at the middle of each pair of keyframes.
+    #[allow(clippy::type_complexity)]
+    fn animate_keyframes(
+        mut animated: Query<
+            (
+                Entity,
+                Ref<'static, StyleAnimations>,
+                Ref<'static, ComputedStyle>,
+                Self::Components,
+            ),
+            Self::Filters,
+        >,
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+    ) {
+        for (entity, animations, computed, components) in animated.iter_mut() {
+            // the computed value is applied again when the style changes
+            if !animations.is_changed() && !computed.is_changed() {
+                continue;
             }
-            let property = default.or_else(|| {
-                rules
-                    .iter()
-                    .filter_map(|r| {
-                        if let Some(depth) = r.selector.match_depth(&branch) {
-                            Some((
-                                r.properties.get(&Self::name()).unwrap(),
-                                depth,
-                                r.selector.weight,
-                            ))
-                        } else {
-                            None
-                        }
-                    })
-                    .group_by(|(_prop, _depth, weight)| *weight)
-                    .into_iter()
-                    .map(|(_, group)| group)
-                    .next()
-                    .map(|properties| {
-                        let mut variants = properties.collect::<Vec<_>>();
-                        variants.sort_by_key(|(_prop, depth, _weight)| -(*depth as i16));
-                        let (value, _depth, _weight) = variants.pop().unwrap();
-                        value
-                    })
-            });
-
-            if let Some(property) = property {
-                if let Some(property) = property.downcast_ref::<Self::Item>() {
-                    Self::apply(property, components, &asset_server, &mut commands, entity);
-                } else {
-                    error!(
-                        "Unable to apply {} property: inconsistent Variant {:?}",
-                        Self::name(),
-                        property
-                    );
+            let Some(value) = animations.values.get(&Self::name()) else {
+                continue;
+            };
+            let (Some(from), Some(to)) = (
+                value.from.downcast_ref::<Self::Item>(),
+                value.to.downcast_ref::<Self::Item>(),
+            ) else {
+                continue;
+            };
+            match Self::interpolate(from, to, value.t) {
+                Some(value) => {
+                    Self::apply(&value, components, &asset_server, &mut commands, entity)
+                }
+                None if value.t < 0.5 => {
+                    Self::apply(from, components, &asset_server, &mut commands, entity)
                 }
+                None => Self::apply(to, components, &asset_server, &mut commands, entity),
             }
         }
     }
@@ -379,27 +663,93 @@ pub trait CompoundProperty: Default + Sized + Send + Sync + 'static {
     fn docstring() -> &'static str {
         ""
     }
+    /// Names of the properties the compound property extracts to.
+    /// Required to extract `inherit`, `initial` and `unset` keywords.
+    fn longhands() -> &'static [&'static str] {
+        &[]
+    }
     fn extract(value: Variant) -> Result<HashMap<Tag, PropertyValue>, ElementsError>;
+    /// Returns the warning about the valid `value` which isn't rendered
+    /// as declared, see [`PropertyParser::warning`].
+    fn warning(value: &StyleProperty) -> Option<String> {
+        let _ = value;
+        None
+    }
+    fn extract_value(value: Variant) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
+        let value = if let Some(keyword) = StyleKeyword::from_variant(&value) {
+            PropertyValue::new(keyword)
+        } else if let Some(value) = DeferredValue::from_variant(Self::name(), &value) {
+            // every longhand extracts its own part after substitution
+            PropertyValue::new(value)
+        } else {
+            return Self::extract(value);
+        };
+        Ok(Self::longhands()
+            .iter()
+            .map(|name| (name.as_tag(), value.clone()))
+            .collect())
+    }
     fn error(message: String) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
         Err(ElementsError::InvalidPropertyValue(message))
     }
 }
 
 pub(crate) type TransformProperty = fn(Variant) -> Result<PropertyValue, ElementsError>;
+pub(crate) type WarnProperty = fn(&StyleProperty) -> Option<String>;
+
+/// Returns the warning about the `value` of the property `name`
+/// reported by the `warnings` of the registered properties.
+fn property_warning(
+    warnings: &RwLock<HashMap<Tag, WarnProperty>>,
+    name: Tag,
+    value: &Variant,
+) -> Option<String> {
+    let warning = *warnings.read().unwrap().get(&name)?;
+    let warning = match value {
+        Variant::Style(value) => warning(value),
+        Variant::String(value) => warning(&StyleProperty::try_from(value.as_str()).ok()?),
+        _ => None,
+    }?;
+    Some(format!("{warning} (`{name}`)"))
+}
+
 #[derive(Default, Clone, Resource)]
-pub struct PropertyTransformer(Arc<RwLock<HashMap<Tag, TransformProperty>>>);
+pub struct PropertyTransformer(
+    Arc<RwLock<HashMap<Tag, TransformProperty>>>,
+    Arc<RwLock<HashMap<Tag, WarnProperty>>>,
+);
 unsafe impl Send for PropertyTransformer {}
 unsafe impl Sync for PropertyTransformer {}
 impl PropertyTransformer {
     #[cfg(test)]
     pub(crate) fn new(rules: HashMap<Tag, TransformProperty>) -> PropertyTransformer {
-        PropertyTransformer(Arc::new(RwLock::new(rules)))
+        PropertyTransformer(Arc::new(RwLock::new(rules)), default())
+    }
+
+    pub(crate) fn register<T: Property>(&self) {
+        self.0
+            .write()
+            .unwrap()
+            .entry(T::name())
+            .and_modify(|_| panic!("Property `{}` already registered.", T::name()))
+            .or_insert(T::transform);
+        self.1.write().unwrap().insert(T::name(), T::warning);
     }
+
+    /// Returns the warning about the valid `value` of the property `name`
+    /// which isn't rendered as declared.
+    pub(crate) fn warning(&self, name: Tag, value: &Variant) -> Option<String> {
+        property_warning(&self.1, name, value)
+    }
+
     pub(crate) fn transform(
         &self,
         name: Tag,
         value: Variant,
     ) -> Result<PropertyValue, ElementsError> {
+        if is_custom_property(name) {
+            return CustomProperty::transform(value);
+        }
         self.0
             .read()
             .unwrap()
@@ -411,14 +761,34 @@ impl PropertyTransformer {
 
 pub(crate) type ExtractProperty = fn(Variant) -> Result<HashMap<Tag, PropertyValue>, ElementsError>;
 #[derive(Default, Clone, Resource)]
-pub struct PropertyExtractor(Arc<RwLock<HashMap<Tag, ExtractProperty>>>);
+pub struct PropertyExtractor(
+    Arc<RwLock<HashMap<Tag, ExtractProperty>>>,
+    Arc<RwLock<HashMap<Tag, WarnProperty>>>,
+);
 unsafe impl Send for PropertyExtractor {}
 unsafe impl Sync for PropertyExtractor {}
 impl PropertyExtractor {
     #[cfg(test)]
     pub(crate) fn new(rules: HashMap<Tag, ExtractProperty>) -> PropertyExtractor {
-        PropertyExtractor(Arc::new(RwLock::new(rules)))
+        PropertyExtractor(Arc::new(RwLock::new(rules)), default())
+    }
+
+    pub(crate) fn register<T: CompoundProperty>(&self) {
+        self.0
+            .write()
+            .unwrap()
+            .entry(T::name())
+            .and_modify(|_| panic!("CompoundProperty `{}` already registered", T::name()))
+            .insert(T::extract_value);
+        self.1.write().unwrap().insert(T::name(), T::warning);
     }
+
+    /// Returns the warning about the valid `value` of the compound
+    /// property `name` which isn't rendered as declared.
+    pub(crate) fn warning(&self, name: Tag, value: &Variant) -> Option<String> {
+        property_warning(&self.1, name, value)
+    }
+
     pub(crate) fn is_compound_property(&self, name: Tag) -> bool {
         self.0.read().unwrap().contains_key(&name)
     }
@@ -437,6 +807,100 @@ impl PropertyExtractor {
     }
 }
 
+/// Inheritance, initial values and transformers of the registered properties.
+#[derive(Default, Resource)]
+pub struct PropertyRegistry {
+    inherited: HashSet<Tag>,
+    initial: HashMap<Tag, PropertyValue>,
+    transformers: HashMap<Tag, TransformProperty>,
+    extractors: HashMap<Tag, ExtractProperty>,
+    /// The values transformed from the substituted deferred ones, so the
+    /// equal values share the same [`PropertyValue`] across the computes
+    deferred: RwLock<HashMap<(Tag, Tag, StyleProperty), PropertyValue>>,
+}
+
+/// The number of the cached deferred values after which the cache is dropped,
+/// so the values driven by the changing custom properties don't pile up
+const MAX_DEFERRED_VALUES: usize = 4096;
+
+impl PropertyRegistry {
+    /// Checks if the property is inherited. Custom properties are always inherited.
+    pub fn is_inherited(&self, property: Tag) -> bool {
+        self.inherited.contains(&property) || is_custom_property(property)
+    }
+
+    pub fn inherited(&self) -> impl Iterator<Item = &Tag> {
+        self.inherited.iter()
+    }
+
+    pub fn initial(&self, property: Tag) -> Option<&PropertyValue> {
+        self.initial.get(&property)
+    }
+
+    /// Transforms the deferred value of the `property` when `var()` functions
+    /// and font-relative units are substituted: the compound property is
+    /// extracted first and the value of the `property` is taken. The same
+    /// substituted value is transformed once, so the computed styles holding
+    /// it are compared by [`PropertyValue::ptr_eq`].
+    pub(crate) fn transform_deferred(
+        &self,
+        property: Tag,
+        value: DeferredValue,
+    ) -> Result<PropertyValue, ElementsError> {
+        let key = (property, value.property, value.value);
+        if let Some(cached) = self.deferred.read().unwrap().get(&key) {
+            return Ok(cached.clone());
+        }
+        let transformed = self.transform_substituted(property, key.1, key.2.clone())?;
+        let mut deferred = self.deferred.write().unwrap();
+        if deferred.len() >= MAX_DEFERRED_VALUES {
+            deferred.clear();
+        }
+        deferred.insert(key, transformed.clone());
+        Ok(transformed)
+    }
+
+    fn transform_substituted(
+        &self,
+        property: Tag,
+        declared: Tag,
+        value: StyleProperty,
+    ) -> Result<PropertyValue, ElementsError> {
+        let variant = Variant::style(value);
+        if declared == property {
+            let transform = self
+                .transformers
+                .get(&property)
+                .ok_or(ElementsError::UnsupportedProperty(property.to_string()))?;
+            transform(variant)
+        } else {
+            let extract = self
+                .extractors
+                .get(&declared)
+                .ok_or(ElementsError::UnsupportedProperty(declared.to_string()))?;
+            extract(variant)?
+                .remove(&property)
+                .ok_or(ElementsError::InvalidPropertyValue(format!(
+                    "Unable to extract {property} from {declared}"
+                )))
+        }
+    }
+
+    pub(crate) fn register<T: Property>(&mut self) {
+        if T::inherited() {
+            self.inherited.insert(T::name());
+        }
+        if let Some(initial) = T::initial() {
+            self.initial.insert(T::name(), initial);
+        }
+        self.transformers.insert(T::name(), T::transform);
+    }
+
+    pub(crate) fn register_compound<T: CompoundProperty>(&mut self) {
+        self.extractors.insert(T::name(), T::extract_value);
+    }
+}
+
 pub trait RegisterProperty {
     fn register_property<T: Property + 'static>(&mut self) -> &mut Self;
     fn register_compound_property<T: CompoundProperty + 'static>(&mut self) -> &mut Self;
@@ -446,18 +910,28 @@ impl RegisterProperty for bevy::prelude::App {
     fn register_property<T: Property + 'static>(&mut self) -> &mut Self {
         self.world_mut()
             .get_resource_or_insert_with(PropertyTransformer::default)
-            .0
-            .write()
-            .unwrap()
-            .entry(T::name())
-            .and_modify(|_| panic!("Property `{}` already registered.", T::name()))
-            .or_insert(T::transform);
+            .register::<T>();
+        self.world_mut()
+            .get_resource_or_insert_with(PropertyRegistry::default)
+            .register::<T>();
         self.add_systems(
             PostUpdate,
-            T::apply_defaults
-                .in_set(ApplyStyleProperties)
-                .after(InvalidateElements)
-                .before(UiSystem::Layout),
+            (
+                T::apply_defaults
+                    .in_set(ApplyStyleProperties)
+                    .after(InvalidateElements)
+                    .after(ComputeStyles)
+                    .after(run_animations_system)
+                    .before(UiSystem::Layout),
+                T::animate_transitions
+                    .in_set(ApplyStyleProperties)
+                    .after(T::apply_defaults)
+                    .before(UiSystem::Layout),
+                T::animate_keyframes
+                    .in_set(ApplyStyleProperties)
+                    .after(T::animate_transitions)
+                    .before(UiSystem::Layout),
+            ),
         );
         self
     }
@@ -465,12 +939,10 @@ impl RegisterProperty for bevy::prelude::App {
     fn register_compound_property<T: CompoundProperty + 'static>(&mut self) -> &mut Self {
         self.world_mut()
             .get_resource_or_insert_with(PropertyExtractor::default)
-            .0
-            .write()
-            .unwrap()
-            .entry(T::name())
-            .and_modify(|_| panic!("CompoundProperty `{}` already registered", T::name()))
-            .insert(T::extract);
+            .register::<T>();
+        self.world_mut()
+            .get_resource_or_insert_with(PropertyRegistry::default)
+            .register_compound::<T>();
         self
     }
 }
@@ -484,6 +956,8 @@ macro_rules! style_property {
         Components = $components:ty;
         Filters = $filters:ty;
         AffectsVirtual = $affects_virtual:literal;
+        $(Inherited = $inherited:literal;)?
+        $(Interpolated = $interpolated:literal;)?
         Parser = $parser:ty;
         Apply = | $value:ident, $component:ident, $assets:ident, $commands:ident, $entity:ident |
             $body:expr;
@@ -507,6 +981,26 @@ macro_rules! style_property {
                 $affects_virtual
             }
 
+            $(fn inherited() -> bool {
+                $inherited
+            })?
+
+            $(fn interpolate(from: &Self::Item, to: &Self::Item, t: f32) -> Option<Self::Item> {
+                if $interpolated {
+                    $crate::ess::Interpolate::interpolate(from, to, t)
+                } else {
+                    None
+                }
+            }
+
+            fn same_value(a: &Self::Item, b: &Self::Item) -> bool {
+                $interpolated && a == b
+            })?
+
+            fn initial() -> Option<$crate::ess::PropertyValue> {
+                Self::transform($crate::eml::Variant::String($default.to_string())).ok()
+            }
+
             fn apply(
                 $value: &Self::Item,
                 #[allow(unused_mut)]
@@ -525,6 +1019,8 @@ macro_rules! style_property {
         Item = $item:ty;
         Components = $components:ty;
         Filters = $filters:ty;
+        $(Inherited = $inherited:literal;)?
+        $(Interpolated = $interpolated:literal;)?
         Parser = $parser:ty;
         Apply = | $value:ident, $component:ident, $assets:ident, $commands:ident, $entity:ident |
             $body:expr;
@@ -536,6 +1032,8 @@ macro_rules! style_property {
             Components = $components;
             Filters = $filters;
             AffectsVirtual = false;
+            $(Inherited = $inherited;)?
+            $(Interpolated = $interpolated;)?
             Parser = $parser;
             Apply = | $value, $component, $assets, $commands, $entity |
                 $body;
@@ -547,7 +1045,9 @@ macro_rules! style_property {
 macro_rules! compound_style_property {
     (   $(#[doc = $s:literal])*
         $typename:ident($prop_name:literal, $value:ident)
-            $body:expr
+            $(Longhands = [$($longhand:literal),* $(,)?];)?
+            $(Warning = $warning:path;)?
+            $body:block
     ) => {
         #[derive(Default)]
         $(#[doc = $s])*
@@ -557,6 +1057,12 @@ macro_rules! compound_style_property {
             fn name() -> $crate::Tag {
                 $crate::tag!($prop_name)
             }
+            $(fn longhands() -> &'static [&'static str] {
+                &[$($longhand),*]
+            })?
+            $(fn warning(value: &$crate::ess::StyleProperty) -> Option<String> {
+                $warning(value)
+            })?
             fn extract($value: $crate::eml::Variant) -> Result<::bevy::utils::HashMap<$crate::Tag, $crate::ess::PropertyValue>, $crate::ElementsError> {
                 $body
             }
@@ -0,0 +1,302 @@
+use crate::ess::InlineText;
+use bevy::color::Alpha;
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_stylebox::Stylebox;
+
+/// The opacity of the element and its descendants, set by `opacity` property.
+/// The alpha of the background, the border, the text, the image and the
+/// stylebox of every entity in the subtree is multiplied by the product of
+/// the opacities of its ancestors. Can be driven by the bindings as any other
+/// component when the property is `managed()`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Opacity(pub f32);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Opacity(1.)
+    }
+}
+
+/// The alpha of the single color before and after the opacity is applied
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FadedAlpha {
+    base: f32,
+    applied: f32,
+}
+
+impl FadedAlpha {
+    /// Applies the `opacity` to the `alpha` of the color, returns the alpha
+    /// to set if it differs. The base alpha is taken from the color unless
+    /// it still holds the applied one, so the colors changed by the
+    /// properties or the bindings are followed.
+    fn fade(slot: &mut Option<FadedAlpha>, alpha: f32, opacity: f32) -> Option<f32> {
+        let base = match slot {
+            Some(faded) if faded.applied == alpha => faded.base,
+            _ => alpha,
+        };
+        let applied = base * opacity;
+        *slot = Some(FadedAlpha { base, applied });
+        (alpha != applied).then_some(applied)
+    }
+}
+
+/// The alphas of the colors of the entity faded by [`Opacity`]
+#[derive(Component, Default)]
+pub(crate) struct FadedColors {
+    /// The product of the opacities of the entity and its ancestors
+    opacity: f32,
+    background: Option<FadedAlpha>,
+    border: Option<FadedAlpha>,
+    image: Option<FadedAlpha>,
+    stylebox: Option<FadedAlpha>,
+    text: Vec<Option<FadedAlpha>>,
+}
+
+type Colors = (
+    Option<&'static mut FadedColors>,
+    Option<&'static mut BackgroundColor>,
+    Option<&'static mut BorderColor>,
+    Option<&'static mut UiImage>,
+    Option<&'static mut Stylebox>,
+    Option<&'static mut Text>,
+    Has<InlineText>,
+);
+
+/// Multiplies the alpha of the colors in the subtrees of [`Opacity`]. The
+/// subtrees are walked from the entities with the changed or removed opacity
+/// and from the faded entities with the changed children. The faded colors
+/// changed by the properties or the bindings are faded again one by one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn opacity_system(
+    mut commands: Commands,
+    changed_opacities: Query<Entity, Changed<Opacity>>,
+    mut removed_opacities: RemovedComponents<Opacity>,
+    changed_children: Query<(Entity, &Children), Changed<Children>>,
+    faded: Query<Entity, With<FadedColors>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    opacities: Query<&Opacity>,
+    mut colors: Query<Colors>,
+) {
+    // the product of the opacities of the entity and its ancestors
+    let inherited = |entity| {
+        std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .filter_map(|entity| opacities.get(entity).ok())
+            .map(|opacity| opacity.0.clamp(0., 1.))
+            .product::<f32>()
+    };
+    let mut starts: Vec<_> = changed_opacities.iter().collect();
+    starts.extend(removed_opacities.read());
+    for (entity, kids) in changed_children.iter() {
+        if inherited(entity) < 1. || kids.iter().any(|kid| faded.contains(*kid)) {
+            starts.push(entity);
+        }
+    }
+    let mut stack: Vec<_> = starts
+        .into_iter()
+        .map(|entity| {
+            let opacity = parents
+                .get(entity)
+                .map_or(1., |parent| inherited(parent.get()));
+            (entity, opacity)
+        })
+        .collect();
+    let mut visited = HashSet::new();
+    while let Some((entity, opacity)) = stack.pop() {
+        if !visited.insert(entity) {
+            continue;
+        }
+        let opacity = opacities
+            .get(entity)
+            .map_or(opacity, |own| opacity * own.0.clamp(0., 1.));
+        // nothing is faded in the subtree apart from the subtrees of
+        // the nested opacities, which don't depend on this one
+        if opacity >= 1. && !faded.contains(entity) {
+            continue;
+        }
+        if let Ok(kids) = children.get(entity) {
+            stack.extend(kids.iter().map(|kid| (*kid, opacity)));
+        }
+        if let Ok(item) = colors.get_mut(entity) {
+            fade(&mut commands, entity, opacity, item);
+        }
+    }
+
+    for entity in faded.iter() {
+        if visited.contains(&entity) {
+            continue;
+        }
+        let Ok(item) = colors.get_mut(entity) else {
+            continue;
+        };
+        let (faded, background, border, image, stylebox, text, _) = &item;
+        let changed = background.as_ref().is_some_and(|c| c.is_changed())
+            || border.as_ref().is_some_and(|c| c.is_changed())
+            || image.as_ref().is_some_and(|c| c.is_changed())
+            || stylebox.as_ref().is_some_and(|c| c.is_changed())
+            || text.as_ref().is_some_and(|c| c.is_changed());
+        if let (true, Some(opacity)) = (changed, faded.as_ref().map(|f| f.opacity)) {
+            fade(&mut commands, entity, opacity, item);
+        }
+    }
+}
+
+/// Fades the colors of the `entity` by `opacity`, restores them and
+/// removes [`FadedColors`] when `opacity` is 1
+fn fade(commands: &mut Commands, entity: Entity, opacity: f32, item: QueryItem<Colors>) {
+    let (faded, background, border, image, stylebox, text, inline) = item;
+    // the inline text copies the colors of the faded text nodes
+    if inline {
+        return;
+    }
+    let mut inserted = None;
+    let faded = match faded {
+        Some(faded) => faded.into_inner(),
+        None => inserted.insert(FadedColors::default()),
+    };
+    faded.opacity = opacity;
+    if let Some(mut background) = background {
+        if let Some(alpha) = FadedAlpha::fade(&mut faded.background, background.0.alpha(), opacity)
+        {
+            background.0.set_alpha(alpha);
+        }
+    }
+    if let Some(mut border) = border {
+        if let Some(alpha) = FadedAlpha::fade(&mut faded.border, border.0.alpha(), opacity) {
+            border.0.set_alpha(alpha);
+        }
+    }
+    if let Some(mut image) = image {
+        if let Some(alpha) = FadedAlpha::fade(&mut faded.image, image.color.alpha(), opacity) {
+            image.color.set_alpha(alpha);
+        }
+    }
+    if let Some(mut stylebox) = stylebox {
+        let alpha = stylebox.modulate.alpha();
+        if let Some(alpha) = FadedAlpha::fade(&mut faded.stylebox, alpha, opacity) {
+            stylebox.modulate.set_alpha(alpha);
+        }
+    }
+    if let Some(mut text) = text {
+        faded.text.resize(text.sections.len(), None);
+        let alphas: Vec<_> = faded
+            .text
+            .iter_mut()
+            .zip(text.sections.iter())
+            .map(|(slot, section)| FadedAlpha::fade(slot, section.style.color.alpha(), opacity))
+            .collect();
+        if alphas.iter().any(Option::is_some) {
+            for (section, alpha) in text.sections.iter_mut().zip(alphas) {
+                if let Some(alpha) = alpha {
+                    section.style.color.set_alpha(alpha);
+                }
+            }
+        }
+    }
+    if opacity >= 1. {
+        // the base alphas are restored, nothing to follow anymore
+        commands.entity(entity).remove::<FadedColors>();
+    } else if let Some(inserted) = inserted {
+        commands.entity(entity).insert(inserted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ess::impls::OpacityParser;
+    use crate::ess::{PropertyParser, StyleProperty};
+    use std::str::FromStr;
+
+    #[test]
+    fn fade_subtree() {
+        let mut world = World::new();
+        let text = world
+            .spawn(TextBundle::from_section("text", TextStyle::default()))
+            .id();
+        let panel = world
+            .spawn((NodeBundle::default(), Opacity(0.5)))
+            .push_children(&[text])
+            .id();
+        let node = NodeBundle {
+            background_color: BackgroundColor(Color::srgba(1., 0., 0., 0.8)),
+            ..default()
+        };
+        let root = world
+            .spawn((node, Opacity(0.5)))
+            .push_children(&[panel])
+            .id();
+        let other = world
+            .spawn(NodeBundle {
+                background_color: BackgroundColor(Color::WHITE),
+                ..default()
+            })
+            .id();
+        let alpha = |world: &World| {
+            (
+                world.get::<BackgroundColor>(root).unwrap().0.alpha(),
+                world.get::<Text>(text).unwrap().sections[0]
+                    .style
+                    .color
+                    .alpha(),
+            )
+        };
+        let mut schedule = Schedule::default();
+        schedule.add_systems(opacity_system);
+        let mut run = |world: &mut World| {
+            world.clear_trackers();
+            schedule.run(world);
+        };
+
+        run(&mut world);
+        assert_eq!(alpha(&world), (0.4, 0.25));
+        // the opacity isn't applied twice
+        run(&mut world);
+        assert_eq!(alpha(&world), (0.4, 0.25));
+        assert!(!world.entity(text).get_ref::<Text>().unwrap().is_changed());
+
+        // the colors set by the properties are faded as well
+        world.get_mut::<Text>(text).unwrap().sections[0].style.color = Color::BLACK;
+        run(&mut world);
+        assert_eq!(alpha(&world), (0.4, 0.25));
+
+        // the children added to the faded subtree are faded
+        let image = world
+            .spawn(ImageBundle {
+                image: UiImage::default().with_color(Color::WHITE),
+                ..default()
+            })
+            .id();
+        world.entity_mut(panel).add_child(image);
+        run(&mut world);
+        assert_eq!(world.get::<UiImage>(image).unwrap().color.alpha(), 0.25);
+
+        world.get_mut::<Opacity>(root).unwrap().0 = 1.;
+        run(&mut world);
+        assert_eq!(alpha(&world), (0.8, 0.5));
+        world.entity_mut(panel).remove::<Opacity>();
+        run(&mut world);
+        assert_eq!(alpha(&world), (0.8, 1.));
+        assert!(world.get::<FadedColors>(text).is_none());
+        assert_eq!(world.get::<UiImage>(image).unwrap().color.alpha(), 1.);
+
+        // the subtrees without opacity aren't touched
+        assert!(!world
+            .entity(other)
+            .get_ref::<BackgroundColor>()
+            .unwrap()
+            .is_changed());
+    }
+
+    #[test]
+    fn parse_opacity() {
+        let parse = |value| OpacityParser::parse(&StyleProperty::from_str(value).unwrap());
+        assert_eq!(parse("0.5").unwrap(), 0.5);
+        assert_eq!(parse("30%").unwrap(), 0.3);
+        assert_eq!(parse("2").unwrap(), 1.);
+        assert!(parse("auto").is_err());
+    }
+}
@@ -3,7 +3,10 @@ use std::{any::Any, marker::PhantomData};
 use crate::ElementsError;
 use bevy::prelude::*;
 
-use super::{colors, PropertyParser, StyleProperty, StylePropertyMethods, StylePropertyToken};
+use super::{
+    calc::dimension_val, colors, style::calc_val, PropertyParser, StyleProperty,
+    StylePropertyMethods, StylePropertyToken,
+};
 
 pub fn identifier<S>(prop: &StyleProperty) -> Result<S, ElementsError>
 where
@@ -32,9 +35,10 @@ pub fn val(prop: &StyleProperty) -> Result<Val, ElementsError> {
     };
     match prop {
         StylePropertyToken::Percentage(val) => Ok(Val::Percent(val.into())),
-        StylePropertyToken::Dimension(val, unit) if unit.as_str() == "px" => {
-            Ok(Val::Px(val.into()))
+        StylePropertyToken::Dimension(val, unit) if dimension_val(0., unit).is_some() => {
+            Ok(dimension_val(val.into(), unit).unwrap())
         }
+        StylePropertyToken::Function(f) if f.name == "calc" => calc_val(&f.args),
         StylePropertyToken::Identifier(val) if val.as_str() == "auto" => Ok(Val::Auto),
         StylePropertyToken::Identifier(val) if val.as_str() == "undefined" => Ok(Val::Px(0.)),
         p => Err(ElementsError::InvalidPropertyValue(format!(
@@ -50,6 +54,12 @@ pub fn val(prop: &StyleProperty) -> Result<Val, ElementsError> {
 /// - `undefined` for `Val::Px(0.)`
 /// - `px` suffixed for `Val::Px` (`25px`)
 /// - `%` suffixed for `Val::Percent` (`25%`)
+/// - `vw`, `vh`, `vmin` and `vmax` suffixed for `Val::Vw`, `Val::Vh`, `Val::VMin`
+///   and `Val::VMax` (`50vw`)
+/// - `em` and `rem` suffixed for the size relative to the `font-size` of the
+///   element or of the root element (`1.5em`)
+/// - `calc()` expression (`calc(100% - 2 * 10px)`), evaluated when applied if
+///   it mixes units
 pub struct ValParser;
 impl PropertyParser<Val> for ValParser {
     fn parse(value: &StyleProperty) -> Result<Val, ElementsError> {
@@ -134,31 +144,128 @@ impl PropertyParser<UiRect> for RectParser {
     }
 }
 
+/// Bevy renders circular corners only: the elliptical corner is approximated
+/// by the smaller radius when both are in pixels or by the horizontal one.
+fn circular(horizontal: Val, vertical: Val) -> Val {
+    match (horizontal, vertical) {
+        (Val::Px(h), Val::Px(v)) => Val::Px(h.min(v)),
+        (horizontal, _) => horizontal,
+    }
+}
+
+pub fn radius(prop: &StyleProperty) -> Result<Val, ElementsError> {
+    match prop.len() {
+        1 => prop[0].val(),
+        2 => Ok(circular(prop[0].val()?, prop[1].val()?)),
+        _ => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $radius, got `{}`",
+            prop.to_string()
+        ))),
+    }
+}
+
+/// <!-- @property-type=$radius -->
+/// The radius of the single corner: one [`$val`](#$val) for the circular corner
+/// (`10px`) or two of them for the horizontal and vertical radii of the elliptical
+/// corner (`20px 10px`). Bevy renders circular corners only, so the elliptical
+/// corner is rounded by the smaller radius when both are in `px` or by the
+/// horizontal one otherwise.
+pub struct RadiusParser;
+impl PropertyParser<Val> for RadiusParser {
+    fn parse(value: &StyleProperty) -> Result<Val, ElementsError> {
+        radius(value)
+    }
+
+    fn warning(value: &StyleProperty) -> Option<String> {
+        match value.len() {
+            2 => elliptical_warning([(value[0].val().ok()?, value[1].val().ok()?)]),
+            _ => None,
+        }
+    }
+}
+
+fn corners(tokens: &[StylePropertyToken]) -> Result<BorderRadius, ElementsError> {
+    let vals = tokens
+        .iter()
+        .map(|token| token.val())
+        .collect::<Result<Vec<_>, _>>()?;
+    match vals[..] {
+        [all] => Ok(BorderRadius::all(all)),
+        [tl_br, tr_bl] => Ok(BorderRadius::new(tl_br, tr_bl, tl_br, tr_bl)),
+        [tl, tr_bl, br] => Ok(BorderRadius::new(tl, tr_bl, br, tr_bl)),
+        [tl, tr, br, bl] => Ok(BorderRadius::new(tl, tr, br, bl)),
+        _ => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected 1 to 4 corner radii, got `{}`",
+            tokens.to_string()
+        ))),
+    }
+}
+
+/// Returns the warning about the first elliptical corner of the `pairs`
+/// of horizontal and vertical radii rendered as the circular one
+fn elliptical_warning(pairs: impl IntoIterator<Item = (Val, Val)>) -> Option<String> {
+    let (horizontal, vertical) = pairs.into_iter().find(|(h, v)| h != v)?;
+    Some(format!(
+        "Elliptical corner `{horizontal:?} / {vertical:?}` is rendered \
+        as the circular one with radius `{:?}`",
+        circular(horizontal, vertical)
+    ))
+}
+
+/// Returns the warning about the elliptical corners of `border-radius`
+/// shorthand rendered with the single radius, `None` if the corners are
+/// circular or the value is invalid
+pub(crate) fn border_radius_warning(prop: &StyleProperty) -> Option<String> {
+    let tokens = prop.tokens();
+    let slash = tokens
+        .iter()
+        .position(|token| matches!(token, StylePropertyToken::Slash))?;
+    let horizontal = corners(&tokens[..slash]).ok()?;
+    let vertical = corners(&tokens[slash + 1..]).ok()?;
+    elliptical_warning([
+        (horizontal.top_left, vertical.top_left),
+        (horizontal.top_right, vertical.top_right),
+        (horizontal.bottom_right, vertical.bottom_right),
+        (horizontal.bottom_left, vertical.bottom_left),
+    ])
+}
+
+/// Parses `border-radius` shorthand: 1 to 4 radii of the corners in top-left,
+/// top-right, bottom-right, bottom-left order, optionally followed by `/` and
+/// the vertical radii of elliptical corners: `10px 5px / 20px`
+pub fn border_radius(prop: &StyleProperty) -> Result<BorderRadius, ElementsError> {
+    let tokens = prop.tokens();
+    let slash = tokens
+        .iter()
+        .position(|token| matches!(token, StylePropertyToken::Slash));
+    let Some(slash) = slash else {
+        return corners(tokens);
+    };
+    let horizontal = corners(&tokens[..slash])?;
+    let vertical = corners(&tokens[slash + 1..])?;
+    Ok(BorderRadius::new(
+        circular(horizontal.top_left, vertical.top_left),
+        circular(horizontal.top_right, vertical.top_right),
+        circular(horizontal.bottom_right, vertical.bottom_right),
+        circular(horizontal.bottom_left, vertical.bottom_left),
+    ))
+}
+
 pub fn color(prop: &StyleProperty) -> Result<Color, ElementsError> {
     if prop.len() == 0 {
         return Err(ElementsError::InvalidPropertyValue(format!(
             "Expected $color, got nothing"
         )));
     }
-    match &prop[0] {
-        StylePropertyToken::Identifier(name) => colors::parse_named_color(name.as_str())
-            .ok_or_else(|| {
-                ElementsError::InvalidPropertyValue(format!("Unknown color name `{name}`"))
-            }),
-        StylePropertyToken::Hash(hash) => colors::parse_hex_color(hash.as_str()),
-        prop => {
-            return Err(ElementsError::InvalidPropertyValue(format!(
-                "Expected $color, got `{}`",
-                prop.to_string()
-            )))
-        }
-    }
+    colors::parse_color_token(&prop[0])
 }
 
 /// <!-- @property-type=$color -->
-/// Describes the `Color` value. Accepts color names (`white`, `red`)
-/// or hex codes (`#3fde1a`). List of predefined colors can be found
-/// here (coming soon).
+/// Describes the `Color` value. Accepts color names (`white`, `red`),
+/// hex codes (`#3fde1a`), `rgb()`, `rgba()`, `hsl()`, `hsla()` and
+/// `color-mix()` functions: `hsl(210 80% 50% / 0.6)`,
+/// `color-mix(in srgb, #3fde1a 30%, white)`. List of predefined
+/// colors can be found here (coming soon).
 /// <!-- TODO: add link to color list -->
 pub struct ColorParser;
 impl PropertyParser<Color> for ColorParser {
@@ -12,7 +12,7 @@ use tagstr::Tag;
 
 use crate::ElementsError;
 
-use super::{colors, PropertyValue};
+use super::{calc::dimension_val, colors, CalcValue, PropertyValue};
 use crate::ess::parser::parse_style_property_value;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
@@ -83,6 +83,8 @@ pub enum StylePropertyToken {
     /// Property delimiter (comma or slash)
     Slash,
     Comma,
+    /// Math operator used by `calc()`: `+`, `-` or `*`
+    Operator(char),
 }
 
 impl StylePropertyToken {
@@ -111,16 +113,19 @@ impl StylePropertyToken {
             StylePropertyToken::Tokens(t) => t.iter().map(|t| t.to_string()).join(" "),
             StylePropertyToken::Slash => format!("/"),
             StylePropertyToken::Comma => format!(","),
+            StylePropertyToken::Operator(op) => format!("{op}"),
         }
     }
 
     pub fn val(&self) -> Result<Val, ElementsError> {
         match self {
             StylePropertyToken::Percentage(p) => Ok(Val::Percent(p.to_float())),
-            StylePropertyToken::Dimension(d, u) if u == "px" => Ok(Val::Px(d.to_float())),
+            StylePropertyToken::Dimension(d, u) if dimension_val(0., u).is_some() => {
+                Ok(dimension_val(d.to_float(), u).unwrap())
+            }
+            StylePropertyToken::Function(f) if f.name == "calc" => calc_val(&f.args),
             StylePropertyToken::Identifier(i) if i == "auto" => Ok(Val::Auto),
             StylePropertyToken::Identifier(i) if i == "undefined" => Ok(Val::Px(0.)),
-            StylePropertyToken::Identifier(i) if i == "undefined" => Ok(Val::Px(0.)),
             _ => Err(ElementsError::InvalidPropertyValue(format!(
                 "Can't treat `{}` as size value",
                 self.to_string()
@@ -143,6 +148,16 @@ impl StylePropertyToken {
     }
 }
 
+/// Evaluates `calc()` with the single unit to [`Val`]
+pub(crate) fn calc_val(args: &[StylePropertyToken]) -> Result<Val, ElementsError> {
+    CalcValue::parse(args)?.to_val().ok_or_else(|| {
+        ElementsError::InvalidPropertyValue(format!(
+            "calc() mixing units can't be used here: `calc({})`",
+            args.iter().map(|a| a.to_string()).join(", ")
+        ))
+    })
+}
+
 pub type StylePropertyTokens = SmallVec<[StylePropertyToken; 8]>;
 
 /// A list of [`PropertyToken`] which was parsed from a single property.
@@ -293,19 +308,7 @@ pub trait StylePropertyMethods {
                 "Expected color, got nothing"
             )));
         }
-        match &props[0] {
-            StylePropertyToken::Identifier(name) => colors::parse_named_color(name.as_str())
-                .ok_or_else(|| {
-                    ElementsError::InvalidPropertyValue(format!("Unknown color name '{name}'"))
-                }),
-            StylePropertyToken::Hash(hash) => colors::parse_hex_color(hash.as_str()),
-            prop => {
-                return Err(ElementsError::InvalidPropertyValue(format!(
-                    "Can't parse color from {}",
-                    prop.to_string()
-                )))
-            }
-        }
+        colors::parse_color_token(&props[0])
     }
 
     /// Tries to parses the current values as a single identifier.
@@ -336,7 +339,10 @@ pub trait StylePropertyMethods {
         };
         match prop {
             StylePropertyToken::Percentage(val) => Ok(Val::Percent(val.into())),
-            StylePropertyToken::Dimension(val, unit) if unit == "px" => Ok(Val::Px(val.into())),
+            StylePropertyToken::Dimension(val, unit) if dimension_val(0., unit).is_some() => {
+                Ok(dimension_val(val.into(), unit).unwrap())
+            }
+            StylePropertyToken::Function(f) if f.name == "calc" => calc_val(&f.args),
             StylePropertyToken::Identifier(val) if val.as_str() == "auto" => Ok(Val::Auto),
             StylePropertyToken::Identifier(val) if val.as_str() == "undefined" => Ok(Val::Px(0.)),
             p => Err(ElementsError::InvalidPropertyValue(format!(
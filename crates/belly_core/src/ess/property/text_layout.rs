@@ -0,0 +1,576 @@
+use ab_glyph::{FontArc, GlyphId, ScaleFont};
+use bevy::prelude::*;
+use bevy::text::{BreakLineOn, TextMeasureInfo};
+use bevy::ui::widget::TextMeasure;
+use bevy::ui::{AvailableSpace, ContentSize, DefaultUiCamera, FixedMeasure, Measure, NodeMeasure};
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::PrimaryWindow;
+
+/// `white-space` of the text: `normal` wraps the lines to fit the element,
+/// `nowrap` breaks the lines only at the explicit line breaks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WhiteSpace {
+    #[default]
+    Normal,
+    NoWrap,
+}
+
+/// `word-break` of the text: `normal` wraps the lines at the word
+/// boundaries, `break-all` wraps them at any character
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WordBreak {
+    #[default]
+    Normal,
+    BreakAll,
+}
+
+/// `text-overflow` of the text: `clip` leaves the text as is, `ellipsis`
+/// truncates the single-line text not fitting its parent with `…`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextOverflow {
+    #[default]
+    Clip,
+    Ellipsis,
+}
+
+/// The distance between the baselines of the text lines
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LineHeight {
+    /// The line height defined by the font
+    #[default]
+    Normal,
+    /// The line height relative to the font size
+    Factor(f32),
+    /// The line height in logical pixels
+    Px(f32),
+}
+
+/// The text layout settings which Bevy [`Text`] doesn't hold. Inserted
+/// by the text style properties (`white-space`, `line-height`, etc.)
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    pub white_space: WhiteSpace,
+    pub word_break: WordBreak,
+    pub text_overflow: TextOverflow,
+    pub line_height: LineHeight,
+    /// Additional space after each character in logical pixels
+    pub letter_spacing: f32,
+    /// The values of the sections before and after the truncation with ellipsis
+    truncated: Option<(Vec<String>, Vec<String>)>,
+}
+
+impl TextLayout {
+    fn linebreak_behavior(&self) -> BreakLineOn {
+        match (self.white_space, self.word_break) {
+            (WhiteSpace::NoWrap, _) => BreakLineOn::NoWrap,
+            (_, WordBreak::BreakAll) => BreakLineOn::AnyCharacter,
+            _ => BreakLineOn::WordBoundary,
+        }
+    }
+
+    fn has_spacing(&self) -> bool {
+        self.line_height != LineHeight::Normal || self.letter_spacing != 0.
+    }
+}
+
+/// Returns the horizontal advance of the `ch` character of the `font`
+/// scaled to `font_size`, without kerning
+pub fn char_advance(ch: char, font: &Font, font_size: f32) -> f32 {
+    let font = ab_glyph::Font::as_scaled(&font.font, font_size);
+    let glyph = font.glyph_id(ch);
+    font.h_advance(glyph)
+}
+
+/// Updates the line breaking of [`Text`] from `white-space` & `word-break`.
+/// The text is marked changed anyway, so Bevy lays it out again.
+pub(crate) fn text_wrap_system(mut texts: Query<(&TextLayout, &mut Text), Changed<TextLayout>>) {
+    for (layout, mut text) in texts.iter_mut() {
+        text.linebreak_behavior = layout.linebreak_behavior();
+    }
+}
+
+/// The font with `letter-spacing` added to the advances of the glyphs and
+/// `line-height` put into the gap between the lines, so Bevy measures, wraps
+/// and draws the spaced text itself. The half of the leading moves the glyphs
+/// down, the height of the font is kept as Bevy scales the glyphs by it. The
+/// spacing is kept in the font units converted from the pixels of the font
+/// size it is made for.
+struct SpacedFont {
+    font: FontArc,
+    letter_spacing: f32,
+    half_leading: f32,
+    line_gap: f32,
+}
+
+impl SpacedFont {
+    fn new(font: FontArc, font_size: f32, letter_spacing: f32, line_height: LineHeight) -> Self {
+        use ab_glyph::Font as _;
+        // Bevy scales the font so its height is the font size
+        let height = font.height_unscaled();
+        let units = height / font_size;
+        let line = match line_height {
+            LineHeight::Normal => None,
+            LineHeight::Factor(factor) => Some(factor * height),
+            LineHeight::Px(px) => Some(px * units),
+        };
+        SpacedFont {
+            letter_spacing: letter_spacing * units,
+            half_leading: line.map_or(0., |line| (line - height) / 2.),
+            line_gap: line.map_or(font.line_gap_unscaled(), |line| line - height),
+            font,
+        }
+    }
+}
+
+impl ab_glyph::Font for SpacedFont {
+    fn units_per_em(&self) -> Option<f32> {
+        self.font.units_per_em()
+    }
+
+    fn ascent_unscaled(&self) -> f32 {
+        self.font.ascent_unscaled() + self.half_leading
+    }
+
+    fn descent_unscaled(&self) -> f32 {
+        self.font.descent_unscaled() + self.half_leading
+    }
+
+    fn line_gap_unscaled(&self) -> f32 {
+        self.line_gap
+    }
+
+    fn glyph_id(&self, c: char) -> GlyphId {
+        self.font.glyph_id(c)
+    }
+
+    fn h_advance_unscaled(&self, id: GlyphId) -> f32 {
+        self.font.h_advance_unscaled(id) + self.letter_spacing
+    }
+
+    fn h_side_bearing_unscaled(&self, id: GlyphId) -> f32 {
+        self.font.h_side_bearing_unscaled(id)
+    }
+
+    fn v_advance_unscaled(&self, id: GlyphId) -> f32 {
+        self.font.v_advance_unscaled(id)
+    }
+
+    fn v_side_bearing_unscaled(&self, id: GlyphId) -> f32 {
+        self.font.v_side_bearing_unscaled(id)
+    }
+
+    fn kern_unscaled(&self, first: GlyphId, second: GlyphId) -> f32 {
+        self.font.kern_unscaled(first, second)
+    }
+
+    fn outline(&self, id: GlyphId) -> Option<ab_glyph::Outline> {
+        self.font.outline(id)
+    }
+
+    fn glyph_count(&self) -> usize {
+        self.font.glyph_count()
+    }
+
+    fn codepoint_ids(&self) -> ab_glyph::CodepointIdIter<'_> {
+        self.font.codepoint_ids()
+    }
+
+    fn glyph_raster_image2(&self, id: GlyphId, size: u16) -> Option<ab_glyph::v2::GlyphImage<'_>> {
+        self.font.glyph_raster_image2(id, size)
+    }
+}
+
+/// The fonts made by [`text_spacing_system`] for the spaced texts and the
+/// fonts they are made of. The font no text uses any more is dropped by Bevy.
+#[derive(Resource, Default)]
+pub(crate) struct SpacedFonts {
+    spaced: HashMap<(AssetId<Font>, [u32; 4]), Handle<Font>>,
+    bases: HashMap<AssetId<Font>, Handle<Font>>,
+}
+
+impl SpacedFonts {
+    /// Returns the font the `font` is made of, the `font` itself
+    /// if it isn't spaced
+    pub(crate) fn base(&self, font: &Handle<Font>) -> Handle<Font> {
+        self.bases
+            .get(&font.id())
+            .cloned()
+            .unwrap_or_else(|| font.clone())
+    }
+
+    /// Returns the `base` font spaced by the `layout` for the `font_size`,
+    /// `None` if the `base` font isn't loaded yet
+    fn spaced(
+        &mut self,
+        base: &Handle<Font>,
+        font_size: f32,
+        layout: &TextLayout,
+        fonts: &mut Assets<Font>,
+    ) -> Option<Handle<Font>> {
+        let line_height = match layout.line_height {
+            LineHeight::Normal => [0, 0],
+            LineHeight::Factor(factor) => [1, factor.to_bits()],
+            LineHeight::Px(px) => [2, px.to_bits()],
+        };
+        let key = (
+            base.id(),
+            [
+                font_size.to_bits(),
+                layout.letter_spacing.to_bits(),
+                line_height[0],
+                line_height[1],
+            ],
+        );
+        if let Some(font) = self.spaced.get(&key) {
+            if let Some(font) = fonts.get_strong_handle(font.id()) {
+                return Some(font);
+            }
+        }
+        let font = fonts.get(base)?.font.clone();
+        self.spaced.retain(|_, font| fonts.contains(font.id()));
+        self.bases.retain(|font, _| fonts.contains(*font));
+        let spaced = SpacedFont::new(font, font_size, layout.letter_spacing, layout.line_height);
+        let font = fonts.add(Font {
+            font: FontArc::new(spaced),
+        });
+        self.spaced.insert(key, font.clone_weak());
+        self.bases.insert(font.id(), base.clone());
+        Some(font)
+    }
+}
+
+/// Sets the fonts of the [`Text`] sections spaced by `letter-spacing` and
+/// `line-height`, so Bevy lays the text out with the spacing, or the base
+/// fonts when the spacing is reset.
+pub(crate) fn text_spacing_system(
+    mut fonts: ResMut<Assets<Font>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    mut spaced: ResMut<SpacedFonts>,
+    mut texts: Query<(Ref<TextLayout>, &mut Text)>,
+) {
+    // the fonts of the texts couldn't be spaced before they are loaded
+    let loaded = font_events.read().count() > 0;
+    for (layout, mut text) in texts.iter_mut() {
+        if !loaded && !layout.is_changed() && !text.is_changed() {
+            continue;
+        }
+        let mut sections = vec![];
+        for section in text.sections.iter() {
+            let base = spaced.base(&section.style.font);
+            let font = if layout.has_spacing() && section.style.font_size > 0. {
+                spaced.spaced(&base, section.style.font_size, &layout, &mut fonts)
+            } else {
+                None
+            };
+            sections.push(font.unwrap_or(base));
+        }
+        if text
+            .sections
+            .iter()
+            .zip(sections.iter())
+            .any(|(section, font)| section.style.font != *font)
+        {
+            for (section, font) in text.sections.iter_mut().zip(sections) {
+                section.style.font = font;
+            }
+        }
+    }
+}
+
+/// The Bevy [`TextMeasure`] with the leading below the last line: Bevy
+/// measures the text down to the descent of the last line only
+struct LeadingMeasure {
+    text: TextMeasure,
+    leading: f32,
+}
+
+impl Measure for LeadingMeasure {
+    fn measure(
+        &self,
+        width: Option<f32>,
+        height: Option<f32>,
+        available_width: AvailableSpace,
+        available_height: AvailableSpace,
+        style: &taffy::Style,
+    ) -> Vec2 {
+        let size = self
+            .text
+            .measure(width, height, available_width, available_height, style);
+        match height {
+            Some(_) => size,
+            None => Vec2::new(size.x, (size.y + self.leading).ceil()),
+        }
+    }
+}
+
+/// Returns the leading of the `line-height` below the last line of the text
+/// measured by the `info`, in physical pixels
+fn text_leading(info: &TextMeasureInfo) -> f32 {
+    info.sections
+        .iter()
+        .map(|section| {
+            let font = &info.fonts[section.font_id.0];
+            ab_glyph::Font::as_scaled(font, section.scale).line_gap()
+        })
+        .reduce(f32::max)
+        .unwrap_or(0.)
+}
+
+/// Adds the leading below the last line to the size of the text with
+/// `line-height`, whenever Bevy measures the text again.
+pub(crate) fn text_leading_system(
+    fonts: Res<Assets<Font>>,
+    cameras: Query<&Camera>,
+    default_camera: DefaultUiCamera,
+    ui_scale: Res<UiScale>,
+    mut texts: Query<
+        (&TextLayout, &Text, &mut ContentSize, Option<&TargetCamera>),
+        Changed<ContentSize>,
+    >,
+) {
+    for (layout, text, mut content_size, target) in texts.iter_mut() {
+        if layout.line_height == LineHeight::Normal {
+            continue;
+        }
+        // the scale factor Bevy has measured the text with
+        let scale_factor = target
+            .map(TargetCamera::entity)
+            .or_else(|| default_camera.get())
+            .and_then(|camera| cameras.get(camera).ok())
+            .and_then(Camera::target_scaling_factor)
+            .unwrap_or(1.)
+            * ui_scale.0;
+        let Ok(info) = TextMeasureInfo::from_text(text, &fonts, scale_factor) else {
+            continue;
+        };
+        let leading = text_leading(&info);
+        if text.linebreak_behavior == BreakLineOn::NoWrap {
+            content_size.set(NodeMeasure::Fixed(FixedMeasure {
+                size: Vec2::new(info.max.x, info.max.y + leading),
+            }));
+        } else {
+            content_size.set(NodeMeasure::Custom(Box::new(LeadingMeasure {
+                text: TextMeasure { info },
+                leading,
+            })));
+        }
+    }
+}
+
+type ChangedText = Or<(Changed<Text>, Changed<Node>, Changed<TextLayout>)>;
+
+/// Truncates the text with `text-overflow: ellipsis` and `white-space: nowrap`
+/// to fit the content box of the parent element, restores the original text
+/// when it fits again. Only the texts changed or resized, and the texts of
+/// the resized parents are checked (all of them when the fonts are loaded).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn text_overflow_system(
+    fonts: Res<Assets<Font>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    ui_scale: Res<UiScale>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    changed: Query<Entity, (With<TextLayout>, ChangedText)>,
+    resized: Query<&Children, Changed<Node>>,
+    mut texts: Query<(&mut TextLayout, &mut Text, &Node, Option<&Parent>, Entity)>,
+    parents: Query<(&Node, &Style, Option<&Parent>)>,
+    nodes: Query<&Node>,
+) {
+    let mut entities: HashSet<_> = changed.iter().collect();
+    if font_events.read().count() > 0 {
+        entities.extend(texts.iter().map(|(_, _, _, _, entity)| entity));
+    }
+    entities.extend(
+        resized
+            .iter()
+            .flat_map(|kids| kids.iter().copied())
+            .filter(|kid| texts.contains(*kid)),
+    );
+    if entities.is_empty() {
+        return;
+    }
+    let viewport = windows
+        .get_single()
+        .map(|window| window.size() / ui_scale.0)
+        .unwrap_or(Vec2::ZERO);
+    for entity in entities {
+        let Ok((mut layout, mut text, node, parent, _)) = texts.get_mut(entity) else {
+            continue;
+        };
+        let ellipsis = layout.text_overflow == TextOverflow::Ellipsis
+            && layout.white_space == WhiteSpace::NoWrap;
+        if !ellipsis && layout.truncated.is_none() {
+            continue;
+        }
+        let original = match &layout.truncated {
+            // the text wasn't changed since it was truncated
+            Some((original, shown)) if same_values(&text, shown) => original.clone(),
+            _ => text.sections.iter().map(|s| s.value.clone()).collect(),
+        };
+        let available = parent
+            .and_then(|parent| parents.get(parent.get()).ok())
+            .map(|(node, style, parent)| {
+                // the percents of the padding and the border are relative
+                // to the width of the parent's parent, like in the layout
+                let base = parent
+                    .and_then(|parent| nodes.get(parent.get()).ok())
+                    .map_or(0., |node| node.size().x);
+                let px = |val: Val| val.resolve(base, viewport).unwrap_or(0.);
+                node.size().x
+                    - px(style.padding.left)
+                    - px(style.padding.right)
+                    - px(style.border.left)
+                    - px(style.border.right)
+            })
+            .unwrap_or(node.size().x);
+        let shown = if ellipsis && available > 0. {
+            if text
+                .sections
+                .iter()
+                .any(|section| !fonts.contains(&section.style.font))
+            {
+                // fonts aren't loaded yet
+                continue;
+            }
+            truncate(&text, &original, available, &fonts)
+        } else {
+            None
+        };
+        let values = shown.as_ref().unwrap_or(&original);
+        if !same_values(&text, values) {
+            for (section, value) in text.sections.iter_mut().zip(values.iter()) {
+                section.value.clone_from(value);
+            }
+        }
+        let truncated = shown.map(|shown| (original, shown));
+        if layout.truncated != truncated {
+            layout.bypass_change_detection().truncated = truncated;
+        }
+    }
+}
+
+fn same_values(text: &Text, values: &[String]) -> bool {
+    text.sections.len() == values.len()
+        && text
+            .sections
+            .iter()
+            .zip(values)
+            .all(|(section, value)| section.value == *value)
+}
+
+/// Returns the `original` values of the `text` sections truncated
+/// with ellipsis to fit the `available` width, `None` if they fit
+fn truncate(
+    text: &Text,
+    original: &[String],
+    available: f32,
+    fonts: &Assets<Font>,
+) -> Option<Vec<String>> {
+    let mut width = 0.;
+    let mut widths = vec![];
+    for (section, value) in text.sections.iter().zip(original) {
+        let font = fonts.get(&section.style.font)?;
+        for ch in value.chars() {
+            width += char_advance(ch, font, section.style.font_size);
+            widths.push(width);
+        }
+    }
+    if width <= available {
+        return None;
+    }
+    let mut shown: Vec<String> = original.iter().map(|_| String::new()).collect();
+    let mut index = 0;
+    for (idx, (section, value)) in text.sections.iter().zip(original).enumerate() {
+        let font = fonts.get(&section.style.font)?;
+        let ellipsis = char_advance('…', font, section.style.font_size);
+        for ch in value.chars() {
+            if widths[index] + ellipsis > available {
+                shown[idx].push('…');
+                return Some(shown);
+            }
+            shown[idx].push(ch);
+            index += 1;
+        }
+    }
+    Some(shown)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spaced_text_layout() {
+        let mut world = World::new();
+        let mut fonts = Assets::<Font>::default();
+        let bytes = include_bytes!("../assets/Exo2-ExtraLight.ttf");
+        let base = fonts.add(Font::try_from_bytes(bytes.to_vec()).unwrap());
+        world.insert_resource(fonts);
+        world.init_resource::<SpacedFonts>();
+        world.init_resource::<Events<AssetEvent<Font>>>();
+        let style = TextStyle {
+            font: base.clone(),
+            font_size: 20.,
+            ..default()
+        };
+        let text = world
+            .spawn((Text::from_section("ab cd", style), TextLayout::default()))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(text_spacing_system);
+        let mut run = |world: &mut World| {
+            schedule.run(world);
+            world.clear_trackers();
+        };
+        let font = |world: &World| {
+            world.get::<Text>(text).unwrap().sections[0]
+                .style
+                .font
+                .clone()
+        };
+        // the size Bevy measures with the leading added by `text_leading_system`
+        let measure = |world: &World, width: f32| {
+            let layout = world.get::<TextLayout>(text).unwrap();
+            let text = world.get::<Text>(text).unwrap();
+            let fonts = world.resource::<Assets<Font>>();
+            let info = TextMeasureInfo::from_text(text, fonts, 1.).unwrap();
+            let size = info.compute_size(Vec2::new(width, f32::INFINITY));
+            match layout.line_height {
+                LineHeight::Normal => size,
+                _ => size + Vec2::Y * text_leading(&info),
+            }
+        };
+        run(&mut world);
+        assert_eq!(font(&world), base);
+        let normal = measure(&world, f32::INFINITY);
+
+        // every character (the space too) is followed by the spacing,
+        // the lines take the line height
+        let mut layout = world.get_mut::<TextLayout>(text).unwrap();
+        layout.letter_spacing = 2.;
+        layout.line_height = LineHeight::Px(30.);
+        run(&mut world);
+        assert_ne!(font(&world), base);
+        let spaced = measure(&world, f32::INFINITY);
+        assert!((spaced.x - normal.x - 10.).abs() < 0.01);
+        assert!((spaced.y - 30.).abs() < 0.01);
+
+        // the text is wrapped with the spacing: it doesn't fit the width
+        // of the text without spacing anymore
+        assert!((measure(&world, normal.x + 1.).y - 60.).abs() < 0.01);
+
+        // the line height relative to the font size
+        world.get_mut::<TextLayout>(text).unwrap().line_height = LineHeight::Factor(2.);
+        run(&mut world);
+        assert!((measure(&world, f32::INFINITY).y - 40.).abs() < 0.01);
+
+        // the same spacing reuses the font, the reset one restores the base font
+        let spaced = font(&world);
+        world.get_mut::<Text>(text).unwrap().sections[0].value = "ef gh".to_string();
+        run(&mut world);
+        assert_eq!(font(&world), spaced);
+        *world.get_mut::<TextLayout>(text).unwrap() = TextLayout::default();
+        run(&mut world);
+        assert_eq!(font(&world), base);
+        assert_eq!(world.resource::<SpacedFonts>().base(&spaced), base);
+    }
+}
@@ -0,0 +1,223 @@
+use super::Interpolate;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+/// The single function of `transform` property
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransformFunction {
+    /// `translate(x, y)`, the percents are relative to the size of the element
+    Translate(Val, Val),
+    /// `rotate(angle)` in radians, clockwise
+    Rotate(f32),
+    /// `scale(x, y)`
+    Scale(f32, f32),
+}
+
+impl TransformFunction {
+    /// The function of the same kind which doesn't transform anything,
+    /// `none` is transitioned from/to the list of these functions
+    fn identity(&self) -> TransformFunction {
+        let zero = |val: Val| match val {
+            Val::Percent(_) => Val::Percent(0.),
+            Val::Vw(_) => Val::Vw(0.),
+            Val::Vh(_) => Val::Vh(0.),
+            Val::VMin(_) => Val::VMin(0.),
+            Val::VMax(_) => Val::VMax(0.),
+            _ => Val::Px(0.),
+        };
+        match *self {
+            TransformFunction::Translate(x, y) => TransformFunction::Translate(zero(x), zero(y)),
+            TransformFunction::Rotate(_) => TransformFunction::Rotate(0.),
+            TransformFunction::Scale(_, _) => TransformFunction::Scale(1., 1.),
+        }
+    }
+
+    fn to_matrix(self, size: Vec2, viewport: Vec2) -> Mat4 {
+        match self {
+            TransformFunction::Translate(x, y) => Mat4::from_translation(Vec3::new(
+                resolve(x, size.x, viewport),
+                resolve(y, size.y, viewport),
+                0.,
+            )),
+            TransformFunction::Rotate(angle) => Mat4::from_rotation_z(angle),
+            TransformFunction::Scale(x, y) => Mat4::from_scale(Vec3::new(x, y, 1.)),
+        }
+    }
+}
+
+impl Interpolate for TransformFunction {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self> {
+        use TransformFunction::*;
+        let lerp = |from: &f32, to: &f32| f32::interpolate(from, to, t).unwrap();
+        match (from, to) {
+            (Translate(fx, fy), Translate(tx, ty)) => Some(Translate(
+                Val::interpolate(fx, tx, t)?,
+                Val::interpolate(fy, ty, t)?,
+            )),
+            (Rotate(from), Rotate(to)) => Some(Rotate(lerp(from, to))),
+            (Scale(fx, fy), Scale(tx, ty)) => Some(Scale(lerp(fx, tx), lerp(fy, ty))),
+            _ => None,
+        }
+    }
+}
+
+/// The lists of the same functions are interpolated function by function,
+/// `none` (the empty list) is interpolated as the list of identities
+impl Interpolate for Vec<TransformFunction> {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self> {
+        let identities = |list: &Self| list.iter().map(TransformFunction::identity).collect();
+        let from = if from.is_empty() {
+            identities(to)
+        } else {
+            from.clone()
+        };
+        let to = if to.is_empty() {
+            identities(&from)
+        } else {
+            to.clone()
+        };
+        if from.len() != to.len() {
+            return None;
+        }
+        from.iter()
+            .zip(to.iter())
+            .map(|(from, to)| TransformFunction::interpolate(from, to, t))
+            .collect()
+    }
+}
+
+fn resolve(val: Val, size: f32, viewport: Vec2) -> f32 {
+    val.resolve(size, viewport).unwrap_or(0.)
+}
+
+/// The transformation of the element set by `transform` and `transform-origin`
+/// properties. It is applied to the [`Transform`] of the node after the layout,
+/// so it doesn't affect the positions of the other elements.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct UiTransform {
+    /// The functions applied from right to left, like in CSS
+    pub functions: Vec<TransformFunction>,
+    /// The point of the element the functions are applied around,
+    /// relative to the top-left corner of the element
+    pub origin: (Val, Val),
+    /// The translation set by the layout and the one after the transformation
+    translation: Option<(Vec3, Vec3)>,
+}
+
+impl Default for UiTransform {
+    fn default() -> Self {
+        UiTransform {
+            functions: vec![],
+            origin: (Val::Percent(50.), Val::Percent(50.)),
+            translation: None,
+        }
+    }
+}
+
+impl UiTransform {
+    /// Returns the matrix transforming the node of the `size` around the origin.
+    /// The node is drawn around its center, so the origin is taken from it.
+    pub fn compute_matrix(&self, size: Vec2, viewport: Vec2) -> Mat4 {
+        let origin = Vec3::new(
+            resolve(self.origin.0, size.x, viewport) - size.x * 0.5,
+            resolve(self.origin.1, size.y, viewport) - size.y * 0.5,
+            0.,
+        );
+        let transform = self
+            .functions
+            .iter()
+            .fold(Mat4::IDENTITY, |matrix, function| {
+                matrix * function.to_matrix(size, viewport)
+            });
+        Mat4::from_translation(origin) * transform * Mat4::from_translation(-origin)
+    }
+}
+
+/// Applies [`UiTransform`] on top of the translation set by the layout. The layout
+/// doesn't reset the rotation and the scale, so they are set directly.
+pub(crate) fn ui_transform_system(
+    ui_scale: Res<UiScale>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut nodes: Query<(&mut UiTransform, &mut Transform, &Node)>,
+) {
+    let viewport = windows
+        .get_single()
+        .map(|window| window.size() / ui_scale.0)
+        .unwrap_or(Vec2::ZERO);
+    for (mut ui_transform, mut transform, node) in nodes.iter_mut() {
+        let layout = match ui_transform.translation {
+            // the layout hasn't moved the node since it was transformed
+            Some((layout, applied)) if applied == transform.translation => layout,
+            _ => transform.translation,
+        };
+        let matrix = ui_transform.compute_matrix(node.size(), viewport);
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        let translation = layout + translation;
+        let target = Transform {
+            translation,
+            rotation,
+            scale,
+        };
+        if *transform != target {
+            *transform = target;
+        }
+        if ui_transform.translation != Some((layout, translation)) {
+            ui_transform.bypass_change_detection().translation = Some((layout, translation));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn transform_around_origin() {
+        let size = Vec2::new(100., 50.);
+        let transform = |functions, origin| {
+            let ui_transform = UiTransform {
+                functions,
+                origin,
+                ..default()
+            };
+            let matrix = ui_transform.compute_matrix(size, Vec2::ZERO);
+            // the top-left corner of the node, relative to its center
+            matrix
+                .transform_point3(Vec3::new(-50., -25., 0.))
+                .truncate()
+        };
+        let center = (Val::Percent(50.), Val::Percent(50.));
+        let top_left = (Val::Px(0.), Val::Px(0.));
+
+        let translate = TransformFunction::Translate(Val::Percent(10.), Val::Px(5.));
+        assert_eq!(transform(vec![translate], center), Vec2::new(-40., -20.));
+        let scale = TransformFunction::Scale(2., 2.);
+        assert_eq!(transform(vec![scale], center), Vec2::new(-100., -50.));
+        assert_eq!(transform(vec![scale], top_left), Vec2::new(-50., -25.));
+        // rotated clockwise around the top-left corner it stays in place
+        let rotate = TransformFunction::Rotate(FRAC_PI_2);
+        let corner = transform(vec![rotate], top_left);
+        assert!(corner.abs_diff_eq(Vec2::new(-50., -25.), 1e-4));
+        // the top-right corner moves to the bottom-right from the center
+        let ui_transform = UiTransform {
+            functions: vec![rotate],
+            ..default()
+        };
+        let matrix = ui_transform.compute_matrix(size, Vec2::ZERO);
+        let corner = matrix.transform_point3(Vec3::new(50., -25., 0.)).truncate();
+        assert!(corner.abs_diff_eq(Vec2::new(25., 50.), 1e-4));
+
+        let none = vec![];
+        let rotated = vec![TransformFunction::Rotate(1.), scale];
+        let half = Interpolate::interpolate(&none, &rotated, 0.5).unwrap();
+        assert_eq!(
+            half,
+            vec![
+                TransformFunction::Rotate(0.5),
+                TransformFunction::Scale(1.5, 1.5)
+            ]
+        );
+        assert_eq!(Interpolate::interpolate(&vec![rotate], &rotated, 0.5), None);
+    }
+}
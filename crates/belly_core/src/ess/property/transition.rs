@@ -0,0 +1,401 @@
+use bevy::prelude::*;
+use smallvec::SmallVec;
+use tagstr::*;
+
+use crate::ElementsError;
+
+use super::{Property, PropertyParser, PropertyValue, StyleProperty, StylePropertyToken};
+
+/// Values the transitions can interpolate. Returns `None` when
+/// the values can't be interpolated (`Val::Px` and `Val::Percent`),
+/// so the new value is applied at once.
+pub trait Interpolate: Sized + PartialEq {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self>;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self> {
+        Some(from + (to - from) * t)
+    }
+}
+
+impl Interpolate for Val {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self> {
+        let lerp = |from: &f32, to: &f32| f32::interpolate(from, to, t).unwrap();
+        match (from, to) {
+            (Val::Px(from), Val::Px(to)) => Some(Val::Px(lerp(from, to))),
+            (Val::Percent(from), Val::Percent(to)) => Some(Val::Percent(lerp(from, to))),
+            (Val::Vw(from), Val::Vw(to)) => Some(Val::Vw(lerp(from, to))),
+            (Val::Vh(from), Val::Vh(to)) => Some(Val::Vh(lerp(from, to))),
+            (Val::VMin(from), Val::VMin(to)) => Some(Val::VMin(lerp(from, to))),
+            (Val::VMax(from), Val::VMax(to)) => Some(Val::VMax(lerp(from, to))),
+            _ => None,
+        }
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(from: &Self, to: &Self, t: f32) -> Option<Self> {
+        Some(Srgba::from(*from).mix(&Srgba::from(*to), t).into())
+    }
+}
+
+/// Timing function of the transition
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    Linear,
+    #[default]
+    Ease,
+    /// `ease-in`, `ease-out`, `ease-in-out` and `cubic-bezier(x1, y1, x2, y2)`
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn parse(token: &StylePropertyToken) -> Option<Easing> {
+        match token {
+            StylePropertyToken::Identifier(ident) => match ident.as_str() {
+                "linear" => Some(Easing::Linear),
+                "ease" => Some(Easing::Ease),
+                "ease-in" => Some(Easing::CubicBezier(0.42, 0., 1., 1.)),
+                "ease-out" => Some(Easing::CubicBezier(0., 0., 0.58, 1.)),
+                "ease-in-out" => Some(Easing::CubicBezier(0.42, 0., 0.58, 1.)),
+                _ => None,
+            },
+            StylePropertyToken::Function(f) if f.name == "cubic-bezier" && f.args.len() == 4 => {
+                let mut points = [0.; 4];
+                for (point, arg) in points.iter_mut().zip(f.args.iter()) {
+                    let StylePropertyToken::Number(number) = arg else {
+                        return None;
+                    };
+                    *point = number.to_float();
+                }
+                let [x1, y1, x2, y2] = points;
+                ((0. ..=1.).contains(&x1) && (0. ..=1.).contains(&x2))
+                    .then_some(Easing::CubicBezier(x1, y1, x2, y2))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the eased progress of the transition for the linear progress `t`
+    pub fn ease(&self, t: f32) -> f32 {
+        match *self {
+            Easing::Linear => t,
+            Easing::Ease => Easing::CubicBezier(0.25, 0.1, 0.25, 1.).ease(t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let bezier = |p1: f32, p2: f32, s: f32| {
+                    3. * (1. - s) * (1. - s) * s * p1 + 3. * (1. - s) * s * s * p2 + s * s * s
+                };
+                // find the curve parameter for `t` by bisection: x(s) is monotonic
+                let (mut low, mut high) = (0., 1.);
+                let mut s = t;
+                for _ in 0..24 {
+                    if bezier(x1, x2, s) < t {
+                        low = s;
+                    } else {
+                        high = s;
+                    }
+                    s = (low + high) * 0.5;
+                }
+                bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+/// Single transition of the `transition` property: `width 300ms ease-out 50ms`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// `None` for `all` properties
+    pub property: Option<Tag>,
+    /// Duration in seconds
+    pub duration: f32,
+    /// Delay in seconds
+    pub delay: f32,
+    pub easing: Easing,
+}
+
+impl Transition {
+    /// Returns the linear progress of the transition after `elapsed` seconds
+    pub fn progress(&self, elapsed: f32) -> f32 {
+        if self.duration <= 0. {
+            1.
+        } else {
+            ((elapsed - self.delay) / self.duration).clamp(0., 1.)
+        }
+    }
+}
+
+/// Value of the `transition` property: the list of transitions
+/// (or nothing for `none`)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Transitions(pub SmallVec<[Transition; 2]>);
+
+impl Transitions {
+    /// Returns the transition of the `property`: the last defined one wins
+    pub fn get(&self, property: Tag) -> Option<&Transition> {
+        self.0
+            .iter()
+            .rev()
+            .find(|t| t.property.map(|p| p == property).unwrap_or(true))
+    }
+}
+
+pub(super) fn seconds(token: &StylePropertyToken) -> Option<f32> {
+    match token {
+        StylePropertyToken::Dimension(value, unit) if unit == "s" => Some(value.to_float()),
+        StylePropertyToken::Dimension(value, unit) if unit == "ms" => {
+            Some(value.to_float() / 1000.)
+        }
+        StylePropertyToken::Number(value) if value.to_float() == 0. => Some(0.),
+        _ => None,
+    }
+}
+
+/// <!-- @property-type=$transition -->
+/// Comma-separated list of transitions: `property duration easing delay`.
+/// The property is `all` if omitted, the easing is one of `linear`, `ease`,
+/// `ease-in`, `ease-out`, `ease-in-out` or `cubic-bezier(x1, y1, x2, y2)`.
+/// Durations are suffixed with `s` or `ms`: `background-color 150ms ease-out, width 0.3s`
+pub struct TransitionParser;
+impl PropertyParser<Transitions> for TransitionParser {
+    fn parse(value: &StyleProperty) -> Result<Transitions, ElementsError> {
+        let mut transitions = Transitions::default();
+        if value.len() == 1 && value[0].is_ident("none") {
+            return Ok(transitions);
+        }
+        for tokens in value.split(|t| matches!(t, StylePropertyToken::Comma)) {
+            let mut transition = Transition {
+                property: None,
+                duration: 0.,
+                delay: 0.,
+                easing: Easing::default(),
+            };
+            let mut times = 0;
+            let mut property = false;
+            let mut easing = false;
+            for token in tokens {
+                if let Some(time) = seconds(token) {
+                    match times {
+                        0 => transition.duration = time,
+                        1 => transition.delay = time,
+                        _ => {}
+                    }
+                    times += 1;
+                    continue;
+                }
+                if let (false, Some(parsed)) = (easing, Easing::parse(token)) {
+                    transition.easing = parsed;
+                    easing = true;
+                    continue;
+                }
+                match token {
+                    StylePropertyToken::Identifier(ident) if !property => {
+                        if ident != "all" {
+                            transition.property = Some(ident.as_tag());
+                        }
+                        property = true;
+                    }
+                    _ => {
+                        return Err(ElementsError::InvalidPropertyValue(format!(
+                            "Unexpected token `{}` in transition",
+                            token.to_string()
+                        )))
+                    }
+                }
+            }
+            if tokens.is_empty() || times > 2 {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected `property duration easing delay` transition, got `{}`",
+                    tokens
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )));
+            }
+            transitions.0.push(transition);
+        }
+        Ok(transitions)
+    }
+}
+
+/// The transition of the running property
+pub struct RunningTransition<P: Property> {
+    /// The value the transition started from
+    pub from: P::Item,
+    pub elapsed: f32,
+    pub transition: Transition,
+}
+
+/// The value of the property `P` applied to the element the last time and
+/// the running transition to it. Inserted to the elements with the transitions
+/// of `P` the first time the property is applied.
+#[derive(Component)]
+pub struct PropertyTransition<P: Property> {
+    pub target: PropertyValue,
+    pub running: Option<RunningTransition<P>>,
+}
+
+impl<P: Property> PropertyTransition<P> {
+    pub fn new(target: PropertyValue) -> Self {
+        PropertyTransition {
+            target,
+            running: None,
+        }
+    }
+
+    /// Returns the value currently applied to the element: the interpolated
+    /// value if the transition is running. Returns `None` if `P` can't
+    /// interpolate the values.
+    pub fn current(&self) -> Option<P::Item> {
+        let target = self.target.downcast_ref::<P::Item>()?;
+        match &self.running {
+            Some(running) => {
+                let t = running.transition.progress(running.elapsed);
+                P::interpolate(&running.from, target, running.transition.easing.ease(t))
+            }
+            None => P::interpolate(target, target, 0.),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::element::Element;
+    use crate::ess::{impls::BackgroundColorProperty, ComputedStyle, PropertyRegistry, RuleIndex};
+    use bevy::utils::HashMap;
+    use smallvec::smallvec;
+    use std::time::Duration;
+
+    fn parse(value: &str) -> Result<Transitions, ElementsError> {
+        TransitionParser::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    #[test]
+    fn parse_transitions() {
+        let transitions = parse("background-color 150ms ease-out, width 0.3s 50ms").unwrap();
+        assert_eq!(
+            transitions.0.as_slice(),
+            &[
+                Transition {
+                    property: Some("background-color".as_tag()),
+                    duration: 0.15,
+                    delay: 0.,
+                    easing: Easing::CubicBezier(0., 0., 0.58, 1.),
+                },
+                Transition {
+                    property: Some("width".as_tag()),
+                    duration: 0.3,
+                    delay: 0.05,
+                    easing: Easing::Ease,
+                }
+            ]
+        );
+        assert!(transitions.get("width".as_tag()).is_some());
+        assert!(transitions.get("height".as_tag()).is_none());
+
+        let transitions = parse("1s linear, color 2s").unwrap();
+        assert_eq!(transitions.get("color".as_tag()).unwrap().duration, 2.);
+        assert_eq!(
+            transitions.get("height".as_tag()).unwrap().easing,
+            Easing::Linear
+        );
+        assert!(parse("none").unwrap().0.is_empty());
+        assert!(parse("width 1s 2s 3s").is_err());
+        assert!(parse("width height 1s").is_err());
+    }
+
+    #[test]
+    fn interpolate_values() {
+        assert_eq!(f32::interpolate(&10., &20., 0.25), Some(12.5));
+        assert_eq!(
+            Val::interpolate(&Val::Percent(0.), &Val::Percent(50.), 0.5),
+            Some(Val::Percent(25.))
+        );
+        assert_eq!(
+            Val::interpolate(&Val::Px(0.), &Val::Percent(50.), 0.5),
+            None
+        );
+        let color = Color::interpolate(&Color::BLACK, &Color::WHITE, 0.5).unwrap();
+        assert!((Srgba::from(color).red - 0.5).abs() < 0.001);
+
+        let linear = Easing::Linear;
+        assert_eq!(linear.ease(0.3), 0.3);
+        for easing in ["ease", "ease-in", "ease-out", "ease-in-out"] {
+            let easing = Easing::parse(&StylePropertyToken::Identifier(easing.into())).unwrap();
+            assert!(easing.ease(0.).abs() < 0.001);
+            assert!((easing.ease(1.) - 1.).abs() < 0.001);
+        }
+        let ease_out = Easing::CubicBezier(0., 0., 0.58, 1.);
+        assert!(ease_out.ease(0.5) > 0.5);
+    }
+
+    #[test]
+    fn transition_restarts_from_current_value() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        app.init_resource::<Time>();
+        app.add_systems(
+            Update,
+            (
+                BackgroundColorProperty::apply_defaults,
+                BackgroundColorProperty::animate_transitions,
+            )
+                .chain(),
+        );
+        let style = |color: Color| {
+            let mut inline = HashMap::default();
+            inline.insert("background-color".as_tag(), PropertyValue::new(color));
+            let transitions = parse("background-color 1s linear").unwrap();
+            inline.insert("transition".as_tag(), PropertyValue::new(transitions));
+            ComputedStyle::compute(
+                Some(&inline),
+                &[],
+                &RuleIndex::default(),
+                &Handle::default(),
+                None,
+                None,
+                &PropertyRegistry::default(),
+            )
+        };
+        let entity = app
+            .world_mut()
+            .spawn((
+                Element {
+                    names: smallvec!["div".as_tag()],
+                    ..default()
+                },
+                Node::default(),
+                BackgroundColor::default(),
+                style(Color::BLACK),
+            ))
+            .id();
+        let mut update = |color: Option<Color>, seconds: f32| {
+            if let Some(color) = color {
+                app.world_mut().entity_mut(entity).insert(style(color));
+            }
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+            Srgba::from(app.world().get::<BackgroundColor>(entity).unwrap().0).red
+        };
+        assert_eq!(update(None, 0.), 0.);
+        // half way to white
+        assert!((update(Some(Color::WHITE), 0.5) - 0.5).abs() < 0.01);
+        // the unrelated class change computes the same target
+        // (another rule, another value instance): keep running
+        assert!((update(Some(Color::WHITE), 0.25) - 0.75).abs() < 0.01);
+        // back to black from the current value
+        assert!((update(Some(Color::BLACK), 0.5) - 0.375).abs() < 0.01);
+        assert!((update(None, 0.25) - 0.1875).abs() < 0.01);
+        assert_eq!(update(None, 1.), 0.);
+        let transition = app
+            .world()
+            .get::<PropertyTransition<BackgroundColorProperty>>(entity)
+            .unwrap();
+        assert!(transition.running.is_none());
+    }
+}
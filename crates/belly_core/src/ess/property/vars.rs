@@ -0,0 +1,213 @@
+use crate::{eml::Variant, ElementsError};
+use bevy::prelude::Deref;
+use tagstr::*;
+
+use super::{
+    has_font_units, PropertyValue, StyleKeyword, StyleProperty, StylePropertyFunction,
+    StylePropertyToken,
+};
+
+/// Checks if the property name is the name of a custom property: `--accent`
+pub fn is_custom_property(name: Tag) -> bool {
+    name.as_str().starts_with("--")
+}
+
+/// Value of the custom property: `--accent: #2196f3;`.
+/// The value is stored as is and substituted by `var()`.
+#[derive(Debug, Clone, PartialEq, Deref)]
+pub struct CustomProperty(pub StyleProperty);
+
+impl CustomProperty {
+    pub(crate) fn transform(variant: Variant) -> Result<PropertyValue, ElementsError> {
+        if let Some(keyword) = StyleKeyword::from_variant(&variant) {
+            return Ok(PropertyValue::new(keyword));
+        }
+        match variant {
+            Variant::Style(value) => Ok(PropertyValue::new(CustomProperty(value))),
+            Variant::String(value) => Ok(PropertyValue::new(CustomProperty(
+                StyleProperty::try_from(value)?,
+            ))),
+            variant => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected custom property value, got {variant:?}"
+            ))),
+        }
+    }
+}
+
+/// The value referencing custom properties with `var()` or using font-relative
+/// units (`em`, `rem`). It is transformed by the `property` after the custom
+/// properties and the `font-size` of the element are computed. For compound
+/// properties (like `margin`) the `property` is the name of the compound property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeferredValue {
+    pub property: Tag,
+    pub value: StyleProperty,
+}
+
+impl DeferredValue {
+    /// Returns the [`DeferredValue`] if the `variant` contains `var()`
+    /// function or font-relative units.
+    pub fn from_variant(property: Tag, variant: &Variant) -> Option<DeferredValue> {
+        let value = match variant {
+            Variant::Style(value) => value.clone(),
+            Variant::String(value) if value.contains("var(") || value.contains("em") => {
+                StyleProperty::try_from(value.as_str()).ok()?
+            }
+            _ => return None,
+        };
+        (has_vars(&value) || has_font_units(&value)).then_some(DeferredValue { property, value })
+    }
+}
+
+/// Checks if any of the `tokens` is `var()` function
+pub fn has_vars(tokens: &[StylePropertyToken]) -> bool {
+    tokens.iter().any(|token| match token {
+        StylePropertyToken::Function(f) if f.name == "var" => true,
+        StylePropertyToken::Function(f) => has_vars(&f.args),
+        StylePropertyToken::Tokens(tokens) => has_vars(tokens),
+        _ => false,
+    })
+}
+
+/// Returns the names of the custom properties referenced by `var()` functions
+/// of the `tokens` including ones referenced by fallbacks.
+pub fn var_names(tokens: &[StylePropertyToken]) -> Vec<Tag> {
+    let mut names = vec![];
+    for token in tokens {
+        match token {
+            StylePropertyToken::Function(f) => {
+                if let (true, Some(StylePropertyToken::Identifier(name))) =
+                    (f.name == "var", f.args.first())
+                {
+                    names.push(name.as_tag());
+                }
+                names.extend(var_names(&f.args));
+            }
+            StylePropertyToken::Tokens(tokens) => names.extend(var_names(tokens)),
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Replaces every `var(--name, fallback)` of the `value` with the value of the
+/// custom property returned by `lookup`, or with the `fallback` if there is
+/// no such property.
+pub fn substitute_vars<'a, F: Fn(Tag) -> Option<&'a StyleProperty>>(
+    value: &StyleProperty,
+    lookup: &F,
+) -> Result<StyleProperty, ElementsError> {
+    let mut result = StyleProperty::default();
+    for token in substitute_tokens(value, lookup)? {
+        result.0.push(token);
+    }
+    Ok(result)
+}
+
+fn substitute_tokens<'a, F: Fn(Tag) -> Option<&'a StyleProperty>>(
+    tokens: &[StylePropertyToken],
+    lookup: &F,
+) -> Result<Vec<StylePropertyToken>, ElementsError> {
+    let mut result = vec![];
+    for token in tokens {
+        match token {
+            StylePropertyToken::Function(f) if f.name == "var" => {
+                let Some(StylePropertyToken::Identifier(name)) = f.args.first() else {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Expected custom property name as first argument of var(), got `{}`",
+                        token.to_string()
+                    )));
+                };
+                if let Some(value) = lookup(name.as_tag()) {
+                    result.extend(value.iter().cloned());
+                } else if f.args.len() > 1 {
+                    let mut fallback = vec![];
+                    for (idx, arg) in f.args[1..].iter().enumerate() {
+                        if idx > 0 {
+                            fallback.push(StylePropertyToken::Comma);
+                        }
+                        match arg {
+                            StylePropertyToken::Tokens(tokens) => fallback.extend(tokens.clone()),
+                            arg => fallback.push(arg.clone()),
+                        }
+                    }
+                    result.extend(substitute_tokens(&fallback, lookup)?);
+                } else {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Undefined custom property `{name}`"
+                    )));
+                }
+            }
+            StylePropertyToken::Function(f) => {
+                let mut args = vec![];
+                for arg in f.args.iter() {
+                    let mut arg = match arg {
+                        StylePropertyToken::Tokens(tokens) => substitute_tokens(tokens, lookup)?,
+                        arg => substitute_tokens(std::slice::from_ref(arg), lookup)?,
+                    };
+                    if arg.len() == 1 {
+                        args.push(arg.pop().unwrap());
+                    } else {
+                        args.push(StylePropertyToken::Tokens(arg));
+                    }
+                }
+                result.push(StylePropertyToken::Function(StylePropertyFunction {
+                    name: f.name.clone(),
+                    args,
+                }));
+            }
+            StylePropertyToken::Tokens(tokens) => {
+                result.push(StylePropertyToken::Tokens(substitute_tokens(
+                    tokens, lookup,
+                )?));
+            }
+            token => result.push(token.clone()),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::utils::HashMap;
+
+    fn prop(value: &str) -> StyleProperty {
+        StyleProperty::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn substitute_custom_properties() {
+        let mut vars = HashMap::default();
+        vars.insert("--accent".as_tag(), prop("#2196f3"));
+        vars.insert("--gap".as_tag(), prop("5px 10px"));
+        let lookup = |name: Tag| vars.get(&name);
+
+        let value = prop("var(--accent)");
+        assert!(has_vars(&value));
+        assert_eq!(substitute_vars(&value, &lookup), Ok(prop("#2196f3")));
+
+        let value = prop("var(--gap) auto");
+        assert_eq!(substitute_vars(&value, &lookup), Ok(prop("5px 10px auto")));
+
+        let value = prop("var(--missing, #fff)");
+        assert_eq!(substitute_vars(&value, &lookup), Ok(prop("#fff")));
+
+        let value = prop("var(--missing, var(--accent))");
+        assert_eq!(
+            var_names(&value),
+            vec!["--missing".as_tag(), "--accent".as_tag()]
+        );
+        assert_eq!(substitute_vars(&value, &lookup), Ok(prop("#2196f3")));
+
+        let value = prop("repeat(2, var(--gap))");
+        assert_eq!(
+            substitute_vars(&value, &lookup),
+            Ok(prop("repeat(2, 5px 10px)"))
+        );
+
+        let value = prop("var(--missing)");
+        assert!(substitute_vars(&value, &lookup).is_err());
+        assert!(!has_vars(&prop("10px auto")));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::ess::StyleSheet;
+use bevy::prelude::*;
+use smallvec::{smallvec, SmallVec};
+
+/// Attaches the stylesheet to the subtree: its rules match only the entity
+/// the component is inserted to and its descendants, so the `.slot` or `button`
+/// rules of the inventory UI don't leak into the rest of the game UI:
+/// ```rust
+/// # use belly_core::prelude::*;
+/// # use bevy::prelude::*;
+/// fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     commands.spawn((
+///         NodeBundle::default(),
+///         ScopedStyleSheet(asset_server.load("inventory.ess")),
+///     ));
+/// }
+/// ```
+/// Scoped rules win over the global rules with the same selector weight.
+/// The attached stylesheet and its imports aren't applied globally, unless
+/// they are loaded with [`StyleSheet::load`].
+#[derive(Component, Clone, Debug)]
+pub struct ScopedStyleSheet(pub Handle<StyleSheet>);
+
+/// Collects the stylesheets attached to the subtrees and the entities they are
+/// scoped to. The imported stylesheets go right before the importing ones.
+pub(crate) fn scoped_stylesheets<'a>(
+    scoped: impl Iterator<Item = (Entity, &'a ScopedStyleSheet)>,
+    assets: &Assets<StyleSheet>,
+) -> Vec<(Handle<StyleSheet>, SmallVec<[Entity; 1]>)> {
+    // the order of scopes shouldn't depend on the order of query
+    let mut scoped: Vec<_> = scoped.collect();
+    scoped.sort_by_key(|(entity, _)| *entity);
+    let mut result: Vec<(Handle<StyleSheet>, SmallVec<[Entity; 1]>)> = vec![];
+    for (entity, sheet) in scoped {
+        let mut handles = vec![];
+        with_imports(&sheet.0, assets, &mut handles);
+        for handle in handles {
+            match result.iter_mut().find(|(h, _)| *h == handle) {
+                Some((_, entities)) => entities.push(entity),
+                None => result.push((handle, smallvec![entity])),
+            }
+        }
+    }
+    result
+}
+
+fn with_imports(
+    handle: &Handle<StyleSheet>,
+    assets: &Assets<StyleSheet>,
+    handles: &mut Vec<Handle<StyleSheet>>,
+) {
+    if handles.contains(handle) {
+        return;
+    }
+    if let Some(sheet) = assets.get(handle) {
+        for import in sheet.imports() {
+            with_imports(import, assets, handles);
+        }
+    }
+    handles.push(handle.clone());
+}
@@ -1,5 +1,6 @@
 use crate::element::Element;
-use bevy::prelude::{default, Changed, Entity, Parent, Query};
+use bevy::log::error;
+use bevy::prelude::{default, Changed, Children, Entity, Parent, Query};
 use smallvec::{smallvec, SmallVec};
 use std::ops::Neg;
 use tagstr::Tag;
@@ -34,15 +35,95 @@ impl Neg for SelectorWeight {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SelectorElement {
     AnyChild,
     DirectChild,
+    AdjacentSibling,
+    GeneralSibling,
     Any,
     Id(Tag),
     Class(Tag),
     Tag(Tag),
     State(Tag),
+    /// Structural pseudo-class matching the elements by their
+    /// position among siblings: `:nth-child(an+b)`, `:first-child`,
+    /// `:nth-last-of-type(an+b)`, etc.
+    Nth {
+        a: i32,
+        b: i32,
+        of_type: bool,
+        from_end: bool,
+    },
+    /// `:only-child` or `:only-of-type`
+    Only {
+        of_type: bool,
+    },
+    /// `:scope`, the anchor element of relative selectors
+    Scope,
+    /// `:not(<compound-selector>, ...)`
+    Not(Vec<SelectorElements>),
+    /// `:has(<relative-selector>, ...)`, each selector is anchored with [`SelectorElement::Scope`]
+    Has(Vec<Selector>),
+    /// `[name]`, `[name=value]`, `[name^=value]`, etc.
+    Attribute(Tag, AttributeMatcher),
+}
+
+/// Describes how the value of the attribute selector is compared
+/// with the element attribute value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeMatcher {
+    /// `[name]`: the attribute is present
+    Exists,
+    /// `[name=value]`: exactly equal
+    Equals(String),
+    /// `[name~=value]`: whitespace-separated list containing the value
+    Includes(String),
+    /// `[name|=value]`: equal to value or starts with `value-`
+    DashMatch(String),
+    /// `[name^=value]`: starts with the value
+    Prefix(String),
+    /// `[name$=value]`: ends with the value
+    Suffix(String),
+    /// `[name*=value]`: contains the value
+    Substring(String),
+}
+
+impl AttributeMatcher {
+    pub fn matches(&self, attribute: &str) -> bool {
+        match self {
+            AttributeMatcher::Exists => true,
+            AttributeMatcher::Equals(value) => attribute == value,
+            AttributeMatcher::Includes(value) => {
+                attribute.split_whitespace().any(|item| item == value)
+            }
+            AttributeMatcher::DashMatch(value) => {
+                attribute == value
+                    || attribute
+                        .strip_prefix(value.as_str())
+                        .is_some_and(|rest| rest.starts_with('-'))
+            }
+            // empty value never matches, as in css
+            AttributeMatcher::Prefix(value) => !value.is_empty() && attribute.starts_with(value),
+            AttributeMatcher::Suffix(value) => !value.is_empty() && attribute.ends_with(value),
+            AttributeMatcher::Substring(value) => !value.is_empty() && attribute.contains(value),
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (op, value) = match self {
+            AttributeMatcher::Exists => return Ok(()),
+            AttributeMatcher::Equals(value) => ("=", value),
+            AttributeMatcher::Includes(value) => ("~=", value),
+            AttributeMatcher::DashMatch(value) => ("|=", value),
+            AttributeMatcher::Prefix(value) => ("^=", value),
+            AttributeMatcher::Suffix(value) => ("$=", value),
+            AttributeMatcher::Substring(value) => ("*=", value),
+        };
+        write!(f, "{op}{value:?}")
+    }
 }
 
 impl SelectorElement {
@@ -60,14 +141,38 @@ impl SelectorElement {
         }
     }
 
+    pub fn is_adjacent_sibling(&self) -> bool {
+        matches!(self, SelectorElement::AdjacentSibling)
+    }
+
+    pub fn is_general_sibling(&self) -> bool {
+        matches!(self, SelectorElement::GeneralSibling)
+    }
+
+    pub fn is_sibling(&self) -> bool {
+        self.is_adjacent_sibling() || self.is_general_sibling()
+    }
+
     pub fn is_separator(&self) -> bool {
-        self.is_any_child() || self.is_direct_child()
+        self.is_any_child() || self.is_direct_child() || self.is_sibling()
     }
 
     pub fn is_value(&self) -> bool {
         !self.is_separator()
     }
 
+    /// Checks if the element is `:has()` pseudo-class
+    /// or contains it (like `:not(:has(...))`)
+    pub fn is_relational(&self) -> bool {
+        match self {
+            SelectorElement::Has(_) => true,
+            SelectorElement::Not(compounds) => compounds
+                .iter()
+                .any(|compound| compound.iter().any(|e| e.is_relational())),
+            _ => false,
+        }
+    }
+
     pub fn describes_node(&self, node: &impl EmlNode) -> bool {
         match self {
             SelectorElement::Any => true,
@@ -75,6 +180,28 @@ impl SelectorElement {
             SelectorElement::State(attr) => node.has_state(attr),
             SelectorElement::Tag(tag) => node.has_tag(tag),
             SelectorElement::Class(class) => node.has_class(class),
+            SelectorElement::Nth {
+                a,
+                b,
+                of_type,
+                from_end,
+            } => node
+                .sibling_index(*of_type)
+                .map(|(index, count)| if *from_end { count - index + 1 } else { index })
+                .map(|index| nth_matches(*a, *b, index as i32))
+                .unwrap_or(false),
+            SelectorElement::Only { of_type } => node
+                .sibling_index(*of_type)
+                .map(|(_, count)| count == 1)
+                .unwrap_or(false),
+            SelectorElement::Scope => node.is_scope(),
+            SelectorElement::Not(compounds) => !compounds
+                .iter()
+                .any(|compound| compound.iter().all(|e| e.describes_node(node))),
+            SelectorElement::Has(selectors) => node.has_relative(selectors),
+            SelectorElement::Attribute(name, matcher) => node
+                .attribute(name)
+                .is_some_and(|value| matcher.matches(value)),
             _ => false,
         }
     }
@@ -83,11 +210,71 @@ impl SelectorElement {
         match self {
             SelectorElement::AnyChild => " ".to_string(),
             SelectorElement::DirectChild => " > ".to_string(),
+            SelectorElement::AdjacentSibling => " + ".to_string(),
+            SelectorElement::GeneralSibling => " ~ ".to_string(),
             SelectorElement::Any => "*".to_string(),
             SelectorElement::State(s) => format!(":{}", s),
             SelectorElement::Tag(t) => format!("{}", t),
             SelectorElement::Class(c) => format!(".{}", c),
             SelectorElement::Id(i) => format!("#{}", i),
+            SelectorElement::Nth {
+                a: 0,
+                b: 1,
+                of_type,
+                from_end,
+            } => {
+                let from = if *from_end { "last" } else { "first" };
+                let of = if *of_type { "of-type" } else { "child" };
+                format!(":{from}-{of}")
+            }
+            SelectorElement::Nth {
+                a,
+                b,
+                of_type,
+                from_end,
+            } => {
+                let last = if *from_end { "last-" } else { "" };
+                let of = if *of_type { "of-type" } else { "child" };
+                let an = match a {
+                    0 => "".to_string(),
+                    1 => "n".to_string(),
+                    -1 => "-n".to_string(),
+                    a => format!("{a}n"),
+                };
+                let b = match b {
+                    0 if *a != 0 => "".to_string(),
+                    b if *b > 0 && *a != 0 => format!("+{b}"),
+                    b => format!("{b}"),
+                };
+                format!(":nth-{last}{of}({an}{b})")
+            }
+            SelectorElement::Only { of_type: true } => ":only-of-type".to_string(),
+            SelectorElement::Only { of_type: false } => ":only-child".to_string(),
+            SelectorElement::Scope => ":scope".to_string(),
+            SelectorElement::Not(compounds) => {
+                let compounds = compounds
+                    .iter()
+                    .map(|c| c.iter().rev().map(|e| e.to_string()).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(":not({compounds})")
+            }
+            SelectorElement::Has(selectors) => {
+                let selectors = selectors
+                    .iter()
+                    .map(|s| {
+                        s.to_string()
+                            .trim_start_matches(":scope")
+                            .trim()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(":has({selectors})")
+            }
+            SelectorElement::Attribute(name, matcher) => {
+                format!("[{name}{matcher}]")
+            }
         }
     }
 
@@ -95,15 +282,48 @@ impl SelectorElement {
         match self {
             SelectorElement::AnyChild => 0,
             SelectorElement::DirectChild => 1,
+            SelectorElement::AdjacentSibling => 1,
+            SelectorElement::GeneralSibling => 0,
             SelectorElement::Any => 0,
             SelectorElement::Tag(_) => 1,
             SelectorElement::State(_) => 10,
             SelectorElement::Class(_) => 10,
             SelectorElement::Id(_) => 100,
+            SelectorElement::Nth { .. } => 10,
+            SelectorElement::Only { .. } => 10,
+            SelectorElement::Scope => 10,
+            SelectorElement::Attribute(..) => 10,
+            // the specificity of the most specific argument
+            SelectorElement::Not(compounds) => compounds
+                .iter()
+                .map(|c| c.iter().map(|e| e.weight()).sum())
+                .max()
+                .unwrap_or_default(),
+            SelectorElement::Has(selectors) => selectors
+                .iter()
+                .map(|s| {
+                    s.elements
+                        .iter()
+                        .filter(|e| !matches!(e, SelectorElement::Scope))
+                        .map(|e| e.weight())
+                        .sum()
+                })
+                .max()
+                .unwrap_or_default(),
         }
     }
 }
 
+/// Checks if 1-based `index` could be represented as `a*n + b` for some `n >= 0`
+fn nth_matches(a: i32, b: i32, index: i32) -> bool {
+    if a == 0 {
+        index == b
+    } else {
+        let diff = index - b;
+        diff % a == 0 && diff / a >= 0
+    }
+}
+
 pub type SelectorElements = SmallVec<[SelectorElement; 8]>;
 
 #[derive(Debug)]
@@ -122,7 +342,7 @@ impl<'a> SelectorEntry<'a> {
     fn next(&self) -> Option<SelectorEntry<'a>> {
         let mut offset = self.offset;
         let elements = self.elements;
-        if elements[offset].is_any_child() || elements[offset].is_direct_child() {
+        if elements[offset].is_separator() {
             offset += 1;
             if offset >= elements.len() {
                 return None;
@@ -145,7 +365,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn len(&self) -> u8 {
         let mut len = 0;
         for element in self.elements.iter().skip(self.offset) {
-            if element.is_any_child() || element.is_direct_child() {
+            if element.is_separator() {
                 return len;
             } else {
                 len += 1;
@@ -162,6 +382,18 @@ impl<'a> SelectorEntry<'a> {
         self.elements[self.offset].is_direct_child()
     }
 
+    pub fn is_adjacent_sibling(&self) -> bool {
+        self.elements[self.offset].is_adjacent_sibling()
+    }
+
+    pub fn is_general_sibling(&self) -> bool {
+        self.elements[self.offset].is_general_sibling()
+    }
+
+    pub fn is_sibling(&self) -> bool {
+        self.elements[self.offset].is_sibling()
+    }
+
     pub fn is_separator(&self) -> bool {
         self.elements[self.offset].is_separator()
     }
@@ -173,8 +405,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_id(&self, id: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::DirectChild => return false,
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Id(element_id) if id == *element_id => return true,
                 _ => continue,
             }
@@ -185,8 +416,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn get_id(&self) -> Option<Tag> {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::DirectChild => return None,
-                SelectorElement::AnyChild => return None,
+                e if e.is_separator() => return None,
                 SelectorElement::Id(id) => return Some(*id),
                 _ => continue,
             }
@@ -197,7 +427,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_class(&self, class: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Class(element_class) if class == *element_class => return true,
                 _ => continue,
             }
@@ -208,7 +438,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_tag(&self, tag: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Tag(element_tag) if tag == *element_tag => return true,
                 _ => continue,
             }
@@ -219,7 +449,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn describes_node(&self, node: &impl EmlNode) -> bool {
         let mut offset = self.offset;
         let elements = self.elements;
-        if elements[offset].is_any_child() || elements[offset].is_direct_child() {
+        if elements[offset].is_separator() {
             return false;
         }
         while offset < elements.len() && elements[offset].is_value() {
@@ -233,7 +463,7 @@ impl<'a> SelectorEntry<'a> {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Selector {
     pub weight: SelectorWeight,
     pub elements: SelectorElements,
@@ -290,7 +520,29 @@ impl Selector {
         result
     }
 
+    /// Checks if the selector uses `:has()` pseudo-class
+    pub fn is_relational(&self) -> bool {
+        self.elements.iter().any(|e| e.is_relational())
+    }
+
+    /// Checks if the leftmost compound of the selector is joined with
+    /// sibling combinator: `#tab + .panel` or `:scope ~ .a .b`
+    pub fn has_sibling_root(&self) -> bool {
+        let mut entry = self.tail();
+        let mut sibling = false;
+        while let Some(next) = entry.next() {
+            sibling = entry.is_sibling();
+            entry = next;
+        }
+        sibling
+    }
+
     pub fn get_root_id(&self) -> Option<Tag> {
+        // elements matched by `#id + .sibling` are not
+        // the descendants of the #id element
+        if self.has_sibling_root() {
+            return None;
+        }
         let mut entry = self.tail();
         while let Some(next) = entry.next() {
             entry = next;
@@ -316,8 +568,36 @@ pub trait EmlNode: Sized {
 
     fn next(&self) -> Option<Self>;
 
+    /// Returns the previous sibling of the node, if any.
+    fn prev_sibling(&self) -> Option<Self> {
+        None
+    }
+
+    /// Returns the 1-based index of the node among its siblings and the
+    /// number of the siblings (including the node). Only siblings with the
+    /// same tag are counted when `of_type` is `true`.
+    fn sibling_index(&self, _of_type: bool) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Checks if the node is the anchor of the relative selector.
+    fn is_scope(&self) -> bool {
+        false
+    }
+
+    /// Checks if any of the relative `selectors` (anchored at this node)
+    /// matches any element placed after the node.
+    fn has_relative(&self, _selectors: &[Selector]) -> bool {
+        false
+    }
+
+    /// Returns the value of the node attribute, if any.
+    fn attribute(&self, _name: &Tag) -> Option<&str> {
+        None
+    }
+
     fn fits(&self, selector: &SelectorEntry) -> Option<u8> {
-        if selector.is_direct_child() {
+        if selector.is_direct_child() || selector.is_adjacent_sibling() {
             let Some(next_selector) = selector.next() else {
                 return None;
             };
@@ -326,12 +606,17 @@ pub trait EmlNode: Sized {
             } else {
                 None
             }
-        } else if selector.is_any_child() {
+        } else if selector.is_any_child() || selector.is_general_sibling() {
             let next_selector = selector.next().unwrap();
             if let Some(weight) = self.fits(&next_selector) {
                 return Some(weight);
             }
-            if let Some(next_node) = self.next() {
+            let next_node = if selector.is_general_sibling() {
+                self.prev_sibling()
+            } else {
+                self.next()
+            };
+            if let Some(next_node) = next_node {
                 if let Some(weight) = next_node.fits(&next_selector) {
                     Some(weight + 1)
                 } else if let Some(weight) = next_node.fits(selector) {
@@ -344,7 +629,12 @@ pub trait EmlNode: Sized {
                 None
             }
         } else if selector.describes_node(self) {
-            match (self.next(), selector.next()) {
+            let next_selector = selector.next();
+            let next_node = match &next_selector {
+                Some(next) if next.is_sibling() => self.prev_sibling(),
+                _ => self.next(),
+            };
+            match (next_node, next_selector) {
                 (None, None) => Some(1),
                 (Some(_node), None) => Some(1),
                 (None, Some(_slice)) => None,
@@ -362,29 +652,184 @@ pub trait EmlNode: Sized {
     }
 }
 
+/// Read access to the elements hierarchy. Required to resolve
+/// `:has()` pseudo-classes, which looks down the tree.
+pub trait ElementsTree {
+    fn element(&self, entity: Entity) -> Option<&Element>;
+    fn parent(&self, entity: Entity) -> Option<Entity>;
+    fn children(&self, entity: Entity) -> &[Entity];
+}
+
+/// [`ElementsTree`] implementation over plain queries.
+pub struct ElementsTreeQuery<'a, 'w, 's> {
+    pub elements: &'a Query<'w, 's, &'static Element>,
+    pub parents: &'a Query<'w, 's, &'static Parent>,
+    pub children: &'a Query<'w, 's, &'static Children>,
+}
+
+impl<'a, 'w, 's> ElementsTree for ElementsTreeQuery<'a, 'w, 's> {
+    fn element(&self, entity: Entity) -> Option<&Element> {
+        self.elements.get(entity).ok()
+    }
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.parents.get(entity).ok().map(|p| p.get())
+    }
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.children
+            .get(entity)
+            .map(|ch| &**ch)
+            .unwrap_or_default()
+    }
+}
+
+/// Single level of the [`ElementsBranch`]: the element itself
+/// and all of its siblings (including the element).
+struct BranchLevel<'e> {
+    siblings: SmallVec<[&'e Element; 8]>,
+    // empty if branch was built without entities
+    entities: SmallVec<[Entity; 8]>,
+    position: usize,
+}
+
+impl<'e> BranchLevel<'e> {
+    fn new(element: &'e Element) -> BranchLevel<'e> {
+        BranchLevel {
+            siblings: smallvec![element],
+            entities: smallvec![],
+            position: 0,
+        }
+    }
+
+    fn with_siblings(siblings: SmallVec<[&'e Element; 8]>, position: usize) -> BranchLevel<'e> {
+        if position >= siblings.len() {
+            panic!(
+                "Invalid branch level position {position}, only {} siblings provided",
+                siblings.len()
+            );
+        }
+        BranchLevel {
+            siblings,
+            entities: smallvec![],
+            position,
+        }
+    }
+
+    fn element(&self) -> &'e Element {
+        self.siblings[self.position]
+    }
+}
+
 #[derive(Default)]
-pub struct ElementsBranch<'e>(SmallVec<[&'e Element; 12]>);
+pub struct ElementsBranch<'e> {
+    levels: SmallVec<[BranchLevel<'e>; 12]>,
+    tree: Option<&'e dyn ElementsTree>,
+    scope: Option<Entity>,
+}
 
 impl<'e> ElementsBranch<'e> {
     pub fn new() -> ElementsBranch<'e> {
         ElementsBranch::default()
     }
 
+    /// Creates an empty branch able to resolve `:has()` pseudo-classes
+    /// using the provided `tree`.
+    pub fn with_tree(tree: &'e dyn ElementsTree) -> ElementsBranch<'e> {
+        ElementsBranch {
+            tree: Some(tree),
+            ..default()
+        }
+    }
+
+    /// Builds the branch from the `entity` up to the root, skipping
+    /// virtual elements.
+    pub fn build(entity: Entity, tree: &'e dyn ElementsTree) -> ElementsBranch<'e> {
+        let mut branch = ElementsBranch::with_tree(tree);
+        let mut tail = entity;
+        while let Some(element) = tree.element(tail) {
+            let parent = tree.parent(tail);
+            if !element.is_virtual() {
+                let level = match parent {
+                    Some(parent) => {
+                        let siblings: SmallVec<[Entity; 8]> = tree
+                            .children(parent)
+                            .iter()
+                            .copied()
+                            .filter(|e| tree.element(*e).is_some_and(|e| !e.is_virtual()))
+                            .collect();
+                        let position = siblings.iter().position(|e| *e == tail).unwrap_or(0);
+                        branch.level(siblings, position)
+                    }
+                    None => branch.level(smallvec![tail], 0),
+                };
+                branch.levels.push(level);
+            }
+            if let Some(parent) = parent {
+                tail = parent;
+            } else {
+                break;
+            }
+        }
+        branch
+    }
+
+    /// Appends the entity at `position` of `siblings` as the new
+    /// tail of the branch. Requires the branch to be created [with tree](ElementsBranch::with_tree).
+    pub fn append_entities(&mut self, siblings: SmallVec<[Entity; 8]>, position: usize) {
+        let level = self.level(siblings, position);
+        self.levels.insert(0, level);
+    }
+
+    fn level(&self, entities: SmallVec<[Entity; 8]>, position: usize) -> BranchLevel<'e> {
+        let tree = self
+            .tree
+            .expect("ElementsBranch should be created with tree to use entities");
+        let siblings = entities
+            .iter()
+            .map(|e| {
+                tree.element(*e)
+                    .expect("Branch entities should be elements")
+            })
+            .collect();
+        let mut level = BranchLevel::with_siblings(siblings, position);
+        level.entities = entities;
+        level
+    }
+
     pub fn insert(&mut self, element: &'e Element) {
-        self.0.push(element);
+        self.levels.push(BranchLevel::new(element));
+    }
+
+    /// Inserts the element at `position` of `siblings` as the next parent
+    /// of the branch. Siblings are required by `+` and `~` combinators.
+    pub fn insert_with_siblings(&mut self, siblings: SmallVec<[&'e Element; 8]>, position: usize) {
+        self.levels
+            .push(BranchLevel::with_siblings(siblings, position));
     }
 
     pub fn append(&mut self, element: &'e Element) {
-        self.0.insert(0, element);
+        self.levels.insert(0, BranchLevel::new(element));
+    }
+
+    /// Appends the element at `position` of `siblings` as the new
+    /// tail of the branch. Siblings are required by `+` and `~` combinators.
+    pub fn append_with_siblings(&mut self, siblings: SmallVec<[&'e Element; 8]>, position: usize) {
+        self.levels
+            .insert(0, BranchLevel::with_siblings(siblings, position));
+    }
+
+    /// The element the branch was built for.
+    pub fn tail_element(&self) -> Option<&'e Element> {
+        self.levels.first().map(|level| level.element())
     }
 
     pub fn pop_tail(&mut self) {
-        self.0.pop();
+        self.levels.pop();
     }
 
     pub fn to_string(&self) -> String {
         let mut result = "".to_string();
-        for (idx, node) in self.0.iter().enumerate().rev() {
+        for (idx, level) in self.levels.iter().enumerate().rev() {
+            let node = level.element();
             if node.is_virtual() {
                 continue;
             }
@@ -400,6 +845,9 @@ impl<'e> ElementsBranch<'e> {
             for state in node.state.iter() {
                 result.push_str(&format!(":{}", state));
             }
+            for (name, value) in node.attributes.iter() {
+                result.push_str(&format!("[{name}={value:?}]"));
+            }
             if idx != 0 {
                 result.push_str(" ");
             }
@@ -409,39 +857,132 @@ impl<'e> ElementsBranch<'e> {
 }
 pub struct ElementNode<'b, 'e> {
     idx: usize,
+    position: usize,
     branch: &'b ElementsBranch<'e>,
 }
 
+impl<'b, 'e> ElementNode<'b, 'e> {
+    fn element(&self) -> &'e Element {
+        self.branch.levels[self.idx].siblings[self.position]
+    }
+
+    fn entity(&self) -> Option<Entity> {
+        self.branch.levels[self.idx]
+            .entities
+            .get(self.position)
+            .copied()
+    }
+}
+
 impl<'b, 'e> EmlNode for ElementNode<'b, 'e> {
     fn id(&self) -> Option<Tag> {
-        self.branch.0[self.idx].id
+        self.element().id
     }
     fn has_tag(&self, tag: &Tag) -> bool {
-        self.branch.0[self.idx]
+        let element = self.element();
+        element
             .names
             .iter()
-            .chain(self.branch.0[self.idx].aliases.iter())
-            .filter(|t| *t == tag)
-            .next()
-            .is_some()
+            .chain(element.aliases.iter())
+            .any(|t| t == tag)
     }
 
     fn has_class(&self, class: &Tag) -> bool {
-        self.branch.0[self.idx].classes.contains(class)
+        self.element().classes.contains(class)
     }
 
     fn has_state(&self, tag: &Tag) -> bool {
-        self.branch.0[self.idx].state.contains(tag)
+        self.element().state.contains(tag)
+    }
+
+    fn attribute(&self, name: &Tag) -> Option<&str> {
+        self.element().attributes.get(name).map(|v| v.as_str())
     }
 
     fn next(&self) -> Option<Self> {
         let idx = self.idx + 1;
         let branch = self.branch;
-        if idx >= branch.0.len() {
+        if idx >= branch.levels.len() {
+            None
+        } else {
+            let position = branch.levels[idx].position;
+            Some(ElementNode {
+                idx,
+                position,
+                branch,
+            })
+        }
+    }
+
+    fn prev_sibling(&self) -> Option<Self> {
+        if self.position == 0 {
             None
         } else {
-            Some(ElementNode { idx, branch })
+            Some(ElementNode {
+                idx: self.idx,
+                position: self.position - 1,
+                branch: self.branch,
+            })
+        }
+    }
+
+    fn sibling_index(&self, of_type: bool) -> Option<(usize, usize)> {
+        let siblings = &self.branch.levels[self.idx].siblings;
+        if !of_type {
+            return Some((self.position + 1, siblings.len()));
+        }
+        let tag = self.element().names.first();
+        let mut index = 0;
+        let mut count = 0;
+        for (position, sibling) in siblings.iter().enumerate() {
+            if sibling.names.first() != tag {
+                continue;
+            }
+            count += 1;
+            if position == self.position {
+                index = count;
+            }
+        }
+        Some((index, count))
+    }
+
+    fn is_scope(&self) -> bool {
+        self.branch.scope.is_some() && self.branch.scope == self.entity()
+    }
+
+    fn has_relative(&self, selectors: &[Selector]) -> bool {
+        let (Some(tree), Some(entity)) = (self.branch.tree, self.entity()) else {
+            return false;
+        };
+        // relative selectors starting with sibling combinator should be
+        // tested against following siblings and their descendants
+        let siblings_required = selectors.iter().any(|s| s.has_sibling_root());
+        let mut candidates: SmallVec<[Entity; 8]> = smallvec![];
+        if siblings_required {
+            if let Some(parent) = tree.parent(entity) {
+                candidates.extend(
+                    tree.children(parent)
+                        .iter()
+                        .copied()
+                        .skip_while(|e| *e != entity)
+                        .skip(1),
+                );
+            }
         }
+        candidates.extend(tree.children(entity).iter().copied());
+        while let Some(candidate) = candidates.pop() {
+            candidates.extend(tree.children(candidate).iter().copied());
+            let real = tree.element(candidate).is_some_and(|e| !e.is_virtual());
+            if !real {
+                continue;
+            }
+            let mut branch = ElementsBranch::build(candidate, tree);
+            branch.scope = Some(entity);
+            if selectors.iter().any(|s| s.matches(&branch)) {
+                return true;
+            }
+        }
+        false
     }
 }
 
@@ -451,6 +992,7 @@ impl<'b, 'e> EmlBranch for &'b ElementsBranch<'e> {
     fn tail(&self) -> Self::Node {
         ElementNode {
             idx: 0,
+            position: self.levels.first().map(|l| l.position).unwrap_or_default(),
             branch: *self,
         }
     }
@@ -463,17 +1005,16 @@ fn _example(
 ) {
     for entity in entities.iter() {
         // build branch for each entity
-        let mut branch = smallvec![];
+        let mut branch = ElementsBranch::new();
         let mut tail = entity;
         while let Ok(element) = elements.get(tail) {
-            branch.push(element);
+            branch.insert(element);
             if let Ok(parent) = parents.get(tail) {
                 tail = parent.get();
             } else {
                 break;
             }
         }
-        let branch = ElementsBranch(branch);
 
         // can now find all matching rules
         let selector: Selector = "div span".into();
@@ -485,75 +1026,24 @@ fn _example(
 
 impl From<&str> for Selector {
     fn from(source: &str) -> Self {
-        use cssparser::{Parser, ParserInput};
-        use tagstr::*;
-        const NEXT_TAG: u8 = 0;
-        const NEXT_CLASS: u8 = 1;
-        const NEXT_ATTR: u8 = 2;
-        let mut selector = Selector::default();
-        // selector.elements.push(SelectorElement::AnyChild);
-        let mut input = ParserInput::new(source);
-        let mut parser = Parser::new(&mut input);
-        let mut next = NEXT_TAG;
-        while let Ok(token) = parser.next_including_whitespace() {
-            use cssparser::Token::*;
-            match token {
-                Ident(v) => {
-                    match next {
-                        NEXT_TAG => selector
-                            .elements
-                            .insert(0, SelectorElement::Tag(v.to_string().as_tag())),
-                        NEXT_CLASS => selector
-                            .elements
-                            .insert(0, SelectorElement::Class(v.to_string().as_tag())),
-                        NEXT_ATTR => selector
-                            .elements
-                            .insert(0, SelectorElement::State(v.to_string().as_tag())),
-                        _ => panic!("Invalid NEXT_TAG"),
-                    };
-                    next = NEXT_TAG;
-                }
-                IDHash(v) => {
-                    if v.is_empty() {
-                        panic!("Invalid #id selector");
-                    } else {
-                        selector
-                            .elements
-                            .insert(0, SelectorElement::Id(v.to_string().as_tag()));
-                    }
-                }
-                WhiteSpace(_) => {
-                    if let Some(token) = selector.elements.first() {
-                        if token.is_direct_child() || token.is_any_child() {
-                            continue;
-                        }
-                    }
-                    selector.elements.insert(0, SelectorElement::AnyChild);
-                }
-                Colon => next = NEXT_ATTR,
-                Delim(c) if *c == '.' => next = NEXT_CLASS,
-                Delim(c) if *c == '*' => selector.elements.insert(0, SelectorElement::Any),
-                Delim(c) if *c == '>' => {
-                    if let Some(token) = selector.elements.first() {
-                        if token.is_any_child() {
-                            selector.elements[0] = SelectorElement::DirectChild;
-                            continue;
-                        }
-                    }
-                    selector.elements.insert(0, SelectorElement::DirectChild);
-                }
-                _ => panic!("Unexpected token: {token:?}"),
+        match crate::ess::parse_selector_list(source) {
+            Ok(selectors) if selectors.len() == 1 => selectors.into_iter().next().unwrap(),
+            Ok(_) => {
+                error!("Expected single selector, got selector list: '{source}'");
+                Selector::default()
+            }
+            Err(e) => {
+                error!("Failed to parse selector '{source}': {e}");
+                Selector::default()
             }
         }
-
-        selector
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use bevy::utils::HashSet;
+    use bevy::utils::{HashMap, HashSet};
     use tagstr::*;
 
     struct TestBranch(Vec<TestNodeData>);
@@ -616,7 +1106,10 @@ mod test {
             let void = |_| ();
             for element in selector.elements {
                 match element {
-                    SelectorElement::Any | SelectorElement::DirectChild => {
+                    SelectorElement::Any
+                    | SelectorElement::DirectChild
+                    | SelectorElement::AdjacentSibling
+                    | SelectorElement::GeneralSibling => {
                         continue;
                     }
                     SelectorElement::AnyChild => {
@@ -631,6 +1124,12 @@ mod test {
                     SelectorElement::Class(class) => void(node.classes.insert(class)),
                     SelectorElement::Id(id) => node.id = Some(id),
                     SelectorElement::Tag(tag) => node.tag = tag,
+                    SelectorElement::Nth { .. }
+                    | SelectorElement::Only { .. }
+                    | SelectorElement::Scope
+                    | SelectorElement::Not(_)
+                    | SelectorElement::Has(_)
+                    | SelectorElement::Attribute(..) => continue,
                 };
                 has_values = true;
             }
@@ -771,4 +1270,272 @@ mod test {
             );
         }
     }
+
+    fn element(tag: &str, classes: &[&str], states: &[&str]) -> Element {
+        Element {
+            names: smallvec![tag.as_tag()],
+            classes: classes.iter().map(|c| c.as_tag()).collect(),
+            state: states.iter().map(|s| s.as_tag()).collect(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn selector_sibling_elements() {
+        let root = element("div", &["tabs"], &[]);
+        let first = element("span", &["tab"], &[]);
+        let second = element("span", &["tab"], &["pressed"]);
+        let panel = element("span", &["panel"], &[]);
+        let footer = element("span", &["footer"], &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert_with_siblings(smallvec![&first, &second, &panel, &footer], 2);
+        branch.insert(&root);
+
+        let valid_selectors: &[&str] = &[
+            ".tab:pressed + .panel",
+            ".tab ~ .panel",
+            "span + span",
+            ".tab ~ .tab ~ .panel",
+            ".tab + .tab + .panel",
+            "div > .tab ~ span.panel",
+            ".tabs .tab + .panel",
+            ".tabs > span ~ .tab + *",
+        ];
+        for src in valid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                selector.matches(&branch),
+                "Selector '{}' should be matched",
+                src
+            );
+        }
+        let invalid_selectors: &[&str] = &[
+            ".footer ~ .panel",
+            ".panel + .panel",
+            ".tab + .panel + .footer",
+            ".tab:pressed + .tab + .panel",
+            ".tab:pressed ~ .tab + .panel",
+            "div + .panel",
+            "div ~ .panel",
+            ".tab > .panel",
+        ];
+        for src in invalid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                !selector.matches(&branch),
+                "Selector '{}' shouldn't be matched",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn selector_sibling_root_id() {
+        let selector: Selector = "#tabs > .tab + .panel".into();
+        assert_eq!(selector.get_root_id(), Some("tabs".as_tag()));
+        let selector: Selector = "#tab + .panel".into();
+        assert_eq!(selector.get_root_id(), None);
+        let selector: Selector = "#tab ~ .panel span".into();
+        assert_eq!(selector.get_root_id(), None);
+    }
+
+    #[test]
+    fn selector_structural_pseudo_classes() {
+        let root = element("div", &[], &[]);
+        let children = [
+            element("span", &["first"], &[]),
+            element("img", &["second"], &[]),
+            element("span", &["third"], &[]),
+            element("span", &["fourth"], &[]),
+            element("img", &["fifth"], &[]),
+        ];
+        let siblings: SmallVec<[&Element; 8]> = children.iter().collect();
+        let matched = |src: &str| -> Vec<usize> {
+            let selector: Selector = src.into();
+            (0..children.len())
+                .filter(|position| {
+                    let mut branch = ElementsBranch::new();
+                    branch.insert_with_siblings(siblings.clone(), *position);
+                    branch.insert(&root);
+                    selector.matches(&branch)
+                })
+                .collect()
+        };
+
+        assert_eq!(matched(":first-child"), vec![0]);
+        assert_eq!(matched(":last-child"), vec![4]);
+        assert_eq!(matched("span:first-of-type"), vec![0]);
+        assert_eq!(matched("span:last-of-type"), vec![3]);
+        assert_eq!(matched(":nth-child(odd)"), vec![0, 2, 4]);
+        assert_eq!(matched(":nth-child(2n)"), vec![1, 3]);
+        assert_eq!(matched(":nth-child(3)"), vec![2]);
+        assert_eq!(matched(":nth-child(n+4)"), vec![3, 4]);
+        assert_eq!(matched(":nth-child(-n+2)"), vec![0, 1]);
+        assert_eq!(matched(":nth-last-child(2)"), vec![3]);
+        assert_eq!(matched("span:nth-of-type(2)"), vec![2]);
+        assert_eq!(matched(":nth-last-of-type(1)"), vec![3, 4]);
+        assert_eq!(matched(":first-child + img"), vec![1]);
+        assert_eq!(matched("div > :nth-child(2) ~ span"), vec![2, 3]);
+        assert!(matched(":only-child").is_empty());
+        assert!(matched(":only-of-type").is_empty());
+
+        let only = element("span", &[], &[]);
+        let mut branch = ElementsBranch::new();
+        branch.insert_with_siblings(smallvec![&only], 0);
+        branch.insert(&root);
+        let selector: Selector = "div > span:only-child:first-child:last-of-type".into();
+        assert!(selector.matches(&branch));
+    }
+
+    #[derive(Default)]
+    struct TestTree {
+        elements: HashMap<Entity, Element>,
+        parents: HashMap<Entity, Entity>,
+        children: HashMap<Entity, Vec<Entity>>,
+    }
+
+    impl TestTree {
+        fn add(&mut self, parent: Option<u32>, element: Element) -> &mut Self {
+            let entity = Entity::from_raw(self.elements.len() as u32);
+            self.elements.insert(entity, element);
+            if let Some(parent) = parent {
+                let parent = Entity::from_raw(parent);
+                self.parents.insert(entity, parent);
+                self.children.entry(parent).or_default().push(entity);
+            }
+            self
+        }
+
+        fn select(&self, selector: &str) -> Vec<u32> {
+            let selector: Selector = selector.into();
+            let mut result: Vec<_> = self
+                .elements
+                .keys()
+                .filter(|e| selector.matches(&ElementsBranch::build(**e, self)))
+                .map(|e| e.index())
+                .collect();
+            result.sort();
+            result
+        }
+    }
+
+    impl ElementsTree for TestTree {
+        fn element(&self, entity: Entity) -> Option<&Element> {
+            self.elements.get(&entity)
+        }
+        fn parent(&self, entity: Entity) -> Option<Entity> {
+            self.parents.get(&entity).copied()
+        }
+        fn children(&self, entity: Entity) -> &[Entity] {
+            self.children
+                .get(&entity)
+                .map(|ch| ch.as_slice())
+                .unwrap_or_default()
+        }
+    }
+
+    fn list_tree() -> TestTree {
+        let mut tree = TestTree::default();
+        tree.add(None, element("div", &["list"], &[]))
+            .add(Some(0), element("row", &["item"], &[]))
+            .add(Some(0), element("row", &["item", "selected"], &[]))
+            .add(Some(0), element("row", &["footer"], &[]))
+            .add(Some(1), element("span", &["label"], &[]))
+            .add(Some(1), element("img", &["icon"], &[]))
+            .add(Some(2), element("span", &["label"], &[]));
+        tree
+    }
+
+    #[test]
+    fn selector_negation() {
+        let tree = list_tree();
+        assert_eq!(tree.select("row:not(.selected)"), vec![1, 3]);
+        assert_eq!(tree.select("row:not(.selected, .footer)"), vec![1]);
+        assert_eq!(tree.select(".list > :not(.item)"), vec![3]);
+        assert_eq!(tree.select(":not(row) > span"), Vec::<u32>::new());
+        assert_eq!(tree.select("row:not(:first-child) span"), vec![6]);
+        assert_eq!(tree.select("*:not(row):not(div)"), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn selector_relational() {
+        let tree = list_tree();
+        assert_eq!(tree.select("row:has(img)"), vec![1]);
+        assert_eq!(tree.select("row:has(> .icon)"), vec![1]);
+        assert_eq!(tree.select("div:has(> .icon)"), Vec::<u32>::new());
+        assert_eq!(tree.select("div:has(.icon)"), vec![0]);
+        assert_eq!(tree.select("div:has(.item .icon)"), vec![0]);
+        assert_eq!(tree.select("row:has(+ .selected)"), vec![1]);
+        assert_eq!(tree.select("row:has(~ .footer)"), vec![1, 2]);
+        assert_eq!(tree.select("row:has(~ * .label)"), vec![1]);
+        assert_eq!(tree.select("row:has(img, + .footer)"), vec![1, 2]);
+        assert_eq!(tree.select("row:not(:has(img))"), vec![2, 3]);
+        assert_eq!(tree.select(".item:has(.label) + .item span"), vec![6]);
+    }
+
+    #[test]
+    fn selector_negation_and_relational_weight() {
+        let selector: Selector = "row:not(#a)".into();
+        assert_eq!(selector.weight, SelectorWeight(101, 0));
+        let selector: Selector = "row:not(.a, #b.c)".into();
+        assert_eq!(selector.weight, SelectorWeight(111, 0));
+        // combinators have own weight
+        let selector: Selector = "row:has(> .a .b)".into();
+        assert_eq!(selector.weight, SelectorWeight(22, 0));
+        assert_eq!(selector.to_string(), "row:has(> .a .b)");
+        let selector: Selector = "row:has(.a, + #b)".into();
+        assert_eq!(selector.weight, SelectorWeight(102, 0));
+        assert_eq!(selector.to_string(), "row:has(.a, + #b)");
+    }
+
+    fn with_attributes(mut element: Element, attributes: &[(&str, &str)]) -> Element {
+        for (name, value) in attributes {
+            element.attributes.insert(name.as_tag(), value.to_string());
+        }
+        element
+    }
+
+    #[test]
+    fn selector_attributes() {
+        let mut tree = TestTree::default();
+        tree.add(None, element("div", &[], &[]))
+            .add(
+                Some(0),
+                with_attributes(element("button", &[], &[]), &[("mode", "toggle")]),
+            )
+            .add(
+                Some(0),
+                with_attributes(
+                    element("button", &[], &[]),
+                    &[("mode", "press"), ("value", "tab-1"), ("disabled", "")],
+                ),
+            )
+            .add(
+                Some(0),
+                with_attributes(element("range", &[], &[]), &[("mode", "vertical")]),
+            )
+            .add(
+                Some(0),
+                with_attributes(element("span", &[], &[]), &[("lang", "en-US")]),
+            );
+        assert_eq!(tree.select("[mode]"), vec![1, 2, 3]);
+        assert_eq!(tree.select("button[mode=toggle]"), vec![1]);
+        assert_eq!(tree.select("[mode=\"vertical\"]"), vec![3]);
+        assert_eq!(tree.select("[value^=\"tab\"]"), vec![2]);
+        assert_eq!(tree.select("[value$=\"-1\"]"), vec![2]);
+        assert_eq!(tree.select("[mode*=ess]"), vec![2]);
+        assert_eq!(tree.select("[value^=\"\"]"), Vec::<u32>::new());
+        assert_eq!(tree.select("[lang|=en]"), vec![4]);
+        assert_eq!(tree.select("[lang~=en]"), Vec::<u32>::new());
+        assert_eq!(tree.select("button[disabled]"), vec![2]);
+        assert_eq!(tree.select("button:not([disabled])"), vec![1]);
+        assert_eq!(
+            tree.select("div:has([disabled]) > [mode=vertical]"),
+            vec![3]
+        );
+
+        let selector: Selector = "button[mode=toggle]".into();
+        assert_eq!(selector.weight, SelectorWeight(11, 0));
+        assert_eq!(selector.to_string(), "button[mode=\"toggle\"]");
+    }
 }
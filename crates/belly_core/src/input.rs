@@ -181,6 +181,32 @@ pub struct NodeQuery {
     view_visibility: Option<&'static ViewVisibility>,
 }
 
+/// Checks if the `point` is inside the node of the `size` as it is drawn: the
+/// point is transformed into the space of the node, so the nodes rotated or
+/// scaled by `transform` property are hit where they are visible. The clip rect
+/// is axis-aligned, so the point is clipped as is.
+fn contains_point(
+    size: Vec2,
+    transform: &GlobalTransform,
+    clip: Option<&CalculatedClip>,
+    point: Vec2,
+) -> bool {
+    if let Some(clip) = clip {
+        if !(clip.clip.min.x..clip.clip.max.x).contains(&point.x)
+            || !(clip.clip.min.y..clip.clip.max.y).contains(&point.y)
+        {
+            return false;
+        }
+    }
+    let local = transform
+        .affine()
+        .inverse()
+        .transform_point3(point.extend(0.))
+        .truncate();
+    let extents = size / 2.0;
+    (-extents.x..extents.x).contains(&local.x) && (-extents.y..extents.y).contains(&local.y)
+}
+
 // pointer_input_system is the rewriten bevy's ui_focus_system
 // it emit PointerEvent with associated entities and data.
 pub fn pointer_input_system(
@@ -247,20 +273,15 @@ pub fn pointer_input_system(
                     }
                 }
 
-                let position = node.global_transform.translation();
-                let ui_position = position.truncate();
-                let extents = node.node.size() / 2.0;
-                let mut min = ui_position - extents;
-                let mut max = ui_position + extents;
-                if let Some(clip) = node.calculated_clip {
-                    min = Vec2::max(min, clip.clip.min);
-                    max = Vec2::min(max, clip.clip.max);
-                }
                 // if the current cursor position is within the bounds of the node, consider it for
                 // emiting the event
                 let contains_cursor = if let Some(cursor_position) = cursor_position {
-                    (min.x..max.x).contains(&cursor_position.x)
-                        && (min.y..max.y).contains(&cursor_position.y)
+                    contains_point(
+                        node.node.size(),
+                        node.global_transform,
+                        node.calculated_clip,
+                        cursor_position,
+                    )
                 } else {
                     false
                 };
@@ -544,3 +565,41 @@ pub fn tab_focus_system(
         break;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    #[test]
+    fn contains_transformed_point() {
+        let size = Vec2::new(100., 20.);
+        let hits = |transform: Transform, clip: Option<Rect>, x, y| {
+            let clip = clip.map(|clip| CalculatedClip { clip });
+            let transform = GlobalTransform::from(transform);
+            contains_point(size, &transform, clip.as_ref(), Vec2::new(x, y))
+        };
+        let plain = Transform::from_xyz(100., 100., 0.);
+        assert!(hits(plain, None, 140., 105.));
+        assert!(!hits(plain, None, 100., 115.));
+
+        // rotated by 45 degrees the wide node covers the diagonal
+        let rotated = plain.with_rotation(Quat::from_rotation_z(FRAC_PI_4));
+        assert!(hits(rotated, None, 130., 130.));
+        assert!(hits(rotated, None, 90., 90.));
+        assert!(!hits(rotated, None, 140., 105.));
+
+        // scaled twice the node covers the points outside of its size
+        let scaled = plain.with_scale(Vec3::splat(2.));
+        assert!(hits(scaled, None, 190., 115.));
+        assert!(!hits(scaled, None, 100., 125.));
+
+        // the clip rect cuts the visible part of the transformed node
+        let clip = Some(Rect::new(0., 0., 150., 150.));
+        assert!(hits(scaled, clip, 140., 115.));
+        assert!(!hits(scaled, clip, 160., 115.));
+        let clip = Some(Rect::new(100., 100., 200., 200.));
+        assert!(hits(rotated, clip, 130., 130.));
+        assert!(!hits(rotated, clip, 90., 90.));
+    }
+}
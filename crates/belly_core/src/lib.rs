@@ -35,6 +35,9 @@ pub mod prelude {
     pub use crate::element::Elements;
     pub use crate::eml::asset::EmlAsset;
     pub use crate::eml::asset::EmlScene;
+    pub use crate::ess::ComputedStyle;
+    pub use crate::ess::Opacity;
+    pub use crate::ess::ScopedStyleSheet;
     pub use crate::ess::StyleSheet;
     pub use crate::relations::connect::Connect;
     pub use crate::relations::connect::EventSource;
@@ -60,6 +63,7 @@ pub mod build {
     pub use crate::relations::props::GetProperties;
 
     // structs
+    pub use crate::element::DisplayElement;
     pub use crate::element::ElementBundle;
     pub use crate::element::TextElementBundle;
     pub use crate::eml::Variant;
@@ -120,6 +120,30 @@ pub fn parse_ident(input: syn::parse::ParseStream) -> syn::Result<String> {
     Ok(ident)
 }
 
+/// Stringifies the functional pseudo-class arguments, keeping
+/// the significant whitespaces: `:has(.a .b)`
+fn stringify_args(group: &proc_macro2::Group) -> String {
+    let mut result = String::new();
+    let mut last = group.span_open();
+    for tt in group.stream() {
+        let span = tt.span();
+        if !result.is_empty() && last.has_space(&span) {
+            result.push(' ');
+        }
+        match &tt {
+            TokenTree::Group(group) => {
+                result.push_str(&format!("({})", stringify_args(group)));
+                last = group.span_close();
+            }
+            tt => {
+                result.push_str(&tt.to_string());
+                last = span;
+            }
+        }
+    }
+    result
+}
+
 #[derive(Debug, Clone)]
 pub enum SelectorToken {
     Tag(String),
@@ -128,7 +152,21 @@ pub enum SelectorToken {
     State(String),
     AnyChild,
     DirectChild,
+    AdjacentSibling,
+    GeneralSibling,
     Any,
+    Attribute(String),
+}
+
+impl SelectorToken {
+    pub fn is_combinator(&self) -> bool {
+        match self {
+            Self::AnyChild | Self::DirectChild | Self::AdjacentSibling | Self::GeneralSibling => {
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl SelectorToken {
@@ -157,7 +195,10 @@ impl std::fmt::Display for SelectorToken {
             Self::State(s) => write!(f, ":{s}"),
             Self::AnyChild => write!(f, " "),
             Self::DirectChild => write!(f, " > "),
+            Self::AdjacentSibling => write!(f, " + "),
+            Self::GeneralSibling => write!(f, " ~ "),
             Self::Any => write!(f, "*"),
+            Self::Attribute(s) => write!(f, "[{s}]"),
         }
     }
 }
@@ -168,12 +209,10 @@ pub struct Selector(Vec<SelectorToken>);
 impl Selector {
     pub fn push(&mut self, token: SelectorToken) {
         match (token, self.last()) {
-            (SelectorToken::DirectChild, None)
-            | (SelectorToken::AnyChild, None)
-            | (SelectorToken::AnyChild, Some(SelectorToken::AnyChild))
-            | (SelectorToken::AnyChild, Some(SelectorToken::DirectChild)) => {}
-            (SelectorToken::DirectChild, Some(SelectorToken::AnyChild)) => {
-                *self.0.last_mut().unwrap() = SelectorToken::DirectChild
+            (token, None) if token.is_combinator() => {}
+            (SelectorToken::AnyChild, Some(last)) if last.is_combinator() => {}
+            (token, Some(SelectorToken::AnyChild)) if token.is_combinator() => {
+                *self.0.last_mut().unwrap() = token
             }
             (token, _) => self.0.push(token),
         }
@@ -237,6 +276,17 @@ impl StyleValueToken {
     }
 }
 
+/// Checks if the punct at the cursor is followed by a whitespace
+fn is_spaced_punct(input: syn::parse::ParseStream) -> bool {
+    let Some((punct, rest)) = input.cursor().punct() else {
+        return false;
+    };
+    match rest.token_tree() {
+        Some((tt, _)) => punct.span().has_space(&tt.span()),
+        None => false,
+    }
+}
+
 #[derive(Deref, Debug)]
 pub struct StyleValue(Vec<StyleValueToken>);
 
@@ -271,6 +321,41 @@ impl syn::parse::Parse for StyleValue {
             } else if input.peek(Token![/]) {
                 input.parse::<Token![/]>()?;
                 value.push(StyleValueToken::Slash);
+            } else if input.peek(Token![-]) && is_spaced_punct(input) {
+                // calc() operators, `-` should be followed by a space: `100% - 20px`
+                input.parse::<Token![-]>()?;
+                value.push(StyleValueToken::Ident("-".into()));
+            } else if input.peek(Token![+]) {
+                input.parse::<Token![+]>()?;
+                value.push(StyleValueToken::Ident("+".into()));
+            } else if input.peek(Token![*]) {
+                input.parse::<Token![*]>()?;
+                value.push(StyleValueToken::Ident("*".into()));
+            } else if input.peek(Token![!]) {
+                input.parse::<Token![!]>()?;
+                let ident = input.parse::<syn::Ident>()?;
+                if ident != "important" {
+                    throw!(ident.span(), "Expected `!important`");
+                }
+                value.push(StyleValueToken::Ident("!important".into()));
+            } else if input.peek(syn::token::Paren) {
+                // parentheses of calc() are functions without name
+                let content;
+                syn::parenthesized!(content in input);
+                let args: Punctuated<StyleValue, Token![,]> =
+                    content.parse_terminated(StyleValue::parse)?;
+                value.push(StyleValueToken::Function(
+                    String::new(),
+                    args.into_iter()
+                        .map(|mut value| {
+                            if value.len() == 1 {
+                                value.0.pop().unwrap()
+                            } else {
+                                StyleValueToken::Values(value.0)
+                            }
+                        })
+                        .collect(),
+                ));
             } else if input.peek(Token![#]) {
                 input.parse::<Token![#]>()?;
                 let color = input.step(|cursor| {
@@ -411,17 +496,27 @@ impl syn::parse::Parse for StyleProperty {
 #[derive(Debug)]
 pub struct StyleRule {
     comments: Vec<String>,
-    selector: Selector,
+    selectors: Vec<Selector>,
     properties: Vec<StyleProperty>,
 }
 
+impl StyleRule {
+    fn selectors_to_string(&self) -> String {
+        self.selectors
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
 impl std::fmt::Display for StyleRule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if f.alternate() {
             for comment in self.comments.iter() {
                 write!(f, "/**{comment}*/\n")?;
             }
-            write!(f, "{} {{\n", self.selector)?;
+            write!(f, "{} {{\n", self.selectors_to_string())?;
 
             for (idx, property) in self.properties.iter().enumerate() {
                 for (pidx, comment) in property.comments.iter().enumerate() {
@@ -435,7 +530,7 @@ impl std::fmt::Display for StyleRule {
             write!(
                 f,
                 "{} {{ {} }}",
-                self.selector.to_string(),
+                self.selectors_to_string(),
                 self.properties
                     .iter()
                     .map(|p| p.to_string())
@@ -450,8 +545,9 @@ impl syn::parse::Parse for StyleRule {
     fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
         let comments = parse_docs(&mut input)?;
         // parse selector
-        let selector = input.step(|cursor| {
+        let selectors = input.step(|cursor| {
             let mut rest = *cursor;
+            let mut selectors = vec![];
             let mut selector = Selector(vec![]);
             let mut last = cursor.span();
             let mut token = None;
@@ -544,11 +640,64 @@ impl syn::parse::Parse for StyleRule {
                         selector.push(SelectorToken::DirectChild);
                         token = None;
                     }
+                    TokenTree::Punct(p) if p.as_char() == '+' => {
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        selector.push(SelectorToken::AdjacentSibling);
+                        token = None;
+                    }
+                    TokenTree::Punct(p) if p.as_char() == '~' => {
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        selector.push(SelectorToken::GeneralSibling);
+                        token = None;
+                    }
+                    TokenTree::Punct(p) if p.as_char() == ',' => {
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        if selector.is_empty() {
+                            throw!(span, "Expected selector before ','");
+                        }
+                        selectors.push(selector);
+                        selector = Selector(vec![]);
+                        token = None;
+                    }
+                    TokenTree::Group(group)
+                        if group.delimiter() == Delimiter::Parenthesis
+                            && matches!(token, Some(SelectorToken::State(_))) =>
+                    {
+                        // functional pseudo-class: `:nth-child(2n + 1)`
+                        let args = stringify_args(&group);
+                        token
+                            .as_mut()
+                            .unwrap()
+                            .add_str(format!("({args})").as_str(), &span)?;
+                        selector.push(token.unwrap());
+                        token = None;
+                    }
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                        // attribute selector: `[mode=toggle]`
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        if has_space {
+                            selector.push(SelectorToken::AnyChild);
+                        }
+                        selector.push(SelectorToken::Attribute(stringify_args(&group)));
+                        token = None;
+                    }
                     TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
                         if token.is_some() {
                             selector.push(token.unwrap());
                         }
-                        return Ok((selector, rest));
+                        if selector.is_empty() && !selectors.is_empty() {
+                            throw!(span, "Expected selector after ','");
+                        }
+                        selectors.push(selector);
+                        return Ok((selectors, rest));
                     }
                     e => throw!(span, "Unsupported selector: {e:?}"),
                 }
@@ -557,7 +706,8 @@ impl syn::parse::Parse for StyleRule {
             if token.is_some() {
                 selector.push(token.unwrap());
             }
-            Ok((selector, rest))
+            selectors.push(selector);
+            Ok((selectors, rest))
         })?;
 
         let mut properties = vec![];
@@ -579,14 +729,425 @@ impl syn::parse::Parse for StyleRule {
 
         Ok(StyleRule {
             comments,
-            selector,
+            selectors,
             properties,
         })
     }
 }
 
+/// `@media` rule with the nested rules:
+/// `@media (max-width: 800px) { .sidebar { display: none } }`
+#[derive(Debug)]
+pub struct MediaRule {
+    comments: Vec<String>,
+    query: String,
+    rules: StyleSheet,
+}
+
+impl std::fmt::Display for MediaRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            for comment in self.comments.iter() {
+                write!(f, "/**{comment}*/\n")?;
+            }
+            write!(f, "@media {} {{\n", self.query)?;
+            for (idx, item) in self.rules.0.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "\n")?;
+                }
+                for line in format!("{item:#}").lines() {
+                    if line.is_empty() {
+                        write!(f, "\n")?;
+                    } else {
+                        write!(f, "  {line}\n")?;
+                    }
+                }
+            }
+            write!(f, "}}\n")
+        } else {
+            write!(
+                f,
+                "@media {} {{ {} }}",
+                self.query,
+                self.rules
+                    .0
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        }
+    }
+}
+
+impl syn::parse::Parse for MediaRule {
+    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let comments = parse_docs(&mut input)?;
+        input.parse::<Token![@]>()?;
+        let name = input.parse::<syn::Ident>()?;
+        if name != "media" {
+            throw!(name.span(), "Unsupported at-rule `@{name}`");
+        }
+        let query = input.step(|cursor| {
+            let mut rest = *cursor;
+            let mut query = String::new();
+            let mut last = name.span();
+            while let Some((tt, next)) = rest.token_tree() {
+                let span = tt.span();
+                match &tt {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                        break;
+                    }
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                        if !query.is_empty() && last.has_space(&span) {
+                            query.push(' ');
+                        }
+                        query.push_str(&format!("({})", stringify_args(group)));
+                        last = group.span_close();
+                    }
+                    tt => {
+                        if !query.is_empty() && last.has_space(&span) {
+                            query.push(' ');
+                        }
+                        query.push_str(&tt.to_string());
+                        last = span;
+                    }
+                }
+                rest = next;
+            }
+            if query.is_empty() {
+                throw!(rest.span(), "Expected media query");
+            }
+            Ok((query, rest))
+        })?;
+        let content;
+        braced!(content in input);
+        let rules = content.parse()?;
+        Ok(MediaRule {
+            comments,
+            query,
+            rules,
+        })
+    }
+}
+
+/// `@import "theme/colors.ess";`
+#[derive(Debug)]
+pub struct ImportRule {
+    comments: Vec<String>,
+    path: String,
+}
+
+impl std::fmt::Display for ImportRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            for comment in self.comments.iter() {
+                write!(f, "/**{comment}*/\n")?;
+            }
+            write!(f, "@import {:?};\n", self.path)
+        } else {
+            write!(f, "@import {:?};", self.path)
+        }
+    }
+}
+
+impl syn::parse::Parse for ImportRule {
+    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let comments = parse_docs(&mut input)?;
+        input.parse::<Token![@]>()?;
+        let name = input.parse::<syn::Ident>()?;
+        if name != "import" {
+            throw!(name.span(), "Expected `@import`");
+        }
+        let path = input.parse::<syn::LitStr>()?.value();
+        input.parse::<Token![;]>()?;
+        Ok(ImportRule { comments, path })
+    }
+}
+
+/// Single keyframe of the `@keyframes` rule: `from, 50% { width: 10px; }`
+#[derive(Debug)]
+pub struct Keyframe {
+    comments: Vec<String>,
+    offsets: Vec<String>,
+    properties: Vec<StyleProperty>,
+}
+
+impl std::fmt::Display for Keyframe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            for comment in self.comments.iter() {
+                write!(f, "/**{comment}*/\n")?;
+            }
+            write!(f, "{} {{\n", self.offsets.join(", "))?;
+            for (idx, property) in self.properties.iter().enumerate() {
+                for (pidx, comment) in property.comments.iter().enumerate() {
+                    let nl = if idx == 0 || pidx != 0 { "" } else { "\n" };
+                    write!(f, "{nl}  /**{comment}*/\n")?;
+                }
+                write!(f, "{property:#}")?;
+            }
+            write!(f, "}}\n")
+        } else {
+            write!(
+                f,
+                "{} {{ {} }}",
+                self.offsets.join(", "),
+                self.properties
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        }
+    }
+}
+
+impl syn::parse::Parse for Keyframe {
+    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let comments = parse_docs(&mut input)?;
+        let mut offsets = vec![];
+        loop {
+            if input.peek(syn::Ident) {
+                let ident = input.parse::<syn::Ident>()?;
+                if ident != "from" && ident != "to" {
+                    throw!(ident.span(), "Expected `from`, `to` or percentage");
+                }
+                offsets.push(ident.to_string());
+            } else {
+                let offset = input.parse::<syn::Lit>()?;
+                let value = match &offset {
+                    syn::Lit::Int(int) => int.base10_digits().to_string(),
+                    syn::Lit::Float(float) => float.base10_digits().to_string(),
+                    _ => throw!(offset.span(), "Expected `from`, `to` or percentage"),
+                };
+                input.parse::<Token![%]>()?;
+                offsets.push(format!("{value}%"));
+            }
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            } else {
+                break;
+            }
+        }
+        let content;
+        braced!(content in input);
+        let properties: Punctuated<StyleProperty, Token![;]> =
+            content.parse_terminated(StyleProperty::parse)?;
+        Ok(Keyframe {
+            comments,
+            offsets,
+            properties: properties.into_iter().collect(),
+        })
+    }
+}
+
+/// `@keyframes` rule with the keyframes:
+/// `@keyframes pulse { from { width: 0px } to { width: 10px } }`
+#[derive(Debug)]
+pub struct KeyframesRule {
+    comments: Vec<String>,
+    name: String,
+    frames: Vec<Keyframe>,
+}
+
+impl std::fmt::Display for KeyframesRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            for comment in self.comments.iter() {
+                write!(f, "/**{comment}*/\n")?;
+            }
+            write!(f, "@keyframes {} {{\n", self.name)?;
+            for (idx, frame) in self.frames.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "\n")?;
+                }
+                for line in format!("{frame:#}").lines() {
+                    write!(f, "  {line}\n")?;
+                }
+            }
+            write!(f, "}}\n")
+        } else {
+            write!(
+                f,
+                "@keyframes {} {{ {} }}",
+                self.name,
+                self.frames
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        }
+    }
+}
+
+impl syn::parse::Parse for KeyframesRule {
+    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let comments = parse_docs(&mut input)?;
+        input.parse::<Token![@]>()?;
+        let keyword = input.parse::<syn::Ident>()?;
+        if keyword != "keyframes" {
+            throw!(keyword.span(), "Expected `@keyframes`");
+        }
+        let name = parse_ident(input)?;
+        let content;
+        braced!(content in input);
+        let mut frames = vec![];
+        while !content.is_empty() {
+            frames.push(content.parse()?);
+        }
+        Ok(KeyframesRule {
+            comments,
+            name,
+            frames,
+        })
+    }
+}
+
+/// `@layer` statement with the order of layers: `@layer base, app;`
+/// or `@layer` rule with the rules of the layer: `@layer app { .button { width: 10px } }`
+#[derive(Debug)]
+pub struct LayerRule {
+    comments: Vec<String>,
+    names: Vec<String>,
+    rules: Option<StyleSheet>,
+}
+
+impl std::fmt::Display for LayerRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            for comment in self.comments.iter() {
+                write!(f, "/**{comment}*/\n")?;
+            }
+        }
+        let names = self.names.join(", ");
+        let Some(rules) = &self.rules else {
+            return match f.alternate() {
+                true => write!(f, "@layer {names};\n"),
+                false => write!(f, "@layer {names};"),
+            };
+        };
+        if f.alternate() {
+            write!(f, "@layer {names} {{\n")?;
+            for (idx, item) in rules.0.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "\n")?;
+                }
+                for line in format!("{item:#}").lines() {
+                    if line.is_empty() {
+                        write!(f, "\n")?;
+                    } else {
+                        write!(f, "  {line}\n")?;
+                    }
+                }
+            }
+            write!(f, "}}\n")
+        } else {
+            write!(
+                f,
+                "@layer {names} {{ {} }}",
+                rules
+                    .0
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        }
+    }
+}
+
+impl syn::parse::Parse for LayerRule {
+    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let comments = parse_docs(&mut input)?;
+        input.parse::<Token![@]>()?;
+        let keyword = input.parse::<syn::Ident>()?;
+        if keyword != "layer" {
+            throw!(keyword.span(), "Expected `@layer`");
+        }
+        let mut names = vec![parse_ident(input)?];
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            names.push(parse_ident(input)?);
+        }
+        if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            return Ok(LayerRule {
+                comments,
+                names,
+                rules: None,
+            });
+        }
+        if names.len() > 1 {
+            throw!(
+                keyword.span(),
+                "`@layer` rule with the block expects a single layer"
+            );
+        }
+        let content;
+        braced!(content in input);
+        let rules = content.parse()?;
+        Ok(LayerRule {
+            comments,
+            names,
+            rules: Some(rules),
+        })
+    }
+}
+
+/// Top-level item of the stylesheet: style rule, `@media`, `@import`,
+/// `@keyframes` or `@layer` rule
+#[derive(Debug)]
+pub enum StyleItem {
+    Rule(StyleRule),
+    Media(MediaRule),
+    Import(ImportRule),
+    Keyframes(KeyframesRule),
+    Layer(LayerRule),
+}
+
+impl std::fmt::Display for StyleItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self, f.alternate()) {
+            (StyleItem::Rule(rule), true) => write!(f, "{rule:#}"),
+            (StyleItem::Rule(rule), false) => write!(f, "{rule}"),
+            (StyleItem::Media(media), true) => write!(f, "{media:#}"),
+            (StyleItem::Media(media), false) => write!(f, "{media}"),
+            (StyleItem::Import(import), true) => write!(f, "{import:#}"),
+            (StyleItem::Import(import), false) => write!(f, "{import}"),
+            (StyleItem::Keyframes(keyframes), true) => write!(f, "{keyframes:#}"),
+            (StyleItem::Keyframes(keyframes), false) => write!(f, "{keyframes}"),
+            (StyleItem::Layer(layer), true) => write!(f, "{layer:#}"),
+            (StyleItem::Layer(layer), false) => write!(f, "{layer}"),
+        }
+    }
+}
+
+impl syn::parse::Parse for StyleItem {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut fork = input.fork();
+        parse_docs(&mut &fork)?;
+        if fork.peek(Token![@]) && fork.peek2(syn::Ident) {
+            fork.parse::<Token![@]>()?;
+            let keyword = fork.parse::<syn::Ident>()?;
+            if keyword == "import" {
+                Ok(StyleItem::Import(input.parse()?))
+            } else if keyword == "keyframes" {
+                Ok(StyleItem::Keyframes(input.parse()?))
+            } else if keyword == "layer" {
+                Ok(StyleItem::Layer(input.parse()?))
+            } else {
+                Ok(StyleItem::Media(input.parse()?))
+            }
+        } else {
+            Ok(StyleItem::Rule(input.parse()?))
+        }
+    }
+}
+
 #[derive(Debug, Default)]
-pub struct StyleSheet(Vec<StyleRule>);
+pub struct StyleSheet(Vec<StyleItem>);
 
 impl std::fmt::Display for StyleSheet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -638,11 +1199,22 @@ mod test {
             "tag.class > direct-child:state {  }",
             ":state any-child.class {  }",
             "tag-name.class-name :some-state #cool-id {  }",
+            ".a, .b {  }",
+            ".tab:pressed + .panel {  }",
+            "tab ~ panel > content {  }",
+            "row:nth-child(2n+1) > cell:first-child {  }",
+            "row:nth-last-of-type(-n+3) {  }",
+            "row:not(.selected, :first-child) {  }",
+            "row:has(> img.icon) > label {  }",
+            "list:has(row:nth-child(2n + 1) label) {  }",
+            "tag > .a, tag:state .b, * {  }",
+            "button[mode=toggle] {  }",
+            "tab[value^=\"tab\"] > [disabled] {  }",
         ];
         for src in selecors {
             let stream: TokenStream = src.parse().unwrap();
             let rule: StyleRule = syn::parse2(stream).unwrap();
-            println!("Selector: {:?}", rule.selector);
+            println!("Selectors: {:?}", rule.selectors);
             assert_eq!(rule.to_string().as_str(), *src);
         }
     }
@@ -670,6 +1242,12 @@ mod test {
             r#"body { grid-template-columns: min-content flex(1); }"#,
             // test slash formating
             r#"body { grid-row: 2 / span 2; }"#,
+            // custom properties
+            r#"body { --accent: #2196f3; color: var(--accent, #ffffff); }"#,
+            r#"body { margin: var(--gap) 10px; }"#,
+            // calc() and relative units
+            r#"body { width: calc(100% - 2 * 10px); height: 50vh; }"#,
+            r#"body { margin: calc((100% + 2em) / 2) 1.5rem; }"#,
         ];
         for src in rules {
             println!("Checking '{src}'");
@@ -776,4 +1354,105 @@ div {
         println!("StyleSheet: {:?}", stylesheet);
         assert_eq!(format!("{stylesheet:#}").as_str().trim(), src.trim());
     }
+
+    #[test]
+    fn test_media() {
+        let src = r#"
+@import "theme/colors.ess";
+
+/** narrow layout */
+@media (max-width: 800px), screen and (orientation: portrait) {
+  .sidebar {
+    display: none;
+  }
+
+  @media (min-resolution: 2dppx) {
+    img {
+      width: 50%;
+    }
+  }
+}
+
+body {
+  prop: value;
+}
+"#;
+        println!("Checking '{src}'");
+        let stream: TokenStream = src.parse().unwrap();
+        let stylesheet: StyleSheet = syn::parse2(stream).unwrap();
+        println!("StyleSheet: {:?}", stylesheet);
+        assert_eq!(format!("{stylesheet:#}").as_str().trim(), src.trim());
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let src = r#"
+/** pulsing background */
+@keyframes pulse-bg {
+  from {
+    background-color: white;
+  }
+
+  50%, 62.5% {
+    /** half way */
+    background-color: red;
+    width: 10px;
+  }
+
+  to {
+    background-color: white;
+  }
+}
+
+.button {
+  animation: pulse-bg 1s infinite alternate ease-in-out;
+}
+"#;
+        println!("Checking '{src}'");
+        let stream: TokenStream = src.parse().unwrap();
+        let stylesheet: StyleSheet = syn::parse2(stream).unwrap();
+        println!("StyleSheet: {:?}", stylesheet);
+        assert_eq!(format!("{stylesheet:#}").as_str().trim(), src.trim());
+        assert_eq!(
+            stylesheet.0[0].to_string(),
+            "@keyframes pulse-bg { from { background-color: white; } \
+            50%, 62.5% { background-color: red; width: 10px; } \
+            to { background-color: white; } }"
+        );
+    }
+
+    #[test]
+    fn test_layers() {
+        let src = r#"
+@layer base, widgets, app;
+
+/** utilities */
+@layer app {
+  .hidden {
+    display: none !important;
+  }
+
+  @media (max-width: 800px) {
+    .sidebar {
+      display: none;
+    }
+  }
+}
+
+body {
+  color: white !important;
+}
+"#;
+        println!("Checking '{src}'");
+        let stream: TokenStream = src.parse().unwrap();
+        let stylesheet: StyleSheet = syn::parse2(stream).unwrap();
+        println!("StyleSheet: {:?}", stylesheet);
+        assert_eq!(format!("{stylesheet:#}").as_str().trim(), src.trim());
+        assert_eq!(stylesheet.0[0].to_string(), "@layer base, widgets, app;");
+
+        let stream: TokenStream = "@layer a, b { .c { d: e; } }".parse().unwrap();
+        assert!(syn::parse2::<StyleSheet>(stream).is_err());
+        let stream: TokenStream = ".c { d: e !importante; }".parse().unwrap();
+        assert!(syn::parse2::<StyleSheet>(stream).is_err());
+    }
 }
@@ -8,7 +8,9 @@ pub(crate) struct CommonsPlugin;
 
 pub mod prelude {
     pub use super::BodyWidgetExtension;
+    pub use super::ColorWidgetExtension;
     pub use super::DivWidgetExtension;
+    pub use super::EmWidgetExtension;
     pub use super::LabelWidgetExtension;
     pub use super::ProgressbarWidgetExtension;
     pub use super::SpanWidgetExtension;
@@ -20,7 +22,9 @@ pub mod prelude {
 impl Plugin for CommonsPlugin {
     fn build(&self, app: &mut App) {
         app.register_widget::<BodyWidget>();
+        app.register_widget::<ColorWidget>();
         app.register_widget::<DivWidget>();
+        app.register_widget::<EmWidget>();
         app.register_widget::<LabelWidget>();
         // app.register_widget::<Label>();
         app.register_widget::<ProgressbarWidget>();
@@ -76,20 +80,51 @@ fn progressbar(ctx: &mut WidgetContext) {
 }
 
 #[widget]
+/// The `<span>` tag is an inline container for a part of a text. The text
+/// containing only the inline elements (`<span>`, `<strong>`, `<em>`,
+/// `<color>`) is rendered as a single text, so it wraps as a whole:
+/// ```html
+/// <div>"Press "<strong>"Enter"</strong>" to "<color value="red">"quit"</color></div>
+/// ```
 fn span(ctx: &mut WidgetContext) {
     let content = ctx.content();
-    ctx.insert(ElementBundle::default()).push_children(&content);
+    ctx.insert((ElementBundle::default(), DisplayElement::Inline))
+        .push_children(&content);
 }
 
 #[widget]
-#[styles( strong: { font: bold; })]
+#[styles( strong: { font-weight: bold; })]
 /// The `<strong>` tag highlights an important part of a text. It can be used
 /// for such important contents, as warnings. This can be one sentence that gives
 /// importance to the whole page, or it may be needed if you want to highlight
 /// some words that are of greater importance compared to the rest of the content.
 fn strong(ctx: &mut WidgetContext) {
     let content = ctx.content();
-    ctx.insert(ElementBundle::default()).push_children(&content);
+    ctx.insert((ElementBundle::default(), DisplayElement::Inline))
+        .push_children(&content);
+}
+
+#[widget]
+#[styles( em: { font-style: italic; })]
+/// The `<em>` tag emphasizes a part of a text, it is rendered in italic.
+fn em(ctx: &mut WidgetContext) {
+    let content = ctx.content();
+    ctx.insert((ElementBundle::default(), DisplayElement::Inline))
+        .push_children(&content);
+}
+
+#[widget]
+/// The `<color>` tag colors a part of a text with the `value` param:
+/// ```html
+/// <span>"Health: "<color value="#d04040">"low"</color></span>
+/// ```
+fn color(ctx: &mut WidgetContext) {
+    if let Some(value) = ctx.param("value".into()) {
+        ctx.style("color", value);
+    }
+    let content = ctx.content();
+    ctx.insert((ElementBundle::default(), DisplayElement::Inline))
+        .push_children(&content);
 }
 
 #[derive(Component, Default)]
@@ -262,6 +262,20 @@ pub enum BtnMode {
     Group(BtnModeGroup),
 }
 
+impl BtnMode {
+    /// The mode name without arguments, used as `mode` attribute
+    /// value of the `<button>`: `button[mode=toggle]`
+    pub fn name(&self) -> &'static str {
+        match self {
+            BtnMode::Press => "press",
+            BtnMode::Instant => "instant",
+            BtnMode::Toggle => "toggle",
+            BtnMode::Repeat(_) => "repeat",
+            BtnMode::Group(_) => "group",
+        }
+    }
+}
+
 impl TryFrom<&str> for BtnMode {
     type Error = String;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -619,6 +633,7 @@ fn handle_states_system(
 ) {
     drop_pressed.clear();
     for (entity, mut btn) in buttons.iter_mut() {
+        elements.set_attribute(entity, tags::mode(), btn.mode.name());
         match &btn.mode {
             // BtnMode::Instant => elements.set_state(entity, tags::pressed(), false),
             BtnMode::Press => elements.set_state(entity, tags::pressed(), false),
@@ -1,6 +1,5 @@
 use crate::common::*;
-use ab_glyph::ScaleFont;
-use belly_core::{build::*, input};
+use belly_core::{build::*, ess::char_advance, input};
 use belly_macro::*;
 use bevy::{input::keyboard::KeyboardInput, prelude::*};
 
@@ -202,12 +201,6 @@ pub struct TextInputCursor {
     state: f32,
 }
 
-fn get_char_advance(ch: char, font: &Font, font_size: f32) -> f32 {
-    let font = ab_glyph::Font::as_scaled(&font.font, font_size);
-    let glyph = font.glyph_id(ch);
-    font.h_advance(glyph)
-}
-
 fn process_keyboard_input(
     changed_elements: Query<(), Changed<Element>>,
     mut keyboard_input: EventReader<KeyboardInput>,
@@ -357,7 +350,7 @@ fn process_keyboard_input(
     };
     let font_size = text.sections[0].style.font_size;
     for (idx, ch) in chars.iter().enumerate() {
-        let advance = get_char_advance(*ch, font, font_size);
+        let advance = char_advance(*ch, font, font_size);
         text_width += advance;
         if idx < index {
             position_from_start += advance;
@@ -491,7 +484,7 @@ fn process_mouse(
             let mut word_end = 0;
             let mut whitespace = false;
             for (idx, ch) in input.value.chars().enumerate() {
-                let advance = get_char_advance(ch, font, font_size);
+                let advance = char_advance(ch, font, font_size);
                 if offset < pos && !idx_found {
                     index = idx;
                     if offset + advance * 0.5 < pos {
@@ -1,4 +1,5 @@
 use super::common::*;
+use crate::tags;
 use belly_core::{build::*, impl_properties};
 use belly_macro::*;
 use bevy::prelude::*;
@@ -258,6 +259,15 @@ impl From<LayoutMode> for Variant {
     }
 }
 
+impl LayoutMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LayoutMode::Vertical => "vertical",
+            LayoutMode::Horizontal => "horizontal",
+        }
+    }
+}
+
 impl FromStr for LayoutMode {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -316,6 +326,7 @@ pub fn configure_range_layout(
             continue;
         }
         configured_modes.insert(entity, mode);
+        elements.set_attribute(entity, tags::mode(), mode.as_str());
         {
             match mode {
                 LayoutMode::Horizontal => {
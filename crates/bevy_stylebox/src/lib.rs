@@ -6,7 +6,10 @@
 use bevy::{
     prelude::*,
     render::{Extract, RenderApp},
-    ui::{ExtractedUiNode, ExtractedUiNodes, FocusPolicy, RenderUiSystem, UiStack},
+    ui::{
+        DefaultUiCamera, ExtractedUiNode, ExtractedUiNodes, FocusPolicy, RenderUiSystem, UiStack,
+    },
+    window::PrimaryWindow,
 };
 
 /// `Stylebox` plugin for `bevy` engine. Dont forget to register it:
@@ -272,6 +275,11 @@ pub fn compute_stylebox_configuration(
 struct StyleboxSlice {
     transform: Mat4,
     region: Rect,
+    /// scale of the image region in uinode space
+    scale: Vec2,
+    /// corners of the uinode covered by the slice in
+    /// top left, top right, bottom right, bottom left order
+    corners: [bool; 4],
 }
 
 #[derive(Component, Default)]
@@ -357,9 +365,15 @@ pub fn compute_stylebox_slices(
                 let mut tr = Mat4::IDENTITY;
                 tr *= Mat4::from_translation(offset.extend(0.));
                 tr *= Mat4::from_scale(scale.extend(1.));
+                let left = uirect.min.x < EPSILON;
+                let right = uirect.max.x > size.x - EPSILON;
+                let top = uirect.min.y < EPSILON;
+                let bottom = uirect.max.y > size.y - EPSILON;
                 transforms.items.push(StyleboxSlice {
                     transform: tr,
                     region: imgrect,
+                    scale,
+                    corners: [left && top, right && top, right && bottom, left && bottom],
                 });
             }
         }
@@ -392,11 +406,16 @@ fn normalize_axis(left: f32, right: f32) -> (f32, f32) {
 }
 
 /// Extracts stylebox vertices into render pipeline based on `Stylebox.texture`,
-/// `Stylebox.modulate` and `StyleboxSlices`
+/// `Stylebox.modulate` and `StyleboxSlices`. The slices covering the corners
+/// of the node are rounded by the node `BorderRadius`.
+#[allow(clippy::too_many_arguments)]
 pub fn extract_stylebox(
+    mut commands: Commands,
     mut extracted_uinodes: ResMut<ExtractedUiNodes>,
     ui_stack: Extract<Res<UiStack>>,
     images: Extract<Res<Assets<Image>>>,
+    default_ui_camera: Extract<DefaultUiCamera>,
+    windows: Extract<Query<&Window, With<PrimaryWindow>>>,
     uinode_query: Extract<
         Query<(
             &Node,
@@ -405,11 +424,22 @@ pub fn extract_stylebox(
             &StyleboxSlices,
             &InheritedVisibility,
             Option<&CalculatedClip>,
+            Option<&TargetCamera>,
+            Option<&BorderRadius>,
         )>,
     >,
 ) {
+    let viewport = windows
+        .get_single()
+        .map(|window| window.size())
+        .unwrap_or(Vec2::ZERO);
     for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
-        let Ok((uinode, transform, stylebox, slices, visibility, clip)) = uinode_query.get(*entity)
+        let Ok((uinode, transform, stylebox, slices, visibility, clip, camera, border_radius)) =
+            uinode_query.get(*entity)
+        else {
+            continue;
+        };
+        let Some(camera_entity) = camera.map(TargetCamera::entity).or(default_ui_camera.get())
         else {
             continue;
         };
@@ -433,10 +463,24 @@ pub fn extract_stylebox(
         let tr = transform.compute_matrix();
         let img_size = img.size();
         let img_size = Vec2::new(img_size.x as f32, img_size.y as f32);
+        let radius = border_radius
+            .map(|radius| radius.resolve(uinode.size(), viewport))
+            .map(|r| [r.top_left, r.top_right, r.bottom_right, r.bottom_left])
+            .unwrap_or_default();
 
         for patch in slices.items.iter() {
+            // the radius is applied in the image region space of the slice
+            let max_radius = 0.5 * patch.region.size().min_element();
+            let border_radius: [f32; 4] = std::array::from_fn(|corner| {
+                if patch.corners[corner] {
+                    (radius[corner] / patch.scale.max_element()).min(max_radius)
+                } else {
+                    0.
+                }
+            });
+            // every slice is extracted as a separate node
             extracted_uinodes.uinodes.insert(
-                *entity,
+                commands.spawn_empty().id(),
                 ExtractedUiNode {
                     transform: tr * patch.transform,
                     color: stylebox.modulate.into(),
@@ -447,8 +491,8 @@ pub fn extract_stylebox(
                     stack_index: stack_index as u32,
                     flip_x: false,
                     flip_y: false,
-                    camera_entity: Entity::PLACEHOLDER,
-                    border_radius: [0.0,0.0,0.0,0.0],
+                    camera_entity,
+                    border_radius,
                     border: [0.0,0.0,0.0,0.0],
                     node_type: bevy::ui::NodeType::Rect,
                 },